@@ -0,0 +1,9 @@
+use std::{env, fs, path::Path};
+
+fn main() {
+    println!("cargo:rerun-if-changed=grammar.rparse");
+    let grammar = fs::read_to_string("grammar.rparse").unwrap();
+    let parser = rparse::format::load(&grammar).unwrap();
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("parser.rs");
+    fs::write(out, rparse::codegen::generate(&parser)).unwrap();
+}
@@ -0,0 +1,127 @@
+//! Parser generated by `rparse::codegen` from `grammar.rparse`
+//!
+//! The tests check that the generated parser returns the same trees and errors as the
+//! interpreter for the same grammar.
+
+pub mod parser {
+    include!(concat!(env!("OUT_DIR"), "/parser.rs"));
+}
+
+/// Interpreted parser of the same grammar
+pub fn interpreter() -> rparse::Parser {
+    rparse::format::load(include_str!("../grammar.rparse")).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rparse::parser::{Nodes, ParseError, ParseResult, VariableKind};
+
+    const VALID: &str = r#"let a = 1 + 2;
+let mut b: &&std::vec? = [1, [x, "s\"t"], f(1, g())] - 3;
+let c: T!;
+let d;
+"#;
+
+    const INVALID: [&str; 7] = [
+        "let = 1;",
+        "let a = [1, 2;",
+        "let a = f(1,);",
+        "let a: & = 1;",
+        "let a = 1 +;",
+        "let a: T(",
+        "a",
+    ];
+
+    /// Variables are kept in a hash map, so the tree is printed with them sorted
+    fn tree(result: &ParseResult, nodes: &Nodes, text: &str, out: &mut String) {
+        let node = match nodes {
+            Nodes::Node(node) => node,
+            Nodes::Token(token) => {
+                out.push_str(&format!(
+                    "{:?} {:?}",
+                    token.kind,
+                    &text[token.index..][..token.len]
+                ));
+                return;
+            }
+        };
+        out.push_str(&format!(
+            "{} {:?} {{",
+            node.name,
+            result.stringify_node(nodes, text)
+        ));
+        let mut names = node.variables.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            out.push_str(&format!(" {}: ", name));
+            match &node.variables[name] {
+                VariableKind::Node(Some(nodes)) => tree(result, nodes, text, out),
+                VariableKind::NodeList(list) => {
+                    out.push('[');
+                    for nodes in list {
+                        tree(result, nodes, text, out);
+                        out.push_str(", ");
+                    }
+                    out.push(']');
+                }
+                kind => out.push_str(&format!("{:?}", kind)),
+            }
+            out.push(';');
+        }
+        out.push_str(" }");
+    }
+
+    fn print(result: &Result<ParseResult, ParseError>, text: &str) -> String {
+        let result = match result {
+            Ok(result) => result,
+            Err(err) => return format!("{:?}", err),
+        };
+        let mut out = String::new();
+        tree(result, &Nodes::Node(result.entry.clone()), text, &mut out);
+        let mut globals = result.globals.iter().collect::<Vec<_>>();
+        globals.sort_by_key(|(name, _)| *name);
+        out.push_str(&format!(" {:?}", globals));
+        out
+    }
+
+    fn compare(text: &str) -> bool {
+        let interpreter = interpreter();
+        let generated = parser::Parser::new();
+        let tokens = match interpreter.lexer.lex_utf8(text) {
+            Ok(tokens) => tokens,
+            Err(_) => return false,
+        };
+        assert_eq!(
+            generated.lexer.lex_utf8(text).unwrap(),
+            tokens,
+            "{:?}",
+            text
+        );
+        let expected = interpreter.parse(&tokens, text);
+        let result = generated.parse(&tokens, text);
+        assert_eq!(print(&result, text), print(&expected, text), "{:?}", text);
+        expected.is_ok()
+    }
+
+    #[test]
+    fn valid() {
+        assert!(compare(VALID));
+        assert!(compare(""));
+    }
+
+    #[test]
+    fn invalid() {
+        for text in INVALID {
+            assert!(!compare(text), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn prefixes() {
+        let ends = VALID.char_indices().map(|(i, _)| i).chain([VALID.len()]);
+        let valid = ends.filter(|end| compare(&VALID[..*end])).count();
+        // the empty text and every statement with and without the new line after it
+        assert_eq!(valid, 9);
+    }
+}
@@ -0,0 +1,193 @@
+//! Grammars checked at compile time
+//!
+//! `grammar!` loads a grammar written in the textual format (see `rparse::format`),
+//! validates it while the crate is compiled and expands to an expression that builds
+//! the `rparse::Parser`. Grammar bugs become compiler errors instead of `NodeNotFound`
+//! or `VariableNotFound` at runtime.
+//!
+//! ```ignore
+//! let parser: rparse::Parser = rparse_macros::grammar!(r#"
+//!     tokens "=";
+//!     entry assign;
+//!
+//!     node assign {
+//!         var name: node;
+//!
+//!         is #text [set(name)];
+//!         is "=";
+//!     }
+//! "#);
+//!
+//! // path relative to the crate root
+//! let parser = rparse_macros::grammar!(file = "grammar.rparse");
+//! ```
+//!
+//! The crate using the macro has to depend on `rparse`. The macro can not be reexported
+//! by `rparse` because it uses `rparse` to load the grammar.
+//!
+//! Grammars with `preprocessor` statements can not be loaded without a registry, add the
+//! preprocessors to the lexer of the returned parser instead.
+use std::path::Path;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitStr, Token,
+};
+
+/// Validates a grammar at compile time and expands to the `rparse::Parser`
+///
+/// Takes either a string with the grammar or `file = "path"` relative to the crate root
+#[proc_macro]
+pub fn grammar(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input.into()).into()
+}
+
+enum Input {
+    Inline(LitStr),
+    File(LitStr),
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            return Ok(Input::Inline(input.parse()?));
+        }
+        let key: Ident = input.parse()?;
+        if key != "file" {
+            return Err(syn::Error::new(
+                key.span(),
+                "expected a grammar string or `file = \"path\"`",
+            ));
+        }
+        input.parse::<Token![=]>()?;
+        Ok(Input::File(input.parse()?))
+    }
+}
+
+fn expand(input: TokenStream) -> TokenStream {
+    let input = match syn::parse2::<Input>(input) {
+        Ok(input) => input,
+        Err(err) => return err.to_compile_error(),
+    };
+    let (source, span, text) = match input {
+        Input::Inline(lit) => (lit.value(), lit.span(), quote!(#lit)),
+        Input::File(lit) => {
+            let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+            let path = Path::new(&root).join(lit.value());
+            let source = match std::fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(err) => {
+                    let message = format!("could not read {}: {}", path.display(), err);
+                    return syn::Error::new(lit.span(), message).to_compile_error();
+                }
+            };
+            // include_str makes cargo rebuild the crate when the grammar changes
+            let path = path.to_string_lossy();
+            (source, lit.span(), quote!(include_str!(#path)))
+        }
+    };
+    if let Err(messages) = check(&source) {
+        let mut errors = messages
+            .into_iter()
+            .map(|message| syn::Error::new(span, message));
+        let mut error = errors.next().unwrap();
+        for err in errors {
+            error.combine(err);
+        }
+        return error.to_compile_error();
+    }
+    quote! {
+        ::rparse::format::load(#text).expect("grammar was validated at compile time")
+    }
+}
+
+/// Loads and validates the grammar, returns the messages for the compiler
+fn check(source: &str) -> Result<(), Vec<String>> {
+    let parser = match rparse::format::load(source) {
+        Ok(parser) => parser,
+        Err(err) => return Err(vec![format!("invalid grammar: {:?}", err)]),
+    };
+    let mut messages = Vec::new();
+    if !parser.grammar.nodes.contains_key(&parser.parser.entry) {
+        messages.push(format!("entry node not found: {}", parser.parser.entry));
+    }
+    for err in parser.grammar.validate(&parser.lexer).errors {
+        let message = match node_line(source, &err.node_name) {
+            Some(line) => format!(
+                "{:?} in node {} (line {} of the grammar)",
+                err.kind, err.node_name, line
+            ),
+            None => format!("{:?} in {}", err.kind, err.node_name),
+        };
+        messages.push(message);
+    }
+    match messages.is_empty() {
+        true => Ok(()),
+        false => Err(messages),
+    }
+}
+
+/// Finds the line where the node is defined
+///
+/// Span of the string can not be narrowed down on stable, so the line is part of the message
+fn node_line(source: &str, name: &str) -> Option<usize> {
+    let plain = format!("node {}", name);
+    let quoted = format!("node `{}`", name);
+    source
+        .lines()
+        .position(|line| {
+            let line = line.trim_start();
+            [&plain, &quoted].iter().any(|head| {
+                line.strip_prefix(head.as_str())
+                    .is_some_and(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '{'))
+            })
+        })
+        .map(|line| line + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_grammar() {
+        let valid = expand(quote! {
+            r#"
+            tokens "=";
+            entry assign;
+
+            node assign {
+                var name: node;
+
+                is #text [set(name)];
+                is "=";
+            }
+            "#
+        });
+        let valid = valid.to_string();
+        assert!(valid.contains("rparse :: format :: load"), "{}", valid);
+
+        let invalid = expand(quote! {
+            r#"
+            tokens "=";
+            entry assign;
+
+            node assign {
+                is #text [set(name)];
+                is value;
+            }
+            "#
+        });
+        let invalid = invalid.to_string();
+        assert!(invalid.contains("compile_error"), "{}", invalid);
+        assert!(invalid.contains("VariableNotFound"), "{}", invalid);
+        assert!(invalid.contains("NodeNotFound"), "{}", invalid);
+        assert!(
+            invalid.contains("in node assign (line 5 of the grammar)"),
+            "{}",
+            invalid
+        );
+    }
+}
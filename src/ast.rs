@@ -0,0 +1,595 @@
+//! Generates typed AST structs from the variables of the nodes
+//!
+//! `generate` emits one struct per node with a field for every variable:
+//!
+//! - `node` - `Option<Child>`
+//! - `list` - `Vec<Child>`
+//! - `bool` - `bool`
+//! - `number` - `i32`
+//!
+//! The type of the child is inferred from the tokens that are `set` into the variable.
+//! A variable that only holds one node gets that node's struct (boxed, nodes can be
+//! recursive), only tokens give `Token` and anything else gets an enum with a variant
+//! for every node and `Token`. Variables that are never set keep the untyped `Nodes`.
+//!
+//! Every struct gets `from_node` which converts the `parser::Node` returned by the
+//! parser, globals are in `Globals::from_variables`.
+//!
+//! ```ignore
+//! // build.rs
+//! std::fs::write(out, rparse::ast::generate(&parser.grammar)).unwrap();
+//!
+//! // src/lib.rs
+//! mod ast {
+//!     include!(concat!(env!("OUT_DIR"), "/ast.rs"));
+//! }
+//!
+//! let result = parser.parse(&tokens, text)?;
+//! let entry = ast::Entry::from_node(&result.entry)?;
+//! ```
+use crate::{
+    grammar::{Grammar, MatchToken, Parameters, Rule, VariableKind},
+    lexer::Token,
+    parser::{self, Nodes},
+    Map,
+};
+
+use core::fmt::{self, Write};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::format;
+        use alloc::vec;
+    }
+}
+
+/// Generates the source of the structs for the nodes of the grammar
+pub fn generate(grammar: &Grammar) -> String {
+    let mut generator = Generator::new(grammar);
+    let mut names: Vec<&String> = grammar.nodes.keys().collect();
+    names.sort();
+
+    let mut out = String::new();
+    out.push_str("// Generated by rparse, do not edit\n");
+    for name in &names {
+        let node = &grammar.nodes[*name];
+        let mut sources = Map::new();
+        for rule in &node.rules {
+            generator.sources(rule, &mut sources, false);
+        }
+        let type_name = generator.types[name.as_str()].clone();
+        generator.structure(&type_name, name, &node.variables, &sources, &mut out);
+    }
+    let mut sources = Map::new();
+    for name in &names {
+        for rule in &grammar.nodes[*name].rules {
+            generator.sources(rule, &mut sources, true);
+        }
+    }
+    generator.globals(&sources, &mut out);
+    for enumeration in &generator.enums {
+        out.push_str(enumeration);
+    }
+    out
+}
+
+/// What can end up in a variable
+#[derive(Clone, PartialEq)]
+enum Source {
+    Token,
+    Node(String),
+}
+
+/// Sources of the variables of a node
+type Sources = Map<String, Vec<Source>>;
+
+struct Generator<'a> {
+    grammar: &'a Grammar,
+    /// Rust names of the nodes
+    types: Map<&'a str, String>,
+    /// Every name that is taken
+    used: Vec<String>,
+    /// Enums for the variables that hold more kinds of children
+    enums: Vec<String>,
+}
+
+impl<'a> Generator<'a> {
+    fn new(grammar: &'a Grammar) -> Generator<'a> {
+        let mut generator = Generator {
+            grammar,
+            types: Map::new(),
+            used: vec!["Globals".to_string()],
+            enums: Vec::new(),
+        };
+        let mut names: Vec<&String> = grammar.nodes.keys().collect();
+        names.sort();
+        for name in names {
+            let type_name = generator.unique(type_name(name));
+            generator.types.insert(name, type_name);
+        }
+        generator
+    }
+
+    /// Adds a number to the name if it is taken
+    fn unique(&mut self, name: String) -> String {
+        unique(&mut self.used, name)
+    }
+
+    /// Collects what is set into the variables, `globals` collects the globals instead
+    fn sources(&self, rule: &Rule, sources: &mut Sources, globals: bool) {
+        match rule {
+            Rule::Is {
+                token,
+                rules,
+                parameters,
+            }
+            | Rule::While {
+                token,
+                rules,
+                parameters,
+            } => {
+                self.matched(token, parameters, sources, globals);
+                self.all_sources(rules, sources, globals);
+            }
+            Rule::Until {
+                token: _,
+                rules,
+                parameters,
+            } => {
+                self.matched(&MatchToken::Any, parameters, sources, globals);
+                self.all_sources(rules, sources, globals);
+            }
+            Rule::Isnt { rules, .. } | Rule::Loop { rules } => {
+                self.all_sources(rules, sources, globals)
+            }
+            Rule::Maybe {
+                token,
+                is,
+                isnt,
+                parameters,
+            } => {
+                self.matched(token, parameters, sources, globals);
+                self.all_sources(is, sources, globals);
+                self.all_sources(isnt, sources, globals);
+            }
+            Rule::IsOneOf { tokens } | Rule::UntilOneOf { tokens } => {
+                for option in tokens {
+                    self.matched(&option.token, &option.parameters, sources, globals);
+                    self.all_sources(&option.rules, sources, globals);
+                }
+            }
+            Rule::MaybeOneOf { is_one_of, isnt } => {
+                for option in is_one_of {
+                    self.matched(&option.token, &option.parameters, sources, globals);
+                    self.all_sources(&option.rules, sources, globals);
+                }
+                self.all_sources(isnt, sources, globals);
+            }
+            Rule::Command {
+                command: crate::grammar::Commands::Compare { rules, .. },
+            } => self.all_sources(rules, sources, globals),
+            Rule::Command { .. } | Rule::Debug { .. } => (),
+        }
+    }
+
+    fn all_sources(&self, rules: &[Rule], sources: &mut Sources, globals: bool) {
+        for rule in rules {
+            self.sources(rule, sources, globals);
+        }
+    }
+
+    fn matched(
+        &self,
+        token: &MatchToken,
+        parameters: &[Parameters],
+        sources: &mut Sources,
+        globals: bool,
+    ) {
+        for parameter in parameters {
+            let variable = match (parameter, globals) {
+                (Parameters::Set(variable), false) | (Parameters::Global(variable), true) => {
+                    variable
+                }
+                _ => continue,
+            };
+            let found = sources.entry(variable.clone()).or_default();
+            self.token_sources(token, found, &mut Vec::new());
+        }
+    }
+
+    fn token_sources<'b>(
+        &'b self,
+        token: &'b MatchToken,
+        found: &mut Vec<Source>,
+        enumerators: &mut Vec<&'b str>,
+    ) {
+        let source = match token {
+            MatchToken::Node(name) => Source::Node(name.clone()),
+            MatchToken::Template(template, arguments) => {
+                Source::Node(crate::grammar::instance_name(template, arguments))
+            }
+            MatchToken::Enumerator(name) => {
+                // enumerators can contain each other
+                if enumerators.contains(&name.as_str()) {
+                    return;
+                }
+                enumerators.push(name);
+                if let Some(enumerator) = self.grammar.enumerators.get(name) {
+                    for value in &enumerator.values {
+                        self.token_sources(value, found, enumerators);
+                    }
+                }
+                return;
+            }
+            MatchToken::Token(_) | MatchToken::Word(_) | MatchToken::Any => Source::Token,
+        };
+        // nodes that are not in the grammar can not be converted
+        if let Source::Node(name) = &source {
+            if !self.types.contains_key(name.as_str()) {
+                return;
+            }
+        }
+        if !found.contains(&source) {
+            found.push(source);
+        }
+    }
+
+    /// Type of a child, creates an enum if needed
+    fn child(&mut self, owner: &str, variable: &str, sources: Option<&Vec<Source>>) -> Child {
+        let sources = match sources {
+            Some(sources) if !sources.is_empty() => sources,
+            _ => return Child::Nodes,
+        };
+        match sources.as_slice() {
+            [Source::Token] => return Child::Token,
+            [Source::Node(name)] => return Child::Node(self.types[name.as_str()].clone()),
+            _ => (),
+        }
+        let name = self.unique(format!("{}{}", owner, type_name(variable)));
+        let mut out = String::new();
+        out.push_str("\n#[derive(Debug, Clone)]\n#[allow(dead_code)]\n");
+        let _ = writeln!(out, "pub enum {} {{", name);
+        for source in sources {
+            match source {
+                Source::Token => out.push_str("    Token(::rparse::lexer::Token),\n"),
+                Source::Node(node) => {
+                    let _ = writeln!(out, "    {0}(Box<{0}>),", self.types[node.as_str()]);
+                }
+            }
+        }
+        out.push_str("}\n\n");
+        let _ = writeln!(out, "impl {} {{", name);
+        out.push_str("    pub fn from_nodes(value: &::rparse::parser::Nodes) -> Result<Self, ::rparse::ast::AstError> {\n");
+        out.push_str("        match value {\n");
+        match sources.contains(&Source::Token) {
+            true => out.push_str(
+                "            ::rparse::parser::Nodes::Token(token) => Ok(Self::Token(token.clone())),\n",
+            ),
+            false => out.push_str(
+                "            ::rparse::parser::Nodes::Token(token) => Err(::rparse::ast::AstError::ExpectedNode(token.clone())),\n",
+            ),
+        }
+        out.push_str(
+            "            ::rparse::parser::Nodes::Node(node) => match node.name.as_str() {\n",
+        );
+        for source in sources {
+            if let Source::Node(node) = source {
+                let _ = writeln!(
+                    out,
+                    "                {:?} => Ok(Self::{1}(Box::new({1}::from_node(node)?))),",
+                    node,
+                    self.types[node.as_str()]
+                );
+            }
+        }
+        out.push_str("                _ => Err(::rparse::ast::AstError::UnexpectedNode(node.name.clone())),\n");
+        out.push_str("            },\n        }\n    }\n}\n");
+        self.enums.push(out);
+        Child::Enum(name)
+    }
+
+    fn fields(
+        &mut self,
+        owner: &str,
+        variables: &Map<String, VariableKind>,
+        sources: &Sources,
+    ) -> Vec<(String, String, String)> {
+        let mut names: Vec<&String> = variables.keys().collect();
+        names.sort();
+        let mut fields = Vec::new();
+        // variables like `a-b` and `a_b` end up with the same field name
+        let mut used = Vec::new();
+        for variable in names {
+            let (ty, value) = match variables[variable] {
+                VariableKind::Node => {
+                    let child = self.child(owner, variable, sources.get(variable));
+                    (
+                        format!("Option<{}>", child.ty()),
+                        format!(
+                            "::rparse::ast::single(variables, name, {:?})?.map(|value| -> Result<_, ::rparse::ast::AstError> {{ Ok({}) }}).transpose()?",
+                            variable,
+                            child.convert()
+                        ),
+                    )
+                }
+                VariableKind::NodeList => {
+                    let child = self.child(owner, variable, sources.get(variable));
+                    (
+                        format!("Vec<{}>", child.ty()),
+                        format!(
+                            "::rparse::ast::list(variables, name, {:?})?.iter().map(|value| -> Result<_, ::rparse::ast::AstError> {{ Ok({}) }}).collect::<Result<_, _>>()?",
+                            variable,
+                            child.convert()
+                        ),
+                    )
+                }
+                VariableKind::Boolean => (
+                    "bool".to_string(),
+                    format!("::rparse::ast::boolean(variables, name, {:?})?", variable),
+                ),
+                VariableKind::Number => (
+                    "i32".to_string(),
+                    format!("::rparse::ast::number(variables, name, {:?})?", variable),
+                ),
+            };
+            fields.push((unique(&mut used, field_name(variable)), ty, value));
+        }
+        fields
+    }
+
+    fn structure(
+        &mut self,
+        type_name: &str,
+        node: &str,
+        variables: &Map<String, VariableKind>,
+        sources: &Sources,
+        out: &mut String,
+    ) {
+        let fields = self.fields(type_name, variables, sources);
+        let _ = writeln!(out, "\n/// node `{}`", node);
+        out.push_str("#[derive(Debug, Clone)]\n#[allow(dead_code)]\n");
+        let _ = writeln!(out, "pub struct {} {{", type_name);
+        for (field, ty, _) in &fields {
+            let _ = writeln!(out, "    pub {}: {},", field, ty);
+        }
+        out.push_str("}\n\n");
+        let _ = writeln!(out, "impl {} {{", type_name);
+        out.push_str("    pub fn from_node(node: &::rparse::parser::Node) -> Result<Self, ::rparse::ast::AstError> {\n");
+        let _ = writeln!(
+            out,
+            "        ::rparse::ast::expect_node(node, {:?})?;",
+            node
+        );
+        out.push_str("        let name = &node.name;\n");
+        out.push_str("        let variables = &node.variables;\n");
+        constructor(&fields, out);
+        out.push_str("    }\n}\n");
+    }
+
+    fn globals(&mut self, sources: &Sources, out: &mut String) {
+        let fields = self.fields("Globals", &self.grammar.globals, sources);
+        out.push_str("\n/// global variables\n#[derive(Debug, Clone)]\n#[allow(dead_code)]\n");
+        out.push_str("pub struct Globals {\n");
+        for (field, ty, _) in &fields {
+            let _ = writeln!(out, "    pub {}: {},", field, ty);
+        }
+        out.push_str("}\n\nimpl Globals {\n");
+        out.push_str("    pub fn from_variables(variables: &::rparse::Map<String, ::rparse::parser::VariableKind>) -> Result<Self, ::rparse::ast::AstError> {\n");
+        out.push_str("        let name = \"globals\";\n");
+        constructor(&fields, out);
+        out.push_str("    }\n}\n");
+    }
+}
+
+fn constructor(fields: &[(String, String, String)], out: &mut String) {
+    out.push_str("        Ok(Self {\n");
+    for (field, _, value) in fields {
+        let _ = writeln!(out, "            {}: {},", field, value);
+    }
+    out.push_str("        })\n");
+}
+
+/// Type of a child in the generated code
+enum Child {
+    Token,
+    Node(String),
+    Enum(String),
+    Nodes,
+}
+
+impl Child {
+    fn ty(&self) -> String {
+        match self {
+            Child::Token => "::rparse::lexer::Token".to_string(),
+            Child::Node(name) => format!("Box<{}>", name),
+            Child::Enum(name) => name.clone(),
+            Child::Nodes => "::rparse::parser::Nodes".to_string(),
+        }
+    }
+
+    /// Expression converting `value: &Nodes`
+    fn convert(&self) -> String {
+        match self {
+            Child::Token => "::rparse::ast::token(value)?".to_string(),
+            Child::Node(name) => format!(
+                "Box::new({}::from_node(::rparse::ast::node(value)?)?)",
+                name
+            ),
+            Child::Enum(name) => format!("{}::from_nodes(value)?", name),
+            Child::Nodes => "value.clone()".to_string(),
+        }
+    }
+}
+
+/// `value_refs` -> `ValueRefs`, `list<expr, ",">` -> `ListExpr`
+fn type_name(name: &str) -> String {
+    let mut result = String::new();
+    for part in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            result.push(first.to_ascii_uppercase());
+            result.push_str(chars.as_str());
+        }
+    }
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, 'N');
+    }
+    result
+}
+
+const KEYWORDS: [&str; 48] = [
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// Adds a number to the name if it is in `used`
+fn unique(used: &mut Vec<String>, name: String) -> String {
+    let mut unique = name.clone();
+    let mut i = 2;
+    while used.contains(&unique) {
+        unique = format!("{}{}", name, i);
+        i += 1;
+    }
+    used.push(unique.clone());
+    unique
+}
+
+/// Variable name as a field, keywords are raw identifiers
+fn field_name(name: &str) -> String {
+    let mut result: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    if result.is_empty() || result.starts_with(|c: char| c.is_ascii_digit()) {
+        result.insert(0, '_');
+    }
+    match result.as_str() {
+        "self" | "Self" | "super" | "crate" | "_" => result.push('_'),
+        word if KEYWORDS.contains(&word) => result.insert_str(0, "r#"),
+        _ => (),
+    }
+    result
+}
+
+/// Checks the name of the node that is converted
+pub fn expect_node(node: &parser::Node, name: &str) -> Result<(), AstError> {
+    match node.name == name {
+        true => Ok(()),
+        false => Err(AstError::WrongNode {
+            expected: name.to_string(),
+            found: node.name.clone(),
+        }),
+    }
+}
+
+pub fn single<'a>(
+    variables: &'a Map<String, parser::VariableKind>,
+    node: &str,
+    variable: &str,
+) -> Result<Option<&'a Nodes>, AstError> {
+    match variables.get(variable) {
+        Some(parser::VariableKind::Node(value)) => Ok(value.as_ref()),
+        _ => Err(AstError::Variable {
+            node: node.to_string(),
+            variable: variable.to_string(),
+        }),
+    }
+}
+
+pub fn list<'a>(
+    variables: &'a Map<String, parser::VariableKind>,
+    node: &str,
+    variable: &str,
+) -> Result<&'a [Nodes], AstError> {
+    match variables.get(variable) {
+        Some(parser::VariableKind::NodeList(values)) => Ok(values),
+        _ => Err(AstError::Variable {
+            node: node.to_string(),
+            variable: variable.to_string(),
+        }),
+    }
+}
+
+pub fn boolean(
+    variables: &Map<String, parser::VariableKind>,
+    node: &str,
+    variable: &str,
+) -> Result<bool, AstError> {
+    match variables.get(variable) {
+        Some(parser::VariableKind::Boolean(value)) => Ok(*value),
+        _ => Err(AstError::Variable {
+            node: node.to_string(),
+            variable: variable.to_string(),
+        }),
+    }
+}
+
+pub fn number(
+    variables: &Map<String, parser::VariableKind>,
+    node: &str,
+    variable: &str,
+) -> Result<i32, AstError> {
+    match variables.get(variable) {
+        Some(parser::VariableKind::Number(value)) => Ok(*value),
+        _ => Err(AstError::Variable {
+            node: node.to_string(),
+            variable: variable.to_string(),
+        }),
+    }
+}
+
+pub fn token(value: &Nodes) -> Result<Token, AstError> {
+    match value {
+        Nodes::Token(token) => Ok(token.clone()),
+        Nodes::Node(node) => Err(AstError::UnexpectedNode(node.name.clone())),
+    }
+}
+
+pub fn node(value: &Nodes) -> Result<&parser::Node, AstError> {
+    match value {
+        Nodes::Node(node) => Ok(node),
+        Nodes::Token(token) => Err(AstError::ExpectedNode(token.clone())),
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum AstError {
+    /// The node has a different name than the struct
+    WrongNode { expected: String, found: String },
+    /// The variable is missing or has a different kind
+    Variable { node: String, variable: String },
+    /// The node can not be stored in the variable
+    UnexpectedNode(String),
+    /// Expected a node, found a token
+    ExpectedNode(Token),
+}
+
+impl fmt::Debug for AstError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AstError::WrongNode { expected, found } => {
+                write!(f, "Expected node {}, found {}", expected, found)
+            }
+            AstError::Variable { node, variable } => {
+                write!(f, "Variable {} of {} has a different kind", variable, node)
+            }
+            AstError::UnexpectedNode(name) => write!(f, "Unexpected node {}", name),
+            AstError::ExpectedNode(token) => {
+                write!(f, "Expected a node, found {:?}", token.kind)
+            }
+        }
+    }
+}
@@ -0,0 +1,680 @@
+//! Fluent builder for grammars
+//!
+//! Building `grammar::Rule` trees by hand is very verbose. The builder produces the same
+//! `Grammar` with a lot less typing:
+//!
+//! ```
+//! use rparse::builder::*;
+//!
+//! let kw_let = node("KWLet")
+//!     .word("let")
+//!     .hard()
+//!     .text()
+//!     .set("ident")
+//!     .maybe(tok(":"), |b| b.node("type").set("type"))
+//!     .maybe(tok("="), |b| b.node("expression").set("value"))
+//!     .tok(";");
+//!
+//! let grammar = grammar().node(kw_let).build().unwrap();
+//! assert!(grammar.nodes["KWLet"].variables.contains_key("type"));
+//! ```
+//!
+//! Parameters (`set`, `hard`, `increment`, ...) are added to the last rule that matches a token.
+//! Variables and globals are declared from the parameters that use them, using the same
+//! variable with different kinds is reported when the grammar is built.
+use crate::{
+    grammar::{
+        Commands, Comparison, Enumerator, Grammar, MatchToken, Node, OneOf, Parameters, Rule,
+        Rules, Template, TemplateError, VariableKind,
+    },
+    lexer::{ControlTokenKind, TokenKinds},
+    Map,
+};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+        use std::fmt;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::vec;
+        use core::fmt;
+    }
+}
+
+/// Starts building a node
+pub fn node(name: &str) -> Builder {
+    Builder {
+        name: name.to_string(),
+        ..Builder::default()
+    }
+}
+
+/// Starts building a grammar
+pub fn grammar() -> GrammarBuilder {
+    GrammarBuilder {
+        grammar: Grammar::new(),
+        globals: Vec::new(),
+        errors: Vec::new(),
+    }
+}
+
+/// Starts building the options of a one of rule
+pub fn options() -> Options {
+    Options::default()
+}
+
+/// Token defined in the lexer
+pub fn tok(token: &str) -> MatchToken {
+    MatchToken::Token(TokenKinds::Token(token.to_string()))
+}
+
+/// Constant word
+pub fn word(word: &str) -> MatchToken {
+    MatchToken::Word(word.to_string())
+}
+
+/// Any text
+pub fn text() -> MatchToken {
+    MatchToken::Token(TokenKinds::Text)
+}
+
+pub fn whitespace() -> MatchToken {
+    MatchToken::Token(TokenKinds::Whitespace)
+}
+
+pub fn eol() -> MatchToken {
+    MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eol))
+}
+
+pub fn eof() -> MatchToken {
+    MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eof))
+}
+
+pub fn indent() -> MatchToken {
+    MatchToken::Token(TokenKinds::Control(ControlTokenKind::Indent))
+}
+
+pub fn dedent() -> MatchToken {
+    MatchToken::Token(TokenKinds::Control(ControlTokenKind::Dedent))
+}
+
+/// Token generated by a preprocessor
+pub fn complex(name: &str) -> MatchToken {
+    MatchToken::Token(TokenKinds::Complex(name.to_string()))
+}
+
+pub fn enumerator(name: &str) -> MatchToken {
+    MatchToken::Enumerator(name.to_string())
+}
+
+/// Another node
+pub fn node_ref(name: &str) -> MatchToken {
+    MatchToken::Node(name.to_string())
+}
+
+/// Template instantiated with arguments
+pub fn template(name: &str, arguments: Vec<MatchToken>) -> MatchToken {
+    MatchToken::Template(name.to_string(), arguments)
+}
+
+/// Builds the rules of a node (or of a block inside of a rule)
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    name: String,
+    rules: Rules,
+    /// Variables in order of use
+    variables: Vec<(String, VariableKind)>,
+    globals: Vec<(String, VariableKind)>,
+    errors: Vec<BuildErrors>,
+}
+
+impl Builder {
+    /// Declares a variable
+    ///
+    /// This is only needed for variables that are not used by any parameter
+    pub fn var(mut self, name: &str, kind: VariableKind) -> Self {
+        self.variables.push((name.to_string(), kind));
+        self
+    }
+
+    pub fn is(mut self, token: MatchToken) -> Self {
+        self.rules.push(Rule::Is {
+            token,
+            rules: Vec::new(),
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    /// Matches a token and executes the rules of the block
+    pub fn is_then(mut self, token: MatchToken, block: impl FnOnce(Builder) -> Builder) -> Self {
+        let rules = self.block(block);
+        self.rules.push(Rule::Is {
+            token,
+            rules,
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    pub fn tok(self, token: &str) -> Self {
+        self.is(tok(token))
+    }
+
+    pub fn word(self, word: &str) -> Self {
+        self.is(self::word(word))
+    }
+
+    pub fn text(self) -> Self {
+        self.is(text())
+    }
+
+    pub fn node(self, name: &str) -> Self {
+        self.is(node_ref(name))
+    }
+
+    /// Ends the node with an error if the token is matched
+    pub fn isnt(mut self, token: MatchToken, block: impl FnOnce(Builder) -> Builder) -> Self {
+        let rules = self.block(block);
+        self.rules.push(Rule::Isnt {
+            token,
+            rules,
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    pub fn maybe(self, token: MatchToken, block: impl FnOnce(Builder) -> Builder) -> Self {
+        self.maybe_else(token, block, |b| b)
+    }
+
+    pub fn maybe_else(
+        mut self,
+        token: MatchToken,
+        is: impl FnOnce(Builder) -> Builder,
+        isnt: impl FnOnce(Builder) -> Builder,
+    ) -> Self {
+        let is = self.block(is);
+        let isnt = self.block(isnt);
+        self.rules.push(Rule::Maybe {
+            token,
+            is,
+            isnt,
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    /// Matches the token again and again (`Rule::While`)
+    pub fn repeat(mut self, token: MatchToken, block: impl FnOnce(Builder) -> Builder) -> Self {
+        let rules = self.block(block);
+        self.rules.push(Rule::While {
+            token,
+            rules,
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    /// Loops until a break (`Rule::Loop`)
+    pub fn looping(mut self, block: impl FnOnce(Builder) -> Builder) -> Self {
+        let rules = self.block(block);
+        self.rules.push(Rule::Loop { rules });
+        self
+    }
+
+    pub fn until(mut self, token: MatchToken, block: impl FnOnce(Builder) -> Builder) -> Self {
+        let rules = self.block(block);
+        self.rules.push(Rule::Until {
+            token,
+            rules,
+            parameters: Vec::new(),
+        });
+        self
+    }
+
+    pub fn one_of(mut self, options: Options) -> Self {
+        let tokens = self.options(options);
+        self.rules.push(Rule::IsOneOf { tokens });
+        self
+    }
+
+    pub fn maybe_one_of(mut self, options: Options, isnt: impl FnOnce(Builder) -> Builder) -> Self {
+        let is_one_of = self.options(options);
+        let isnt = self.block(isnt);
+        self.rules.push(Rule::MaybeOneOf { is_one_of, isnt });
+        self
+    }
+
+    pub fn until_one_of(mut self, options: Options) -> Self {
+        let tokens = self.options(options);
+        self.rules.push(Rule::UntilOneOf { tokens });
+        self
+    }
+
+    /// Executes the rules if the comparison is true
+    pub fn compare(
+        mut self,
+        left: &str,
+        comparison: Comparison,
+        right: &str,
+        block: impl FnOnce(Builder) -> Builder,
+    ) -> Self {
+        let rules = self.block(block);
+        self.command(Commands::Compare {
+            left: left.to_string(),
+            right: right.to_string(),
+            comparison,
+            rules,
+        })
+    }
+
+    pub fn error(self, message: &str) -> Self {
+        self.command(Commands::Error {
+            message: message.to_string(),
+        })
+    }
+
+    pub fn hard_error(self, set: bool) -> Self {
+        self.command(Commands::HardError { set })
+    }
+
+    pub fn goto(self, label: &str) -> Self {
+        self.command(Commands::Goto {
+            label: label.to_string(),
+        })
+    }
+
+    pub fn label(self, name: &str) -> Self {
+        self.command(Commands::Label {
+            name: name.to_string(),
+        })
+    }
+
+    pub fn print(self, message: &str) -> Self {
+        self.command(Commands::Print {
+            message: message.to_string(),
+        })
+    }
+
+    pub fn command(mut self, command: Commands) -> Self {
+        self.rules.push(Rule::Command { command });
+        self
+    }
+
+    /// Builds the node
+    pub fn build(self) -> Result<Node, Vec<BuildError>> {
+        let mut errors = Vec::new();
+        let node = self.finish(&mut errors);
+        match errors.is_empty() {
+            true => Ok(node),
+            false => Err(errors),
+        }
+    }
+
+    fn finish(self, errors: &mut Vec<BuildError>) -> Node {
+        let name = self.name;
+        let mut error = |kind| {
+            errors.push(BuildError {
+                kind,
+                node_name: name.clone(),
+            })
+        };
+        for kind in self.errors {
+            error(kind);
+        }
+        let variables = declare(&self.variables, &mut error);
+        Node {
+            name,
+            rules: self.rules,
+            variables,
+        }
+    }
+
+    /// Builds a block and takes over its variables
+    fn block(&mut self, block: impl FnOnce(Builder) -> Builder) -> Rules {
+        let block = block(Builder::default());
+        self.variables.extend(block.variables);
+        self.globals.extend(block.globals);
+        self.errors.extend(block.errors);
+        block.rules
+    }
+
+    fn options(&mut self, options: Options) -> Vec<OneOf> {
+        self.variables.extend(options.variables);
+        self.globals.extend(options.globals);
+        self.errors.extend(options.errors);
+        options.options
+    }
+}
+
+impl Parameterize for Builder {
+    fn parameter(mut self, parameter: Parameters) -> Self {
+        match self.rules.last_mut() {
+            Some(
+                Rule::Is { parameters, .. }
+                | Rule::Isnt { parameters, .. }
+                | Rule::Maybe { parameters, .. }
+                | Rule::While { parameters, .. }
+                | Rule::Until { parameters, .. },
+            ) => parameters.push(parameter),
+            _ => self.errors.push(BuildErrors::NoToken(parameter)),
+        }
+        self
+    }
+
+    fn use_variable(mut self, name: &str, kind: VariableKind) -> Self {
+        self.variables.push((name.to_string(), kind));
+        self
+    }
+
+    fn use_global(mut self, name: &str, kind: VariableKind) -> Self {
+        self.globals.push((name.to_string(), kind));
+        self
+    }
+}
+
+/// Builds the options of `one_of`, `maybe_one_of` and `until_one_of`
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    options: Vec<OneOf>,
+    variables: Vec<(String, VariableKind)>,
+    globals: Vec<(String, VariableKind)>,
+    errors: Vec<BuildErrors>,
+}
+
+impl Options {
+    pub fn option(self, token: MatchToken) -> Self {
+        self.option_then(token, |b| b)
+    }
+
+    /// Option that executes the rules of the block when it is matched
+    pub fn option_then(
+        mut self,
+        token: MatchToken,
+        block: impl FnOnce(Builder) -> Builder,
+    ) -> Self {
+        let block = block(Builder::default());
+        self.variables.extend(block.variables);
+        self.globals.extend(block.globals);
+        self.errors.extend(block.errors);
+        self.options.push(OneOf {
+            token,
+            rules: block.rules,
+            parameters: Vec::new(),
+        });
+        self
+    }
+}
+
+impl Parameterize for Options {
+    fn parameter(mut self, parameter: Parameters) -> Self {
+        match self.options.last_mut() {
+            Some(option) => option.parameters.push(parameter),
+            None => self.errors.push(BuildErrors::NoToken(parameter)),
+        }
+        self
+    }
+
+    fn use_variable(mut self, name: &str, kind: VariableKind) -> Self {
+        self.variables.push((name.to_string(), kind));
+        self
+    }
+
+    fn use_global(mut self, name: &str, kind: VariableKind) -> Self {
+        self.globals.push((name.to_string(), kind));
+        self
+    }
+}
+
+/// Parameters of the last matched token
+///
+/// The parameters that use variables declare them as well
+pub trait Parameterize: Sized {
+    /// Adds a parameter to the last matched token
+    fn parameter(self, parameter: Parameters) -> Self;
+    /// Records the use of a variable
+    fn use_variable(self, name: &str, kind: VariableKind) -> Self;
+    /// Records the use of a global
+    fn use_global(self, name: &str, kind: VariableKind) -> Self;
+
+    /// Sets a node variable
+    fn set(self, var: &str) -> Self {
+        self.parameter(Parameters::Set(var.to_string()))
+            .use_variable(var, VariableKind::Node)
+    }
+
+    /// Pushes to a list variable
+    fn push(self, var: &str) -> Self {
+        self.parameter(Parameters::Set(var.to_string()))
+            .use_variable(var, VariableKind::NodeList)
+    }
+
+    fn increment(self, var: &str) -> Self {
+        self.parameter(Parameters::Increment(var.to_string()))
+            .use_variable(var, VariableKind::Number)
+    }
+
+    fn decrement(self, var: &str) -> Self {
+        self.parameter(Parameters::Decrement(var.to_string()))
+            .use_variable(var, VariableKind::Number)
+    }
+
+    fn set_true(self, var: &str) -> Self {
+        self.parameter(Parameters::True(var.to_string()))
+            .use_variable(var, VariableKind::Boolean)
+    }
+
+    fn set_false(self, var: &str) -> Self {
+        self.parameter(Parameters::False(var.to_string()))
+            .use_variable(var, VariableKind::Boolean)
+    }
+
+    /// Sets a global node variable
+    fn set_global(self, var: &str) -> Self {
+        self.parameter(Parameters::Global(var.to_string()))
+            .use_global(var, VariableKind::Node)
+    }
+
+    fn increment_global(self, var: &str) -> Self {
+        self.parameter(Parameters::IncrementGlobal(var.to_string()))
+            .use_global(var, VariableKind::Number)
+    }
+
+    fn true_global(self, var: &str) -> Self {
+        self.parameter(Parameters::TrueGlobal(var.to_string()))
+            .use_global(var, VariableKind::Boolean)
+    }
+
+    fn false_global(self, var: &str) -> Self {
+        self.parameter(Parameters::FalseGlobal(var.to_string()))
+            .use_global(var, VariableKind::Boolean)
+    }
+
+    /// The node must match once this token is matched
+    fn hard(self) -> Self {
+        self.parameter(Parameters::HardError(true))
+    }
+
+    fn soft(self) -> Self {
+        self.parameter(Parameters::HardError(false))
+    }
+
+    fn returns(self) -> Self {
+        self.parameter(Parameters::Return)
+    }
+
+    fn breaks(self, blocks: usize) -> Self {
+        self.parameter(Parameters::Break(blocks))
+    }
+
+    fn back(self, steps: u8) -> Self {
+        self.parameter(Parameters::Back(steps))
+    }
+
+    fn goes_to(self, label: &str) -> Self {
+        self.parameter(Parameters::Goto(label.to_string()))
+    }
+
+    fn node_start(self) -> Self {
+        self.parameter(Parameters::NodeStart)
+    }
+
+    fn node_end(self) -> Self {
+        self.parameter(Parameters::NodeEnd)
+    }
+}
+
+/// Collects nodes, templates, enumerators and globals into a grammar
+pub struct GrammarBuilder {
+    grammar: Grammar,
+    globals: Vec<(String, VariableKind)>,
+    errors: Vec<BuildError>,
+}
+
+impl GrammarBuilder {
+    pub fn node(mut self, node: Builder) -> Self {
+        self.globals.extend(node.globals.iter().cloned());
+        if self.grammar.nodes.contains_key(&node.name) {
+            self.errors.push(BuildError {
+                kind: BuildErrors::DuplicateNode,
+                node_name: node.name.clone(),
+            });
+        }
+        let node = node.finish(&mut self.errors);
+        self.grammar.add_node(node);
+        self
+    }
+
+    /// Adds a template, the parameters can be matched using `node_ref`
+    pub fn template(mut self, parameters: &[&str], node: Builder) -> Self {
+        self.globals.extend(node.globals.iter().cloned());
+        let node = node.finish(&mut self.errors);
+        self.grammar.add_template(Template {
+            name: node.name,
+            parameters: parameters.iter().map(|p| p.to_string()).collect(),
+            rules: node.rules,
+            variables: node.variables,
+        });
+        self
+    }
+
+    pub fn enumerator(mut self, name: &str, values: Vec<MatchToken>) -> Self {
+        self.grammar.enumerators.insert(
+            name.to_string(),
+            Enumerator {
+                name: name.to_string(),
+                values,
+            },
+        );
+        self
+    }
+
+    /// Declares a global
+    ///
+    /// This is only needed for globals that are not used by any parameter
+    pub fn global(mut self, name: &str, kind: VariableKind) -> Self {
+        self.globals.push((name.to_string(), kind));
+        self
+    }
+
+    pub fn eof(mut self, eof: bool) -> Self {
+        self.grammar.eof = eof;
+        self
+    }
+
+    /// Builds the grammar and instantiates the templates
+    pub fn build(mut self) -> Result<Grammar, Vec<BuildError>> {
+        let mut errors = self.errors;
+        let mut error = |kind| {
+            errors.push(BuildError {
+                kind,
+                node_name: "__globals__".to_string(),
+            })
+        };
+        self.grammar.globals = declare(&self.globals, &mut error);
+        if let Err(err) = self.grammar.instantiate() {
+            error(BuildErrors::Template(err));
+        }
+        match errors.is_empty() {
+            true => Ok(self.grammar),
+            false => Err(errors),
+        }
+    }
+}
+
+/// Declares the used variables
+///
+/// A variable that is set and pushed to is a list
+fn declare(
+    uses: &[(String, VariableKind)],
+    error: &mut impl FnMut(BuildErrors),
+) -> Map<String, VariableKind> {
+    let mut variables: Map<String, VariableKind> = Map::new();
+    for (name, kind) in uses {
+        let declared = match variables.get_mut(name) {
+            Some(declared) => declared,
+            None => {
+                variables.insert(name.clone(), kind.clone());
+                continue;
+            }
+        };
+        match (&*declared, kind) {
+            (a, b) if a == b => (),
+            (VariableKind::NodeList, VariableKind::Node) => (),
+            (VariableKind::Node, VariableKind::NodeList) => *declared = VariableKind::NodeList,
+            _ => error(BuildErrors::VariableKind {
+                variable: name.clone(),
+                kinds: vec![declared.clone(), kind.clone()],
+            }),
+        }
+    }
+    variables
+}
+
+#[derive(Clone)]
+pub struct BuildError {
+    pub kind: BuildErrors,
+    pub node_name: String,
+}
+
+impl fmt::Debug for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} in {}", self.kind, self.node_name)
+    }
+}
+
+#[derive(Clone)]
+pub enum BuildErrors {
+    /// Parameter was added before any token was matched
+    NoToken(Parameters),
+    /// Variable is used as more than one kind
+    VariableKind {
+        variable: String,
+        kinds: Vec<VariableKind>,
+    },
+    /// Node was added twice
+    DuplicateNode,
+    /// Templates could not be instantiated
+    Template(TemplateError),
+}
+
+impl fmt::Debug for BuildErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildErrors::NoToken(parameter) => {
+                write!(f, "Parameter {:?} does not follow a token", parameter)
+            }
+            BuildErrors::VariableKind { variable, kinds } => {
+                write!(f, "Variable {} is used as {:?}", variable, kinds)
+            }
+            BuildErrors::DuplicateNode => write!(f, "Node defined twice"),
+            BuildErrors::Template(err) => write!(f, "{:?}", err),
+        }
+    }
+}
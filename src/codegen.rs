@@ -0,0 +1,680 @@
+//! Generates a standalone Rust parser from a grammar
+//!
+//! The interpreter in `parser` walks the rules and looks up nodes by name for every
+//! token. `generate` compiles the grammar into one function per node and one per
+//! block of rules instead, so the parser can be built in `build.rs` and included
+//! in the crate:
+//!
+//! ```ignore
+//! // build.rs
+//! let parser = rparse::format::load(&std::fs::read_to_string("grammar.rparse").unwrap()).unwrap();
+//! let out = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("parser.rs");
+//! std::fs::write(out, rparse::codegen::generate(&parser)).unwrap();
+//!
+//! // src/lib.rs
+//! mod parser {
+//!     include!(concat!(env!("OUT_DIR"), "/parser.rs"));
+//! }
+//!
+//! let parser = parser::Parser::new();
+//! let tokens = parser.lexer.lex_utf8(text)?;
+//! let result = parser.parse(&tokens, text)?;
+//! ```
+//!
+//! The generated `Parser` returns the same `ParseResult` and `ParseError` as
+//! `rparse::Parser::parse`. Preprocessors are not part of the grammar, push them
+//! to `parser.lexer.preprocessors`.
+use crate::{
+    grammar::{Commands, Grammar, MatchToken, OneOf, Parameters, Rule},
+    lexer::{Literal, ModeAction, TokenKinds},
+    Map, Parser,
+};
+
+use core::fmt::Write;
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::format;
+    }
+}
+
+/// Lints the generated code would trigger
+const ALLOW: &str =
+    "#[allow(dead_code, unused_mut, unused_variables, unreachable_code, clippy::all)]";
+
+/// Not every grammar needs all of them
+const IMPORTS: [&str; 4] = [
+    "::rparse::grammar::{Comparison, MatchToken, VariableKind}",
+    "::rparse::lexer::{ControlTokenKind, Lexer, Literal, ModeAction, Token, TokenKinds}",
+    "::rparse::parser::generated::*",
+    "::rparse::parser::{Node, Nodes, ParseError, ParseErrors, ParseResult}",
+];
+
+/// Generates the source of a module with a `Parser` for the grammar
+pub fn generate(parser: &Parser) -> String {
+    let mut generator = Generator::new(&parser.grammar);
+    for name in generator.nodes.clone() {
+        generator.node(name);
+    }
+    for name in generator.enumerators.clone() {
+        generator.enumerator(name);
+    }
+
+    let mut out = String::new();
+    out.push_str("// Generated by rparse, do not edit\n\n");
+    for import in IMPORTS {
+        let _ = writeln!(out, "#[allow(unused_imports)]\nuse {};", import);
+    }
+    out.push('\n');
+
+    let _ = writeln!(out, "{}", ALLOW);
+    out.push_str("pub struct Parser {\n    pub lexer: Lexer,\n    kinds: Vec<TokenKinds>,\n}\n\n");
+
+    let _ = writeln!(out, "{}", ALLOW);
+    out.push_str("impl Parser {\n");
+    out.push_str("    pub fn new() -> Parser {\n        let mut lexer = Lexer::new();\n");
+    for token in &parser.lexer.token_kinds {
+        let _ = writeln!(out, "        lexer.add_token({:?}.to_string());", token);
+    }
+    for pattern in &parser.lexer.patterns {
+        let _ = writeln!(
+            out,
+            "        lexer.add_pattern({:?}, {:?}).unwrap();",
+            pattern.name,
+            pattern.pattern.source()
+        );
+    }
+    for literal in &parser.lexer.literals {
+        let _ = writeln!(
+            out,
+            "        lexer.add_literal({:?}, {});",
+            literal.name,
+            self::literal(&literal.literal)
+        );
+    }
+    for keyword in &parser.lexer.keywords {
+        let _ = writeln!(
+            out,
+            "        lexer.add_keyword({:?}, {});",
+            keyword.word, keyword.case_insensitive
+        );
+    }
+    if parser.lexer.indentation {
+        out.push_str("        lexer.set_indentation(true);\n");
+    }
+    for token in sorted(&parser.lexer.actions) {
+        let _ = writeln!(
+            out,
+            "        lexer.on({:?}, {});",
+            token,
+            mode_action(&parser.lexer.actions[token])
+        );
+    }
+    for mode in sorted(&parser.lexer.modes) {
+        let mode = &parser.lexer.modes[mode];
+        let _ = write!(out, "        lexer.add_mode({:?})", mode.name);
+        for token in mode.tokens() {
+            let _ = write!(out, "\n            .add_token({:?})", token);
+        }
+        for token in sorted(mode.actions()) {
+            let action = mode_action(&mode.actions()[token]);
+            let _ = write!(out, "\n            .on({:?}, {})", token, action);
+        }
+        if let Some(merged) = mode.merged() {
+            let _ = write!(out, "\n            .merge({:?})", merged);
+        }
+        let _ = writeln!(out, "\n            .inherit({});", mode.inherits());
+        for pattern in mode.patterns() {
+            let _ = writeln!(
+                out,
+                "        lexer.add_mode({:?}).add_pattern({:?}, {:?}).unwrap();",
+                mode.name,
+                pattern.name,
+                pattern.pattern.source()
+            );
+        }
+    }
+    out.push_str("        Parser {\n            lexer,\n            kinds: vec![\n");
+    for kind in &generator.kinds {
+        let _ = writeln!(out, "                {},", token_kind(kind));
+    }
+    out.push_str("            ],\n        }\n    }\n\n");
+
+    out.push_str("    pub fn parse(&self, tokens: &Vec<Token>, text: &str) -> Result<ParseResult, ParseError> {\n");
+    let _ = writeln!(
+        out,
+        "        let globals = variables(&{});",
+        variables(&parser.grammar.globals)
+    );
+    out.push_str("        let mut context = Context::new(&self.lexer, tokens, text, globals);\n");
+    out.push_str("        let ctx = &mut context;\n");
+    let _ = writeln!(
+        out,
+        "        let result = {};",
+        generator.call_node(&parser.parser.entry)
+    );
+    let _ = writeln!(
+        out,
+        "        context.finish({}, result)\n    }}",
+        parser.grammar.eof
+    );
+    for function in &generator.functions {
+        out.push('\n');
+        out.push_str(function);
+    }
+    out.push_str("}\n");
+    out
+}
+
+struct Generator<'a> {
+    grammar: &'a Grammar,
+    /// Sorted node names, the index is the name of the function
+    nodes: Vec<&'a str>,
+    /// Sorted enumerator names
+    enumerators: Vec<&'a str>,
+    /// Token kinds stored in the parser
+    kinds: Vec<TokenKinds>,
+    functions: Vec<String>,
+    blocks: usize,
+}
+
+impl<'a> Generator<'a> {
+    fn new(grammar: &'a Grammar) -> Generator<'a> {
+        let mut nodes: Vec<&str> = grammar.nodes.keys().map(|name| name.as_str()).collect();
+        nodes.sort();
+        let mut enumerators: Vec<&str> = grammar
+            .enumerators
+            .keys()
+            .map(|name| name.as_str())
+            .collect();
+        enumerators.sort();
+        Generator {
+            grammar,
+            nodes,
+            enumerators,
+            kinds: Vec::new(),
+            functions: Vec::new(),
+            blocks: 0,
+        }
+    }
+
+    /// Expression that parses a node
+    fn call_node(&self, name: &str) -> String {
+        match self.nodes.binary_search(&name) {
+            Ok(i) => format!("self.node_{}(ctx)", i),
+            Err(_) => format!("ctx.missing_node({:?})", name),
+        }
+    }
+
+    fn node(&mut self, name: &str) {
+        let node = &self.grammar.nodes[name];
+        let index = self.nodes.binary_search(&name).unwrap();
+        let block = self.block(&node.rules);
+
+        let mut out = String::new();
+        let _ = writeln!(out, "    // node {}", name);
+        let _ = writeln!(
+            out,
+            "    fn node_{}(&self, ctx: &mut Context) -> Result<Node, (ParseError, Node)> {{",
+            index
+        );
+        let _ = writeln!(
+            out,
+            "        let (mut node, saved) = ctx.start_node({:?}, variables(&{}));",
+            node.name,
+            variables(&node.variables)
+        );
+        match block {
+            Some(block) => {
+                let _ = writeln!(
+                    out,
+                    "        let result = self.block_{}(ctx, &mut node, &saved);",
+                    block
+                );
+            }
+            None => out.push_str("        let result = Ok(Msg::Ok);\n"),
+        }
+        out.push_str("        ctx.end_node(node, saved, result)\n    }\n");
+        self.functions.push(out);
+    }
+
+    fn enumerator(&mut self, name: &str) {
+        let enumerator = &self.grammar.enumerators[name];
+        let index = self.enumerators.binary_search(&name).unwrap();
+
+        let mut out = String::new();
+        let _ = writeln!(out, "    // enumerator {}", name);
+        let _ = writeln!(
+            out,
+            "    fn enumerator_{}(&self, ctx: &mut Context) -> Result<TokenCompare, ParseError> {{",
+            index
+        );
+        out.push_str("        let saved = ctx.save();\n");
+        for value in &enumerator.values {
+            let _ = writeln!(out, "        match {}? {{", self.match_token(value));
+            out.push_str(
+                "            TokenCompare::Is(val) => return Ok(TokenCompare::Is(val)),\n",
+            );
+            out.push_str("            TokenCompare::IsNot(err) => {\n");
+            out.push_str("                ctx.restore(&saved);\n");
+            out.push_str("                ctx.soft(err, false)?;\n");
+            out.push_str("            }\n        }\n");
+        }
+        let _ = writeln!(
+            out,
+            "        Ok(TokenCompare::IsNot(ctx.error(ParseErrors::ExpectedOneOf {{ expected: {}, found: ctx.found() }}, None)))\n    }}",
+            match_tokens(enumerator.values.iter())
+        );
+        self.functions.push(out);
+    }
+
+    /// Expression that matches a token, returns `Result<TokenCompare, ParseError>`
+    fn match_token(&mut self, token: &MatchToken) -> String {
+        match token {
+            MatchToken::Token(kind) => {
+                let index = match self.kinds.iter().position(|k| k == kind) {
+                    Some(index) => index,
+                    None => {
+                        self.kinds.push(kind.clone());
+                        self.kinds.len() - 1
+                    }
+                };
+                format!("ctx.kind(&self.kinds[{}])", index)
+            }
+            MatchToken::Node(name) => format!("node_compare({})", self.call_node(name)),
+            MatchToken::Template(template, arguments) => {
+                let name = crate::grammar::instance_name(template, arguments);
+                format!("node_compare({})", self.call_node(&name))
+            }
+            MatchToken::Word(word) => format!("ctx.word({:?})", word),
+            MatchToken::Enumerator(name) => match self.enumerators.binary_search(&name.as_str()) {
+                Ok(i) => format!("self.enumerator_{}(ctx)", i),
+                Err(_) => format!("ctx.missing_enumerator({:?})", name),
+            },
+            MatchToken::Any => "ctx.any()".to_string(),
+        }
+    }
+
+    /// Generates a function for the rules and returns its index
+    ///
+    /// Empty blocks return `None`, they do nothing
+    fn block(&mut self, rules: &[Rule]) -> Option<usize> {
+        if rules.is_empty() {
+            return None;
+        }
+        let index = self.blocks;
+        self.blocks += 1;
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "    fn block_{}(&self, ctx: &mut Context, node: &mut Node, saved: &Cursor) -> Result<Msg, ParseError> {{",
+            index
+        );
+        out.push_str("        let mut advance = true;\n");
+        out.push_str("        let mut bus = MsgBus::new();\n");
+        out.push_str("        let mut i = 0;\n");
+        let _ = writeln!(out, "        while i < {} {{", rules.len());
+        out.push_str("            ctx.next(node)?;\n");
+        out.push_str("            match i {\n");
+        for (i, rule) in rules.iter().enumerate() {
+            let _ = writeln!(out, "                {} => {{", i);
+            let mut body = String::new();
+            self.rule(rule, &mut body);
+            indent(&mut out, &body, 5);
+            out.push_str("                }\n");
+        }
+        out.push_str("                _ => unreachable!(),\n            }\n");
+        out.push_str("            if advance {\n                i += 1;\n            } else {\n                advance = true;\n            }\n");
+        out.push_str("            while let Some(msg) = bus.receive() {\n");
+        out.push_str("                match msg {\n");
+        out.push_str("                    Msg::Return => return Ok(Msg::Return),\n");
+        out.push_str("                    Msg::Break(n) => return if n == 1 { Ok(Msg::Ok) } else { Ok(Msg::Break(n - 1)) },\n");
+        out.push_str("                    Msg::Goto(label) => {\n");
+        out.push_str("                        i = match label.as_str() {\n");
+        let mut labels: Vec<&str> = Vec::new();
+        for (i, rule) in rules.iter().enumerate() {
+            if let Rule::Command {
+                command: Commands::Label { name },
+            } = rule
+            {
+                // the first label with the name wins
+                if !labels.contains(&name.as_str()) {
+                    labels.push(name);
+                    let _ = writeln!(out, "                            {:?} => {},", name, i);
+                }
+            }
+        }
+        out.push_str("                            _ => return Ok(Msg::Goto(label)),\n");
+        out.push_str("                        };\n                    }\n");
+        out.push_str("                    Msg::Back(steps) => {\n");
+        out.push_str("                        if i < steps {\n                            return Ok(Msg::Back(steps - i));\n                        }\n");
+        out.push_str("                        i -= steps;\n                    }\n");
+        out.push_str(
+            "                    Msg::Ok => {}\n                }\n            }\n        }\n",
+        );
+        out.push_str("        Ok(Msg::Ok)\n    }\n");
+        self.functions.push(out);
+        Some(index)
+    }
+
+    /// Statement that runs a block and passes its message on
+    fn run_block(&mut self, rules: &[Rule], out: &mut String) {
+        if let Some(block) = self.block(rules) {
+            let _ = writeln!(
+                out,
+                "self.block_{}(ctx, node, saved)?.push(&mut bus);",
+                block
+            );
+        }
+    }
+
+    /// Code that runs after a token was matched into `val`
+    fn matched(&mut self, parameters: &[Parameters], rules: &[Rule], out: &mut String) {
+        out.push_str("let is_token = val.is_token();\n");
+        self.parameters(parameters, out);
+        out.push_str("if is_token {\n    ctx.matched();\n}\n");
+        self.run_block(rules, out);
+    }
+
+    /// Tries the options one after another, sets `found` when one matches
+    fn one_of(&mut self, options: &[OneOf], reset: bool, out: &mut String) {
+        for option in options {
+            let mut body = String::new();
+            let _ = writeln!(body, "match {}? {{", self.match_token(&option.token));
+            body.push_str("    TokenCompare::Is(val) => {\n        found = true;\n");
+            let mut matched = String::new();
+            self.matched(&option.parameters, &option.rules, &mut matched);
+            indent(&mut body, &matched, 2);
+            body.push_str("    }\n");
+            let _ = writeln!(
+                body,
+                "    TokenCompare::IsNot(err) => ctx.soft(err, {})?,",
+                reset
+            );
+            body.push_str("}\n");
+            out.push_str("if !found {\n");
+            indent(out, &body, 1);
+            out.push_str("}\n");
+        }
+    }
+
+    fn rule(&mut self, rule: &Rule, out: &mut String) {
+        match rule {
+            Rule::Is {
+                token,
+                rules,
+                parameters,
+            } => {
+                let _ = writeln!(out, "match {}? {{", self.match_token(token));
+                out.push_str("    TokenCompare::Is(val) => {\n");
+                let mut body = String::new();
+                self.matched(parameters, rules, &mut body);
+                indent(out, &body, 2);
+                out.push_str("    }\n    TokenCompare::IsNot(err) => return Err(err),\n}\n");
+            }
+            Rule::Isnt { token, rules, .. } => {
+                let _ = writeln!(out, "match {}? {{", self.match_token(token));
+                out.push_str("    TokenCompare::Is(_) => ctx.fail(ParseErrors::ExpectedToNotBe(ctx.found()), saved, node)?,\n");
+                out.push_str("    TokenCompare::IsNot(_) => {\n");
+                let mut body = String::new();
+                self.run_block(rules, &mut body);
+                indent(out, &body, 2);
+                out.push_str("    }\n}\n");
+            }
+            Rule::IsOneOf { tokens } => {
+                out.push_str("let mut found = false;\n");
+                self.one_of(tokens, true, out);
+                out.push_str("if !found {\n");
+                let _ = writeln!(
+                    out,
+                    "    ctx.fail(ParseErrors::ExpectedOneOf {{ expected: {}, found: ctx.found() }}, saved, node)?;",
+                    match_tokens(tokens.iter().map(|option| &option.token))
+                );
+                out.push_str("}\n");
+            }
+            Rule::Maybe {
+                token,
+                is,
+                isnt,
+                parameters,
+            } => {
+                let _ = writeln!(out, "match {}? {{", self.match_token(token));
+                out.push_str("    TokenCompare::Is(val) => {\n");
+                let mut body = String::new();
+                self.matched(parameters, is, &mut body);
+                indent(out, &body, 2);
+                out.push_str(
+                    "    }\n    TokenCompare::IsNot(err) => {\n        ctx.soft(err, false)?;\n",
+                );
+                let mut body = String::new();
+                self.run_block(isnt, &mut body);
+                indent(out, &body, 2);
+                out.push_str("    }\n}\n");
+            }
+            Rule::MaybeOneOf { is_one_of, isnt } => {
+                out.push_str("let mut found = false;\n");
+                self.one_of(is_one_of, false, out);
+                out.push_str("if !found {\n");
+                let mut body = String::new();
+                self.run_block(isnt, &mut body);
+                indent(out, &body, 1);
+                out.push_str("}\n");
+            }
+            Rule::While {
+                token,
+                rules,
+                parameters,
+            } => {
+                let _ = writeln!(out, "match {}? {{", self.match_token(token));
+                out.push_str("    TokenCompare::Is(val) => {\n");
+                let mut body = String::new();
+                self.matched(parameters, rules, &mut body);
+                body.push_str("advance = false;\n");
+                indent(out, &body, 2);
+                out.push_str("    }\n    TokenCompare::IsNot(err) => ctx.soft(err, false)?,\n}\n");
+            }
+            Rule::Loop { rules } => {
+                self.run_block(rules, out);
+                out.push_str("advance = false;\n");
+            }
+            Rule::Until {
+                token,
+                rules,
+                parameters,
+            } => {
+                let _ = writeln!(
+                    out,
+                    "while let TokenCompare::IsNot(_) = {}? {{",
+                    self.match_token(token)
+                );
+                let _ = writeln!(out, "    ctx.step(node, || {})?;", match_token(token));
+                out.push_str("}\nlet val = ctx.current();\n");
+                self.parameters(parameters, out);
+                out.push_str("ctx.matched();\n");
+                self.run_block(rules, out);
+            }
+            Rule::UntilOneOf { tokens } => {
+                out.push_str("let mut found = false;\nwhile ctx.in_bounds() {\n");
+                let mut body = String::new();
+                self.one_of(tokens, false, &mut body);
+                body.push_str("if found {\n    break;\n}\nctx.skip();\n");
+                indent(out, &body, 1);
+                out.push_str("}\n");
+                out.push_str("if !found {\n");
+                let _ = writeln!(
+                    out,
+                    "    ctx.fail(ParseErrors::ExpectedOneOf {{ expected: {}, found: ctx.found() }}, saved, node)?;",
+                    match_tokens(tokens.iter().map(|option| &option.token))
+                );
+                out.push_str("}\n");
+            }
+            Rule::Command { command } => match command {
+                Commands::Compare {
+                    left,
+                    right,
+                    comparison,
+                    rules,
+                } => {
+                    let _ = writeln!(
+                        out,
+                        "if ctx.compare(node, {:?}, {:?}, &Comparison::{:?})? {{",
+                        left, right, comparison
+                    );
+                    let mut body = String::new();
+                    self.run_block(rules, &mut body);
+                    indent(out, &body, 1);
+                    out.push_str("}\n");
+                }
+                Commands::Error { message } => {
+                    let _ = writeln!(
+                        out,
+                        "return Err(ctx.error(ParseErrors::Message({:?}.to_string()), Some(node)));",
+                        message
+                    );
+                }
+                Commands::HardError { set } => {
+                    let _ = writeln!(out, "ctx.hard(node, {});", set);
+                }
+                Commands::Goto { label } => {
+                    let _ = writeln!(out, "bus.send(Msg::Goto({:?}.to_string()));", label);
+                }
+                Commands::Label { .. } => (),
+                Commands::Print { message } => {
+                    let _ = writeln!(out, "ctx.print({:?});", message);
+                }
+            },
+            Rule::Debug { target } => {
+                let _ = writeln!(out, "ctx.debug(node, {:?}, true)?;", target.as_deref());
+            }
+        }
+    }
+
+    fn parameters(&mut self, parameters: &[Parameters], out: &mut String) {
+        for parameter in parameters {
+            let _ = match parameter {
+                Parameters::Set(name) => writeln!(out, "ctx.set(node, {:?}, &val)?;", name),
+                Parameters::Print(message) => writeln!(out, "ctx.print({:?});", message),
+                Parameters::Debug(target) => {
+                    writeln!(out, "ctx.debug(node, {:?}, false)?;", target.as_deref())
+                }
+                Parameters::Increment(name) => writeln!(out, "ctx.count(node, {:?}, 1)?;", name),
+                Parameters::Decrement(name) => writeln!(out, "ctx.count(node, {:?}, -1)?;", name),
+                Parameters::True(name) => writeln!(out, "ctx.flag(node, {:?}, true)?;", name),
+                Parameters::False(name) => writeln!(out, "ctx.flag(node, {:?}, false)?;", name),
+                Parameters::Global(name) => writeln!(out, "ctx.set_global({:?}, &val)?;", name),
+                Parameters::IncrementGlobal(name) => {
+                    writeln!(out, "ctx.count_global({:?}, 1)?;", name)
+                }
+                Parameters::TrueGlobal(name) => {
+                    writeln!(out, "ctx.flag_global({:?}, true)?;", name)
+                }
+                Parameters::FalseGlobal(name) => {
+                    writeln!(out, "ctx.flag_global({:?}, false)?;", name)
+                }
+                Parameters::HardError(value) => writeln!(out, "ctx.hard(node, {});", value),
+                Parameters::NodeStart => writeln!(out, "ctx.node_start(node);"),
+                Parameters::NodeEnd => writeln!(out, "ctx.node_end(node);"),
+                Parameters::Back(steps) => writeln!(out, "bus.send(Msg::Back({}));", steps),
+                Parameters::Return => writeln!(out, "bus.send(Msg::Return);"),
+                Parameters::Goto(label) => {
+                    writeln!(out, "bus.send(Msg::Goto({:?}.to_string()));", label)
+                }
+                Parameters::Break(n) => writeln!(out, "bus.send(Msg::Break({}));", n),
+            };
+        }
+    }
+}
+
+/// Indents every line of `body` by `level` levels and appends it
+fn indent(out: &mut String, body: &str, level: usize) {
+    for line in body.lines() {
+        for _ in 0..level {
+            out.push_str("    ");
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+/// `variables(...)` argument for a node or the globals, sorted by name
+fn variables(variables: &Map<String, crate::grammar::VariableKind>) -> String {
+    let mut names: Vec<&String> = variables.keys().collect();
+    names.sort();
+    let mut out = String::from("[");
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "({:?}, VariableKind::{:?})", name, variables[*name]);
+    }
+    out.push(']');
+    out
+}
+
+fn sorted<V>(map: &Map<String, V>) -> Vec<&String> {
+    let mut keys = map.keys().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+/// Expression that builds a `Literal` of the lexer
+fn literal(literal: &Literal) -> String {
+    match literal {
+        Literal::String { quote, escape } => format!("Literal::string({:?}, {:?})", quote, escape),
+        Literal::LineComment { start } => format!("Literal::line_comment({:?})", start),
+        Literal::BlockComment { start, end, nested } => {
+            format!("Literal::block_comment({:?}, {:?}, {})", start, end, nested)
+        }
+        Literal::RawString { start, end } => format!("Literal::raw_string({:?}, {:?})", start, end),
+    }
+}
+
+fn mode_action(action: &ModeAction) -> String {
+    match action {
+        ModeAction::Push(mode) => format!("ModeAction::Push({:?}.to_string())", mode),
+        ModeAction::Pop => "ModeAction::Pop".to_string(),
+    }
+}
+
+/// Expression that builds a token kind
+fn token_kind(kind: &TokenKinds) -> String {
+    match kind {
+        TokenKinds::Token(token) => format!("TokenKinds::Token({:?}.to_string())", token),
+        TokenKinds::Complex(token) => format!("TokenKinds::Complex({:?}.to_string())", token),
+        TokenKinds::Text => "TokenKinds::Text".to_string(),
+        TokenKinds::Whitespace => "TokenKinds::Whitespace".to_string(),
+        TokenKinds::Control(control) => {
+            format!("TokenKinds::Control(ControlTokenKind::{:?})", control)
+        }
+    }
+}
+
+/// Expression that builds a `MatchToken`, used for the errors
+fn match_token(token: &MatchToken) -> String {
+    match token {
+        MatchToken::Token(kind) => format!("MatchToken::Token({})", token_kind(kind)),
+        MatchToken::Node(name) => format!("MatchToken::Node({:?}.to_string())", name),
+        MatchToken::Word(word) => format!("MatchToken::Word({:?}.to_string())", word),
+        MatchToken::Enumerator(name) => format!("MatchToken::Enumerator({:?}.to_string())", name),
+        MatchToken::Template(template, arguments) => format!(
+            "MatchToken::Template({:?}.to_string(), {})",
+            template,
+            match_tokens(arguments.iter())
+        ),
+        MatchToken::Any => "MatchToken::Any".to_string(),
+    }
+}
+
+/// Expression that builds a `Vec<MatchToken>`
+fn match_tokens<'a>(tokens: impl Iterator<Item = &'a MatchToken>) -> String {
+    let tokens: Vec<String> = tokens.map(match_token).collect();
+    format!("vec![{}]", tokens.join(", "))
+}
@@ -0,0 +1,432 @@
+//! Serde deserializer over the parse tree
+//!
+//! `from_tree` turns the result of the parser into any type that implements `Deserialize`:
+//!
+//! - nodes are structs (or maps), every variable is a field
+//! - `list` variables are sequences
+//! - `node` variables are options, an unset variable is `None`
+//! - `bool` and `number` variables are booleans and integers
+//! - tokens are their text, so they can be strings, chars, numbers or booleans
+//! - nodes can also be strings, the text they span is used
+//! - enums pick the variant by the name of the node, or by the text of the token
+//!
+//! Variables that are not in the struct are ignored.
+//!
+//! ```ignore
+//! #[derive(Deserialize)]
+//! struct LetDecl<'a> {
+//!     ident: &'a str,
+//!     value: Option<Value<'a>>,
+//!     mutable: bool,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! enum Value<'a> {
+//!     #[serde(rename = "KWLet")]
+//!     Let(Box<LetDecl<'a>>),
+//!     #[serde(rename = "number")]
+//!     Number(i64),
+//! }
+//!
+//! let result = parser.parse(&tokens, text)?;
+//! let decl: LetDecl = rparse::from_tree(&result, text)?;
+//! ```
+use crate::{
+    lexer::Token,
+    parser::{Node, Nodes, ParseResult, VariableKind},
+};
+
+use core::fmt;
+use serde::de::{
+    self, value::BorrowedStrDeserializer, Deserialize, DeserializeSeed, EnumAccess, MapAccess,
+    SeqAccess, VariantAccess, Visitor,
+};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::format;
+    }
+}
+
+/// Deserializes the entry node of the result
+pub fn from_tree<'a, T: Deserialize<'a>>(
+    result: &'a ParseResult,
+    text: &'a str,
+) -> Result<T, DeError> {
+    from_node(&result.entry, text)
+}
+
+/// Deserializes a single node
+pub fn from_node<'a, T: Deserialize<'a>>(node: &'a Node, text: &'a str) -> Result<T, DeError> {
+    T::deserialize(Deserializer::new(Value::Node(node), text))
+}
+
+/// Deserializes a node or a token
+pub fn from_nodes<'a, T: Deserialize<'a>>(nodes: &'a Nodes, text: &'a str) -> Result<T, DeError> {
+    T::deserialize(Deserializer::from_nodes(nodes, text))
+}
+
+pub enum DeError {
+    /// Error reported by the `Deserialize` implementation
+    Custom(String),
+    /// The text of a token or a node could not be parsed
+    InvalidText {
+        text: String,
+        expected: &'static str,
+    },
+    /// The value can not be used as an enum
+    NotAnEnum,
+    /// Tuple variants would need the variables in order
+    TupleVariant,
+}
+
+impl fmt::Debug for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeError::Custom(msg) => write!(f, "{}", msg),
+            DeError::InvalidText { text, expected } => {
+                write!(f, "Could not parse {:?} as {}", text, expected)
+            }
+            DeError::NotAnEnum => write!(f, "Only nodes and tokens can be deserialized as enums"),
+            DeError::TupleVariant => write!(f, "Tuple variants are not supported"),
+        }
+    }
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl de::StdError for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        DeError::Custom(format!("{}", msg))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Value<'a> {
+    Node(&'a Node),
+    Token(&'a Token),
+    Variable(&'a VariableKind),
+}
+
+/// Deserializer for a node, a token or a variable
+pub struct Deserializer<'a> {
+    value: Value<'a>,
+    text: &'a str,
+}
+
+impl<'a> Deserializer<'a> {
+    fn new(value: Value<'a>, text: &'a str) -> Self {
+        // a set node variable is the same as its content
+        let value = match value {
+            Value::Variable(VariableKind::Node(Some(nodes))) => match nodes {
+                Nodes::Node(node) => Value::Node(node),
+                Nodes::Token(token) => Value::Token(token),
+            },
+            value => value,
+        };
+        Deserializer { value, text }
+    }
+
+    pub fn from_nodes(nodes: &'a Nodes, text: &'a str) -> Self {
+        match nodes {
+            Nodes::Node(node) => Self::new(Value::Node(node), text),
+            Nodes::Token(token) => Self::new(Value::Token(token), text),
+        }
+    }
+
+    /// The text of a node or a token
+    fn slice(&self) -> Option<&'a str> {
+        match self.value {
+            Value::Node(node) => Some(&self.text[node.first_string_idx..node.last_string_idx]),
+            Value::Token(token) => Some(&self.text[token.index..token.index + token.len]),
+            Value::Variable(_) => None,
+        }
+    }
+}
+
+macro_rules! parse_text {
+    ($($method:ident => $visit:ident($ty:ty),)*) => {
+        $(
+            fn $method<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+                match self.slice() {
+                    Some(text) => match text.trim().parse::<$ty>() {
+                        Ok(value) => visitor.$visit(value),
+                        Err(_) => Err(DeError::InvalidText {
+                            text: text.to_string(),
+                            expected: stringify!($ty),
+                        }),
+                    },
+                    None => self.deserialize_any(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'a> de::Deserializer<'a> for Deserializer<'a> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.value {
+            Value::Node(node) => visitor.visit_map(Fields::new(node, self.text)),
+            Value::Token(_) => visitor.visit_borrowed_str(self.slice().unwrap()),
+            Value::Variable(VariableKind::Node(_)) => visitor.visit_none(),
+            Value::Variable(VariableKind::NodeList(list)) => visitor.visit_seq(List {
+                iter: list.iter(),
+                text: self.text,
+            }),
+            Value::Variable(VariableKind::Boolean(value)) => visitor.visit_bool(*value),
+            Value::Variable(VariableKind::Number(value)) => visitor.visit_i32(*value),
+        }
+    }
+
+    parse_text! {
+        deserialize_bool => visit_bool(bool),
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64),
+        deserialize_f32 => visit_f32(f32),
+        deserialize_f64 => visit_f64(f64),
+    }
+
+    fn deserialize_char<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_str<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.slice() {
+            Some(text) => visitor.visit_borrowed_str(text),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_string<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.slice() {
+            Some(text) => visitor.visit_borrowed_bytes(text.as_bytes()),
+            None => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.value {
+            Value::Variable(VariableKind::Node(None)) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.value {
+            Value::Variable(VariableKind::Node(None)) => visitor.visit_unit(),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_unit(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple<V: Visitor<'a>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_struct<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'a>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        let tag = match self.value {
+            Value::Node(node) => node.name.as_str(),
+            Value::Token(_) => self.slice().unwrap(),
+            Value::Variable(_) => return Err(DeError::NotAnEnum),
+        };
+        visitor.visit_enum(Variant { tag, content: self })
+    }
+
+    fn deserialize_identifier<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'a>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_unit()
+    }
+}
+
+/// The variables of a node
+struct Fields<'a> {
+    iter: <&'a crate::Map<String, VariableKind> as IntoIterator>::IntoIter,
+    value: Option<&'a VariableKind>,
+    text: &'a str,
+}
+
+impl<'a> Fields<'a> {
+    fn new(node: &'a Node, text: &'a str) -> Self {
+        Fields {
+            iter: node.variables.iter(),
+            value: None,
+            text,
+        }
+    }
+}
+
+impl<'a> MapAccess<'a> for Fields<'a> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'a>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(BorrowedStrDeserializer::new(key.as_str()))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'a>>(&mut self, seed: S) -> Result<S::Value, DeError> {
+        let value = self
+            .value
+            .take()
+            .expect("next_value called before next_key");
+        seed.deserialize(Deserializer::new(Value::Variable(value), self.text))
+    }
+}
+
+/// The items of a list variable
+struct List<'a> {
+    iter: core::slice::Iter<'a, Nodes>,
+    text: &'a str,
+}
+
+impl<'a> SeqAccess<'a> for List<'a> {
+    type Error = DeError;
+
+    fn next_element_seed<S: DeserializeSeed<'a>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, DeError> {
+        match self.iter.next() {
+            Some(nodes) => seed
+                .deserialize(Deserializer::from_nodes(nodes, self.text))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// A node or a token tagged with its name
+struct Variant<'a> {
+    tag: &'a str,
+    content: Deserializer<'a>,
+}
+
+impl<'a> EnumAccess<'a> for Variant<'a> {
+    type Error = DeError;
+    type Variant = Deserializer<'a>;
+
+    fn variant_seed<S: DeserializeSeed<'a>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), DeError> {
+        let tag = seed.deserialize(BorrowedStrDeserializer::<DeError>::new(self.tag))?;
+        Ok((tag, self.content))
+    }
+}
+
+impl<'a> VariantAccess<'a> for Deserializer<'a> {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), DeError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'a>>(self, seed: S) -> Result<S::Value, DeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'a>>(self, _len: usize, _visitor: V) -> Result<V::Value, DeError> {
+        Err(DeError::TupleVariant)
+    }
+
+    fn struct_variant<V: Visitor<'a>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
@@ -0,0 +1,361 @@
+//! Dialects
+//!
+//! A dialect is a list of patches that is applied on top of a base parser.
+//! This way one grammar can be shipped as a "strict" and an "extended" parser
+//! without copying it.
+//!
+//! Dialects are applied in layers, every dialect sees the result of the previous one.
+//! Instead of silently producing a broken grammar, everything that does not fit
+//! (missing nodes, overriding a node that a previous layer already changed, ...) is
+//! reported as a conflict.
+use crate::{
+    grammar::{
+        Commands, Enumerator, MatchToken, Node, OneOf, Rule, Rules, TemplateError, VariableKind,
+    },
+    Parser,
+};
+
+use serde::{Deserialize, Serialize};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+        use std::fmt;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use core::fmt;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Dialect {
+    pub name: String,
+    pub patches: Vec<Patch>,
+}
+
+/// A single change to the base parser
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Patch {
+    /// Adds a node that does not exist yet
+    AddNode(Node),
+    /// Replaces an existing node
+    OverrideNode(Node),
+    /// Appends options to a one of rule (`IsOneOf`, `MaybeOneOf` or `UntilOneOf`) of a node
+    ///
+    /// One of rules are counted from 0 in the order they appear in the node (including nested ones)
+    ExtendOneOf {
+        node: String,
+        nth: usize,
+        options: Vec<OneOf>,
+    },
+    /// Adds an enumerator that does not exist yet
+    AddEnumerator(Enumerator),
+    /// Appends values to an existing enumerator
+    ExtendEnumerator {
+        name: String,
+        values: Vec<MatchToken>,
+    },
+    /// Adds a global variable
+    AddGlobal { name: String, kind: VariableKind },
+    /// Adds tokens to the lexer, tokens that already exist are skipped
+    AddTokens(Vec<String>),
+}
+
+impl Dialect {
+    pub fn new(name: &str) -> Dialect {
+        Dialect {
+            name: name.to_string(),
+            patches: Vec::new(),
+        }
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        self.patches.push(Patch::AddNode(node));
+    }
+
+    pub fn override_node(&mut self, node: Node) {
+        self.patches.push(Patch::OverrideNode(node));
+    }
+
+    pub fn extend_one_of(&mut self, node: &str, nth: usize, options: Vec<OneOf>) {
+        self.patches.push(Patch::ExtendOneOf {
+            node: node.to_string(),
+            nth,
+            options,
+        });
+    }
+
+    pub fn add_enumerator(&mut self, enumerator: Enumerator) {
+        self.patches.push(Patch::AddEnumerator(enumerator));
+    }
+
+    pub fn extend_enumerator(&mut self, name: &str, values: Vec<MatchToken>) {
+        self.patches.push(Patch::ExtendEnumerator {
+            name: name.to_string(),
+            values,
+        });
+    }
+
+    pub fn add_global(&mut self, name: &str, kind: VariableKind) {
+        self.patches.push(Patch::AddGlobal {
+            name: name.to_string(),
+            kind,
+        });
+    }
+
+    pub fn add_tokens(&mut self, tokens: &[String]) {
+        self.patches.push(Patch::AddTokens(tokens.to_vec()));
+    }
+}
+
+pub struct Conflict {
+    /// Name of the dialect that caused the conflict
+    pub dialect: String,
+    pub kind: Conflicts,
+}
+
+impl fmt::Debug for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} in dialect {}", self.kind, self.dialect)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum Conflicts {
+    /// Node that is being added already exists
+    NodeExists(String),
+    /// Node that is being changed does not exist
+    NodeNotFound(String),
+    /// Node does not have that many one of rules
+    OneOfNotFound { node: String, nth: usize },
+    /// Enumerator that is being added already exists
+    EnumeratorExists(String),
+    /// Enumerator that is being changed does not exist
+    EnumeratorNotFound(String),
+    /// Global that is being added already exists
+    GlobalExists(String),
+    /// Option or value that is already there (it would never be reached)
+    Duplicate { name: String, token: MatchToken },
+    /// Node is overridden after a previous dialect changed it, the changes would be lost
+    Overridden { node: String, by: String },
+    /// Template used by the dialect could not be instantiated
+    Template(TemplateError),
+}
+
+impl fmt::Debug for Conflicts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflicts::NodeExists(name) => write!(f, "Node already exists: {}", name),
+            Conflicts::NodeNotFound(name) => write!(f, "Node not found: {}", name),
+            Conflicts::OneOfNotFound { node, nth } => {
+                write!(f, "Node {} does not have one of rule number {}", node, nth)
+            }
+            Conflicts::EnumeratorExists(name) => write!(f, "Enumerator already exists: {}", name),
+            Conflicts::EnumeratorNotFound(name) => write!(f, "Enumerator not found: {}", name),
+            Conflicts::GlobalExists(name) => write!(f, "Global already exists: {}", name),
+            Conflicts::Duplicate { name, token } => {
+                write!(f, "{:?} is already part of {}", token, name)
+            }
+            Conflicts::Overridden { node, by } => write!(
+                f,
+                "Node {} was already changed by dialect {} and would be overridden",
+                node, by
+            ),
+            Conflicts::Template(err) => write!(f, "{:?}", err),
+        }
+    }
+}
+
+/// Applies dialects on top of a base parser in order
+///
+/// All the conflicts are collected and returned at once
+pub fn apply(base: &Parser, dialects: &[Dialect]) -> Result<Parser, Vec<Conflict>> {
+    let mut parser = base.clone();
+    let mut conflicts = Vec::new();
+    // which dialect changed a node last
+    let mut changed: Vec<(String, String)> = Vec::new();
+    for dialect in dialects {
+        let mut conflict = |kind| {
+            conflicts.push(Conflict {
+                dialect: dialect.name.clone(),
+                kind,
+            })
+        };
+        for patch in &dialect.patches {
+            let grammar = &mut parser.grammar;
+            match patch {
+                Patch::AddNode(node) => {
+                    if grammar.nodes.contains_key(&node.name) {
+                        conflict(Conflicts::NodeExists(node.name.clone()));
+                        continue;
+                    }
+                    grammar.add_node(node.clone());
+                }
+                Patch::OverrideNode(node) => {
+                    if !grammar.nodes.contains_key(&node.name) {
+                        conflict(Conflicts::NodeNotFound(node.name.clone()));
+                        continue;
+                    }
+                    if let Some((_, by)) = changed
+                        .iter()
+                        .find(|(name, by)| *name == node.name && *by != dialect.name)
+                    {
+                        conflict(Conflicts::Overridden {
+                            node: node.name.clone(),
+                            by: by.clone(),
+                        });
+                    }
+                    grammar.add_node(node.clone());
+                }
+                Patch::ExtendOneOf { node, nth, options } => {
+                    let target = match grammar.nodes.get_mut(node) {
+                        Some(target) => target,
+                        None => {
+                            conflict(Conflicts::NodeNotFound(node.clone()));
+                            continue;
+                        }
+                    };
+                    let existing = match nth_one_of(&mut target.rules, &mut nth.clone()) {
+                        Some(existing) => existing,
+                        None => {
+                            conflict(Conflicts::OneOfNotFound {
+                                node: node.clone(),
+                                nth: *nth,
+                            });
+                            continue;
+                        }
+                    };
+                    for option in options {
+                        if existing.iter().any(|o| o.token == option.token) {
+                            conflict(Conflicts::Duplicate {
+                                name: node.clone(),
+                                token: option.token.clone(),
+                            });
+                            continue;
+                        }
+                        existing.push(option.clone());
+                    }
+                }
+                Patch::AddEnumerator(enumerator) => {
+                    if grammar.enumerators.contains_key(&enumerator.name) {
+                        conflict(Conflicts::EnumeratorExists(enumerator.name.clone()));
+                        continue;
+                    }
+                    grammar
+                        .enumerators
+                        .insert(enumerator.name.clone(), enumerator.clone());
+                }
+                Patch::ExtendEnumerator { name, values } => {
+                    let enumerator = match grammar.enumerators.get_mut(name) {
+                        Some(enumerator) => enumerator,
+                        None => {
+                            conflict(Conflicts::EnumeratorNotFound(name.clone()));
+                            continue;
+                        }
+                    };
+                    for value in values {
+                        if enumerator.values.contains(value) {
+                            conflict(Conflicts::Duplicate {
+                                name: name.clone(),
+                                token: value.clone(),
+                            });
+                            continue;
+                        }
+                        enumerator.values.push(value.clone());
+                    }
+                }
+                Patch::AddGlobal { name, kind } => {
+                    if grammar.globals.contains_key(name) {
+                        conflict(Conflicts::GlobalExists(name.clone()));
+                        continue;
+                    }
+                    grammar.globals.insert(name.clone(), kind.clone());
+                }
+                Patch::AddTokens(tokens) => {
+                    for token in tokens {
+                        if !parser.lexer.token_kinds.contains(token) {
+                            parser.lexer.add_token(token.clone());
+                        }
+                    }
+                }
+            }
+            match patch {
+                Patch::OverrideNode(Node { name, .. }) | Patch::ExtendOneOf { node: name, .. } => {
+                    changed.retain(|(node, _)| node != name);
+                    changed.push((name.clone(), dialect.name.clone()));
+                }
+                _ => (),
+            }
+        }
+        // nodes of the dialect can use new instances of the templates
+        if let Err(err) = parser.grammar.instantiate() {
+            conflict(Conflicts::Template(err));
+        }
+    }
+    if conflicts.is_empty() {
+        Ok(parser)
+    } else {
+        Err(conflicts)
+    }
+}
+
+/// Finds the options of the nth one of rule
+///
+/// `nth` is decreased for every one of rule that is skipped
+fn nth_one_of<'a>(rules: &'a mut Rules, nth: &mut usize) -> Option<&'a mut Vec<OneOf>> {
+    for rule in rules {
+        match rule {
+            Rule::IsOneOf { tokens: options } | Rule::UntilOneOf { tokens: options } => {
+                if *nth == 0 {
+                    return Some(options);
+                }
+                *nth -= 1;
+                for option in options {
+                    if let Some(found) = nth_one_of(&mut option.rules, nth) {
+                        return Some(found);
+                    }
+                }
+            }
+            Rule::MaybeOneOf { is_one_of, isnt } => {
+                if *nth == 0 {
+                    return Some(is_one_of);
+                }
+                *nth -= 1;
+                for option in is_one_of {
+                    if let Some(found) = nth_one_of(&mut option.rules, nth) {
+                        return Some(found);
+                    }
+                }
+                if let Some(found) = nth_one_of(isnt, nth) {
+                    return Some(found);
+                }
+            }
+            Rule::Maybe { is, isnt, .. } => {
+                if let Some(found) = nth_one_of(is, nth) {
+                    return Some(found);
+                }
+                if let Some(found) = nth_one_of(isnt, nth) {
+                    return Some(found);
+                }
+            }
+            Rule::Is { rules, .. }
+            | Rule::Isnt { rules, .. }
+            | Rule::While { rules, .. }
+            | Rule::Until { rules, .. }
+            | Rule::Loop { rules }
+            | Rule::Command {
+                command: Commands::Compare { rules, .. },
+            } => {
+                if let Some(found) = nth_one_of(rules, nth) {
+                    return Some(found);
+                }
+            }
+            Rule::Command { .. } | Rule::Debug { .. } => (),
+        }
+    }
+    None
+}
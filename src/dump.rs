@@ -0,0 +1,253 @@
+//! Versioned json dumps
+//!
+//! `serde_json::to_string(&parser)` writes the internal enums as they are, so any change
+//! to them breaks the saved grammars. Dumps made by this module are wrapped in a header
+//! with the format version:
+//!
+//! ```text
+//! { "format": "rparse", "version": N, "parser": { ... } }
+//! ```
+//!
+//! When an older dump is loaded, the migrations between its version and `VERSION` are
+//! applied to the json before it is deserialized. Dumps without a header (plain
+//! `serde_json::to_string(&parser)`) are treated as version 1.
+//!
+//! Preprocessors are saved by their name (`Lexer::add_preprocessor`) and bound again
+//! from a registry when the dump is loaded with `load_with`.
+//!
+//! When changing anything that is serialized, bump `VERSION` and add a migration.
+use crate::{lexer::Preprocessors, Parser};
+
+use serde_json::{Map, Value};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+        use std::fmt;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use core::fmt;
+    }
+}
+
+/// Version of the dumps written by `dump`
+pub const VERSION: u32 = 8;
+
+/// Value of the `format` field in the header
+const FORMAT: &str = "rparse";
+
+/// Changes the json of a parser from one version to the next one
+type Migration = fn(&mut Value) -> Result<(), String>;
+
+/// Migrations from every version to the next one, `MIGRATIONS[0]` migrates from 1 to 2
+const MIGRATIONS: [Migration; VERSION as usize - 1] = [
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8,
+];
+
+/// Dumps a parser as json with a version header
+pub fn dump(parser: &Parser) -> Result<String, DumpError> {
+    let parser = serde_json::to_value(parser).map_err(|err| DumpError::Json(err.to_string()))?;
+    let mut header = Map::new();
+    header.insert("format".to_string(), Value::from(FORMAT));
+    header.insert("version".to_string(), Value::from(VERSION));
+    header.insert("parser".to_string(), parser);
+    serde_json::to_string(&Value::Object(header)).map_err(|err| DumpError::Json(err.to_string()))
+}
+
+/// Loads a parser from a json dump, migrating it if it is older than `VERSION`
+///
+/// Fails if the lexer has named preprocessors, use `load_with` to bind them
+pub fn load(dump: &str) -> Result<Parser, DumpError> {
+    load_with(dump, &Preprocessors::new())
+}
+
+/// Loads a parser from a json dump and binds its preprocessors from the registry
+pub fn load_with(dump: &str, preprocessors: &Preprocessors) -> Result<Parser, DumpError> {
+    let value: Value =
+        serde_json::from_str(dump).map_err(|err| DumpError::Json(err.to_string()))?;
+    let (version, mut parser) = split(value)?;
+    if version == 0 {
+        return Err(DumpError::InvalidVersion(version));
+    }
+    if version > VERSION {
+        return Err(DumpError::NewerVersion {
+            found: version,
+            supported: VERSION,
+        });
+    }
+    for (i, migration) in MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        migration(&mut parser).map_err(|message| DumpError::Migration {
+            from: i as u32 + 1,
+            message,
+        })?;
+    }
+    let mut parser: Parser =
+        serde_json::from_value(parser).map_err(|err| DumpError::Json(err.to_string()))?;
+    parser
+        .lexer
+        .bind_preprocessors(preprocessors)
+        .map_err(DumpError::MissingPreprocessor)?;
+    Ok(parser)
+}
+
+/// Returns the version of a dump without loading it
+pub fn version(dump: &str) -> Result<u32, DumpError> {
+    let value: Value =
+        serde_json::from_str(dump).map_err(|err| DumpError::Json(err.to_string()))?;
+    Ok(split(value)?.0)
+}
+
+/// Splits a dump into its version and the parser
+fn split(value: Value) -> Result<(u32, Value), DumpError> {
+    let mut header = match value {
+        Value::Object(header) => header,
+        _ => return Err(DumpError::NotADump),
+    };
+    match header.get("format") {
+        Some(Value::String(format)) if format == FORMAT => (),
+        Some(_) => return Err(DumpError::NotADump),
+        // no header
+        None => return Ok((1, Value::Object(header))),
+    }
+    let version = match header.get("version").and_then(Value::as_u64) {
+        Some(version) => u32::try_from(version).map_err(|_| DumpError::NotADump)?,
+        None => return Err(DumpError::NotADump),
+    };
+    match header.remove("parser") {
+        Some(parser) => Ok((version, parser)),
+        None => Err(DumpError::NotADump),
+    }
+}
+
+/// Version 2 added name clashes and templates to the grammar
+fn v1_to_v2(parser: &mut Value) -> Result<(), String> {
+    let grammar = match parser.get_mut("grammar") {
+        Some(Value::Object(grammar)) => grammar,
+        _ => return Err("missing grammar".to_string()),
+    };
+    grammar
+        .entry("clashes")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    grammar
+        .entry("templates")
+        .or_insert_with(|| Value::Object(Map::new()));
+    Ok(())
+}
+
+/// Version 3 added patterns to the lexer
+fn v2_to_v3(parser: &mut Value) -> Result<(), String> {
+    let lexer = match parser.get_mut("lexer") {
+        Some(Value::Object(lexer)) => lexer,
+        _ => return Err("missing lexer".to_string()),
+    };
+    lexer
+        .entry("patterns")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// Version 4 added modes to the lexer
+fn v3_to_v4(parser: &mut Value) -> Result<(), String> {
+    let lexer = match parser.get_mut("lexer") {
+        Some(Value::Object(lexer)) => lexer,
+        _ => return Err("missing lexer".to_string()),
+    };
+    lexer
+        .entry("modes")
+        .or_insert_with(|| Value::Object(Map::new()));
+    lexer
+        .entry("actions")
+        .or_insert_with(|| Value::Object(Map::new()));
+    Ok(())
+}
+
+/// Version 5 added literals to the lexer
+fn v4_to_v5(parser: &mut Value) -> Result<(), String> {
+    let lexer = match parser.get_mut("lexer") {
+        Some(Value::Object(lexer)) => lexer,
+        _ => return Err("missing lexer".to_string()),
+    };
+    lexer
+        .entry("literals")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// Version 6 added indentation tracking to the lexer
+fn v5_to_v6(parser: &mut Value) -> Result<(), String> {
+    let lexer = match parser.get_mut("lexer") {
+        Some(Value::Object(lexer)) => lexer,
+        _ => return Err("missing lexer".to_string()),
+    };
+    lexer.entry("indentation").or_insert(Value::Bool(false));
+    Ok(())
+}
+
+/// Version 7 added keywords to the lexer
+fn v6_to_v7(parser: &mut Value) -> Result<(), String> {
+    let lexer = match parser.get_mut("lexer") {
+        Some(Value::Object(lexer)) => lexer,
+        _ => return Err("missing lexer".to_string()),
+    };
+    lexer
+        .entry("keywords")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// Version 8 added the names of the preprocessors to the lexer
+fn v7_to_v8(parser: &mut Value) -> Result<(), String> {
+    let lexer = match parser.get_mut("lexer") {
+        Some(Value::Object(lexer)) => lexer,
+        _ => return Err("missing lexer".to_string()),
+    };
+    lexer
+        .entry("preprocessor_names")
+        .or_insert_with(|| Value::Array(Vec::new()));
+    Ok(())
+}
+
+#[derive(Clone, PartialEq)]
+pub enum DumpError {
+    /// The json could not be read or does not match the parser
+    Json(String),
+    /// The json is not a parser dump
+    NotADump,
+    /// Versions start at 1
+    InvalidVersion(u32),
+    /// The dump was made by a newer version of the library
+    NewerVersion { found: u32, supported: u32 },
+    /// A migration failed
+    Migration { from: u32, message: String },
+    /// The lexer uses a preprocessor that is not in the registry
+    MissingPreprocessor(String),
+}
+
+impl fmt::Debug for DumpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DumpError::Json(err) => write!(f, "Invalid json: {}", err),
+            DumpError::NotADump => write!(f, "Not a grammar dump"),
+            DumpError::InvalidVersion(version) => write!(f, "Invalid version: {}", version),
+            DumpError::NewerVersion { found, supported } => write!(
+                f,
+                "Dump version {} is newer than the supported version {}",
+                found, supported
+            ),
+            DumpError::Migration { from, message } => write!(
+                f,
+                "Migration from version {} to {} failed: {}",
+                from,
+                from + 1,
+                message
+            ),
+            DumpError::MissingPreprocessor(name) => {
+                write!(f, "Missing preprocessor: {}", name)
+            }
+        }
+    }
+}
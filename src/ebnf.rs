@@ -0,0 +1,1129 @@
+//! EBNF grammars
+//!
+//! Imports W3C style (`name ::= ...`) and ISO style (`name = ... ;`) EBNF into a `Parser`.
+//!
+//! ```text
+//! expr   ::= term (("+" | "-") term)*
+//! term   ::= factor (("*" | "/") factor)*
+//! factor ::= number | "(" expr ")"
+//! number ::= "0" | "1" | "2"
+//! ```
+//!
+//! Supported are sequences, alternation `|`, `?`, `*`, `+`, grouping with `()`
+//! and quoted terminals. In ISO style rules `[...]` is an option, `{...}` a repetition
+//! and `,` may be used between elements. Comments are written as `/* */` or `(* *)`.
+//!
+//! Every rule becomes a node with the same name and the first rule is the entry.
+//! Terminals made of letters, digits and `_` are matched as words, everything else
+//! is added to the lexer as a token. Groups that are more than a single element are
+//! lifted into helper nodes (`expr_1`, `expr_2`, ...) so that a partially matched group
+//! does not break the node around it.
+//!
+//! Referenced rules are stored in variables named after them. A rule referenced once
+//! outside of a repetition is a `node` variable, otherwise it is a `list`.
+//!
+//! > note: the rparse parser does not backtrack, alternatives are tried in order and
+//! > repetitions are greedy, so `"a" | "a" "b"` will never match the second alternative
+//!
+//! Constructs that can not be lowered (left recursion, character classes, exceptions, ...)
+//! are reported as errors.
+//!
+//! The other direction is covered by `export` which writes any grammar as W3C style EBNF.
+//! It is meant for documentation, so everything EBNF can not express (variables, `goto`,
+//! `back`, `compare`, ...) ends up in comments.
+use crate::{
+    grammar::{
+        instance_name, Commands, Comparison, MatchToken, Node, OneOf, Parameters, Rule, Rules,
+        VariableKind,
+    },
+    lexer::{ControlTokenKind, TextLocation, TokenKinds},
+    Map, Parser,
+};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+        use std::fmt;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::vec;
+        use alloc::boxed::Box;
+        use alloc::format;
+        use core::fmt;
+    }
+}
+
+/// Imports a parser from an EBNF grammar
+///
+/// All the problems found in the grammar are returned at once
+pub fn import(source: &str) -> Result<Parser, Vec<EbnfError>> {
+    let mut reader = Reader {
+        source,
+        idx: 0,
+        line: 0,
+        column: 0,
+        errors: Vec::new(),
+    };
+    let definitions = match reader.definitions() {
+        Ok(definitions) => definitions,
+        Err(err) => {
+            reader.errors.push(err);
+            return Err(reader.errors);
+        }
+    };
+    let mut errors = reader.errors;
+    check(&definitions, &mut errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut lowering = Lowering {
+        definitions: &definitions,
+        parser: Parser::new(),
+        helpers: 0,
+    };
+    lowering.parser.parser.entry = definitions[0].name.clone();
+    for definition in &definitions {
+        lowering.helpers = 0;
+        lowering.node(&definition.name, definition.name.clone(), &definition.expr);
+    }
+    Ok(lowering.parser)
+}
+
+/// Exports a parser as W3C style EBNF
+///
+/// The entry node comes first, the rest of the nodes and enumerators are sorted by name.
+/// Builtin and complex tokens are written in uppercase (`TEXT`, `EOL`, `INT`, ...)
+pub fn export(parser: &Parser) -> String {
+    let grammar = &parser.grammar;
+    let mut exporter = Exporter {
+        lexical: Vec::new(),
+    };
+    let mut names = grammar.nodes.keys().collect::<Vec<_>>();
+    names.sort();
+    if let Some(idx) = names.iter().position(|name| **name == parser.parser.entry) {
+        let entry = names.remove(idx);
+        names.insert(0, entry);
+    }
+    let mut enumerators = grammar.enumerators.keys().collect::<Vec<_>>();
+    enumerators.sort();
+
+    let mut body = String::new();
+    for name in names {
+        body.push('\n');
+        exporter.node(&grammar.nodes[name], &mut body);
+    }
+    for name in enumerators {
+        let enumerator = &grammar.enumerators[name];
+        let values = enumerator
+            .values
+            .iter()
+            .map(|value| exporter.token(value))
+            .collect::<Vec<_>>();
+        body.push_str(&format!(
+            "\n{} ::= {}\n",
+            rule_name(name),
+            values.join("\n    | ")
+        ));
+    }
+
+    let mut out = String::from("/* exported from an rparse grammar */\n");
+    if !exporter.lexical.is_empty() {
+        exporter.lexical.sort();
+        out.push_str(&format!(
+            "/* provided by the lexer: {} */\n",
+            exporter.lexical.join(", ")
+        ));
+    }
+    out.push_str(&body);
+    out
+}
+
+pub struct EbnfError {
+    pub kind: EbnfErrors,
+    pub location: TextLocation,
+}
+
+impl fmt::Debug for EbnfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} at line {} column {}",
+            self.kind, self.location.line, self.location.column
+        )
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum EbnfErrors {
+    /// Terminal that is missing its closing quote
+    UnterminatedString,
+    /// Comment that is missing its end
+    UnterminatedComment,
+    /// Something else was expected
+    Expected { expected: String, found: String },
+    /// Terminal without any characters
+    EmptyTerminal,
+    /// Rule defined more than once
+    DuplicateRule(String),
+    /// Rule that is referenced but never defined
+    UndefinedRule(String),
+    /// Rules that reach themselves without consuming a token
+    ///
+    /// Contains the path from the rule back to itself
+    LeftRecursion(Vec<String>),
+    /// Repetition of something that can match nothing would loop forever
+    NullableRepetition(String),
+    /// Construct that has no equivalent in the rparse grammar
+    Unsupported(String),
+}
+
+impl fmt::Debug for EbnfErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EbnfErrors::UnterminatedString => write!(f, "Unterminated string"),
+            EbnfErrors::UnterminatedComment => write!(f, "Unterminated comment"),
+            EbnfErrors::Expected { expected, found } => {
+                write!(f, "Expected {}, found {}", expected, found)
+            }
+            EbnfErrors::EmptyTerminal => write!(f, "Empty terminal"),
+            EbnfErrors::DuplicateRule(name) => write!(f, "Rule defined twice: {}", name),
+            EbnfErrors::UndefinedRule(name) => write!(f, "Rule is not defined: {}", name),
+            EbnfErrors::LeftRecursion(path) => {
+                write!(f, "Left recursion can not be parsed: {}", path.join(" -> "))
+            }
+            EbnfErrors::NullableRepetition(name) => write!(
+                f,
+                "Repetition in rule {} can match nothing and would never end",
+                name
+            ),
+            EbnfErrors::Unsupported(what) => write!(f, "Unsupported construct: {}", what),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    /// Quoted terminal
+    Terminal(String),
+    /// Reference to a rule
+    Rule(String, TextLocation),
+    Seq(Vec<Expr>),
+    Alt(Vec<Expr>),
+    /// `x?` or `[x]`
+    Optional(Box<Expr>),
+    /// `x*` or `{x}`
+    Repeat(Box<Expr>),
+    /// `x+`
+    Repeat1(Box<Expr>),
+    /// Placeholder for something that has already been reported
+    Invalid,
+}
+
+struct Definition {
+    name: String,
+    expr: Expr,
+    location: TextLocation,
+}
+
+/// W3C and ISO styles only differ in the meaning of `[` and `.`
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    W3c,
+    Iso,
+}
+
+struct Reader<'a> {
+    source: &'a str,
+    idx: usize,
+    line: usize,
+    column: usize,
+    /// Problems that do not stop the reader
+    errors: Vec<EbnfError>,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.source[self.idx..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.idx += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if !self.rest().starts_with(text) {
+            return false;
+        }
+        for _ in text.chars() {
+            self.bump();
+        }
+        true
+    }
+
+    fn location(&self) -> TextLocation {
+        TextLocation::new(self.line, self.column)
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(c) => format!("{:?}", c),
+            None => "end of file".to_string(),
+        }
+    }
+
+    fn expected(&self, expected: &str) -> EbnfError {
+        EbnfError {
+            kind: EbnfErrors::Expected {
+                expected: expected.to_string(),
+                found: self.found(),
+            },
+            location: self.location(),
+        }
+    }
+
+    fn unsupported(&mut self, what: String, location: TextLocation) -> Expr {
+        self.errors.push(EbnfError {
+            kind: EbnfErrors::Unsupported(what),
+            location,
+        });
+        Expr::Invalid
+    }
+
+    /// Skips whitespace and comments
+    fn skip(&mut self) -> Result<(), EbnfError> {
+        loop {
+            let end = if self.rest().starts_with("/*") {
+                "*/"
+            } else if self.rest().starts_with("(*") {
+                "*)"
+            } else {
+                match self.peek() {
+                    Some(c) if c.is_whitespace() => {
+                        self.bump();
+                        continue;
+                    }
+                    _ => return Ok(()),
+                }
+            };
+            let location = self.location();
+            self.bump();
+            self.bump();
+            while !self.eat(end) {
+                if self.bump().is_none() {
+                    return Err(EbnfError {
+                        kind: EbnfErrors::UnterminatedComment,
+                        location,
+                    });
+                }
+            }
+        }
+    }
+
+    fn name(&mut self) -> Option<String> {
+        if self.peek() == Some('<') {
+            let end = self.rest().find('>')?;
+            let name = self.rest()[1..end].trim().to_string();
+            while self.bump() != Some('>') {}
+            return Some(name);
+        }
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            let is_name = c.is_alphanumeric()
+                || c == '_'
+                // dashes are allowed inside of names but not at the end
+                || (c == '-'
+                    && !name.is_empty()
+                    && self.rest()[1..].starts_with(|c: char| c.is_alphanumeric()));
+            if !is_name || (name.is_empty() && c.is_numeric()) {
+                break;
+            }
+            name.push(c);
+            self.bump();
+        }
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+
+    /// Checks if a new definition starts here
+    fn definition_ahead(&mut self) -> bool {
+        let (idx, line, column) = (self.idx, self.line, self.column);
+        let result = self.name().is_some()
+            && self.skip().is_ok()
+            && (self.rest().starts_with("::=")
+                || self.rest().starts_with(":=")
+                || (self.rest().starts_with('=') && !self.rest().starts_with("==")));
+        self.idx = idx;
+        self.line = line;
+        self.column = column;
+        result
+    }
+
+    fn definitions(&mut self) -> Result<Vec<Definition>, EbnfError> {
+        let mut definitions: Vec<Definition> = Vec::new();
+        loop {
+            self.skip()?;
+            if self.peek().is_none() {
+                break;
+            }
+            let location = self.location();
+            let name = match self.name() {
+                Some(name) => name,
+                None => return Err(self.expected("rule name")),
+            };
+            self.skip()?;
+            let style = if self.eat("::=") {
+                Style::W3c
+            } else if self.eat(":=") || self.eat("=") {
+                Style::Iso
+            } else {
+                return Err(self.expected("`::=` or `=`"));
+            };
+            let expr = self.alternation(style)?;
+            self.skip()?;
+            if !self.eat(";") && style == Style::Iso {
+                self.eat(".");
+            }
+            if definitions.iter().any(|d| d.name == name) {
+                self.errors.push(EbnfError {
+                    kind: EbnfErrors::DuplicateRule(name),
+                    location,
+                });
+                continue;
+            }
+            definitions.push(Definition {
+                name,
+                expr,
+                location,
+            });
+        }
+        if definitions.is_empty() {
+            return Err(self.expected("rule"));
+        }
+        Ok(definitions)
+    }
+
+    fn alternation(&mut self, style: Style) -> Result<Expr, EbnfError> {
+        let mut alternatives = vec![self.sequence(style)?];
+        loop {
+            self.skip()?;
+            if !self.eat("|") {
+                break;
+            }
+            alternatives.push(self.sequence(style)?);
+        }
+        if alternatives.len() == 1 {
+            return Ok(alternatives.pop().unwrap());
+        }
+        Ok(Expr::Alt(alternatives))
+    }
+
+    fn sequence(&mut self, style: Style) -> Result<Expr, EbnfError> {
+        let mut items = Vec::new();
+        loop {
+            self.skip()?;
+            match self.peek() {
+                None | Some('|' | ')' | ']' | '}' | ';') => break,
+                Some('.') if style == Style::Iso => break,
+                _ if self.definition_ahead() => break,
+                _ => (),
+            }
+            items.push(self.postfix(style)?);
+            self.skip()?;
+            if style == Style::Iso {
+                self.eat(",");
+            }
+        }
+        if items.len() == 1 {
+            return Ok(items.pop().unwrap());
+        }
+        Ok(Expr::Seq(items))
+    }
+
+    fn postfix(&mut self, style: Style) -> Result<Expr, EbnfError> {
+        let mut expr = self.primary(style)?;
+        loop {
+            self.skip()?;
+            expr = match self.peek() {
+                Some('?') => Expr::Optional(Box::new(expr)),
+                Some('*') => Expr::Repeat(Box::new(expr)),
+                Some('+') => Expr::Repeat1(Box::new(expr)),
+                Some('-') => {
+                    let location = self.location();
+                    self.bump();
+                    self.skip()?;
+                    self.primary(style)?;
+                    self.unsupported("exception `-`".to_string(), location);
+                    continue;
+                }
+                _ => return Ok(expr),
+            };
+            self.bump();
+        }
+    }
+
+    fn primary(&mut self, style: Style) -> Result<Expr, EbnfError> {
+        let location = self.location();
+        match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.bump();
+                let end = match self.rest().find(quote) {
+                    Some(end) => end,
+                    None => {
+                        return Err(EbnfError {
+                            kind: EbnfErrors::UnterminatedString,
+                            location,
+                        })
+                    }
+                };
+                let terminal = self.rest()[..end].to_string();
+                while self.bump() != Some(quote) {}
+                if terminal.is_empty() {
+                    self.errors.push(EbnfError {
+                        kind: EbnfErrors::EmptyTerminal,
+                        location,
+                    });
+                    return Ok(Expr::Invalid);
+                }
+                Ok(Expr::Terminal(terminal))
+            }
+            Some('(') => {
+                self.bump();
+                let expr = self.alternation(style)?;
+                self.skip()?;
+                if !self.eat(")") {
+                    return Err(self.expected("`)`"));
+                }
+                Ok(expr)
+            }
+            Some('[') if style == Style::Iso => {
+                self.bump();
+                let expr = self.alternation(style)?;
+                self.skip()?;
+                if !self.eat("]") {
+                    return Err(self.expected("`]`"));
+                }
+                Ok(Expr::Optional(Box::new(expr)))
+            }
+            Some('[') => {
+                // `]` right after `[` (or `[^`) is part of the class
+                let end = self.rest().get(2..).and_then(|rest| rest.find(']'));
+                let class = match end {
+                    Some(end) => self.rest()[..end + 3].to_string(),
+                    None => return Err(self.expected("`]`")),
+                };
+                for _ in class.chars() {
+                    self.bump();
+                }
+                Ok(self.unsupported(format!("character class {}", class), location))
+            }
+            Some('{') => {
+                self.bump();
+                let expr = self.alternation(style)?;
+                self.skip()?;
+                if !self.eat("}") {
+                    return Err(self.expected("`}`"));
+                }
+                Ok(Expr::Repeat(Box::new(expr)))
+            }
+            Some('#') => {
+                self.bump();
+                let mut code = String::from("#");
+                while let Some(c) = self.peek().filter(|c| c.is_alphanumeric()) {
+                    code.push(c);
+                    self.bump();
+                }
+                Ok(self.unsupported(format!("character {}", code), location))
+            }
+            Some('?') if style == Style::Iso => {
+                self.bump();
+                while self.bump().is_some_and(|c| c != '?') {}
+                Ok(self.unsupported("special sequence".to_string(), location))
+            }
+            _ => match self.name() {
+                Some(name) => Ok(Expr::Rule(name, location)),
+                None => Err(self.expected("expression")),
+            },
+        }
+    }
+}
+
+/// Reports everything that can not be lowered into nodes
+fn check(definitions: &[Definition], errors: &mut Vec<EbnfError>) {
+    for definition in definitions {
+        undefined(&definition.expr, definitions, errors);
+    }
+
+    // find the rules that can match nothing
+    let mut nullable: Vec<&str> = Vec::new();
+    loop {
+        let mut changed = false;
+        for definition in definitions {
+            if !nullable.contains(&definition.name.as_str())
+                && is_nullable(&definition.expr, &nullable)
+            {
+                nullable.push(&definition.name);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // rules that can appear at the start of each rule
+    let mut edges: Map<&str, Vec<&str>> = Map::new();
+    for definition in definitions {
+        let mut first = Vec::new();
+        leftmost(&definition.expr, &nullable, &mut first);
+        edges.insert(&definition.name, first);
+    }
+    let mut reported: Vec<&str> = Vec::new();
+    for definition in definitions {
+        if reported.contains(&definition.name.as_str()) {
+            continue;
+        }
+        let mut path = vec![definition.name.as_str()];
+        let mut visited = Vec::new();
+        if cycle(&edges, &definition.name, &mut path, &mut visited) {
+            path.push(&definition.name);
+            reported.extend(path.iter().copied());
+            errors.push(EbnfError {
+                kind: EbnfErrors::LeftRecursion(path.iter().map(|s| s.to_string()).collect()),
+                location: definition.location.clone(),
+            });
+        }
+    }
+
+    for definition in definitions {
+        if nullable_repetition(&definition.expr, &nullable) {
+            errors.push(EbnfError {
+                kind: EbnfErrors::NullableRepetition(definition.name.clone()),
+                location: definition.location.clone(),
+            });
+        }
+    }
+}
+
+fn undefined(expr: &Expr, definitions: &[Definition], errors: &mut Vec<EbnfError>) {
+    match expr {
+        Expr::Rule(name, location) => {
+            if !definitions.iter().any(|d| d.name == *name) {
+                errors.push(EbnfError {
+                    kind: EbnfErrors::UndefinedRule(name.clone()),
+                    location: location.clone(),
+                });
+            }
+        }
+        Expr::Seq(items) | Expr::Alt(items) => {
+            for item in items {
+                undefined(item, definitions, errors);
+            }
+        }
+        Expr::Optional(inner) | Expr::Repeat(inner) | Expr::Repeat1(inner) => {
+            undefined(inner, definitions, errors)
+        }
+        Expr::Terminal(_) | Expr::Invalid => (),
+    }
+}
+
+fn is_nullable(expr: &Expr, nullable: &[&str]) -> bool {
+    match expr {
+        Expr::Terminal(_) | Expr::Invalid => false,
+        Expr::Rule(name, _) => nullable.contains(&name.as_str()),
+        Expr::Seq(items) => items.iter().all(|item| is_nullable(item, nullable)),
+        Expr::Alt(items) => items.iter().any(|item| is_nullable(item, nullable)),
+        Expr::Optional(_) | Expr::Repeat(_) => true,
+        Expr::Repeat1(inner) => is_nullable(inner, nullable),
+    }
+}
+
+/// Collects the rules that can be matched before any token is consumed
+fn leftmost<'a>(expr: &'a Expr, nullable: &[&str], first: &mut Vec<&'a str>) {
+    match expr {
+        Expr::Rule(name, _) => first.push(name),
+        Expr::Seq(items) => {
+            for item in items {
+                leftmost(item, nullable, first);
+                if !is_nullable(item, nullable) {
+                    break;
+                }
+            }
+        }
+        Expr::Alt(items) => {
+            for item in items {
+                leftmost(item, nullable, first);
+            }
+        }
+        Expr::Optional(inner) | Expr::Repeat(inner) | Expr::Repeat1(inner) => {
+            leftmost(inner, nullable, first)
+        }
+        Expr::Terminal(_) | Expr::Invalid => (),
+    }
+}
+
+/// Searches for a path from the last rule in path back to the first one
+fn cycle<'a>(
+    edges: &Map<&'a str, Vec<&'a str>>,
+    start: &str,
+    path: &mut Vec<&'a str>,
+    visited: &mut Vec<&'a str>,
+) -> bool {
+    let current = *path.last().unwrap();
+    for next in edges.get(current).into_iter().flatten() {
+        if *next == start {
+            return true;
+        }
+        if visited.contains(next) {
+            continue;
+        }
+        visited.push(next);
+        path.push(next);
+        if cycle(edges, start, path, visited) {
+            return true;
+        }
+        path.pop();
+    }
+    false
+}
+
+fn nullable_repetition(expr: &Expr, nullable: &[&str]) -> bool {
+    match expr {
+        Expr::Repeat(inner) | Expr::Repeat1(inner) => {
+            is_nullable(inner, nullable) || nullable_repetition(inner, nullable)
+        }
+        Expr::Optional(inner) => nullable_repetition(inner, nullable),
+        Expr::Seq(items) | Expr::Alt(items) => {
+            items.iter().any(|item| nullable_repetition(item, nullable))
+        }
+        Expr::Terminal(_) | Expr::Rule(..) | Expr::Invalid => false,
+    }
+}
+
+struct Lowering<'a> {
+    definitions: &'a [Definition],
+    parser: Parser,
+    /// Number of helper nodes of the current rule
+    helpers: usize,
+}
+
+/// How many times a variable is set and if it is set in a repetition
+type Scope = Map<String, (usize, bool)>;
+
+impl<'a> Lowering<'a> {
+    fn node(&mut self, owner: &str, name: String, expr: &Expr) {
+        let mut scope = Scope::new();
+        let rules = self.sequence(owner, core::slice::from_ref(expr), false, &mut scope);
+        let variables = scope
+            .into_iter()
+            .map(|(name, (count, repeated))| {
+                let kind = if count == 1 && !repeated {
+                    VariableKind::Node
+                } else {
+                    VariableKind::NodeList
+                };
+                (name, kind)
+            })
+            .collect();
+        self.parser.grammar.add_node(Node {
+            name,
+            rules,
+            variables,
+        });
+    }
+
+    fn helper(&mut self, owner: &str) -> String {
+        loop {
+            self.helpers += 1;
+            let name = format!("{}_{}", owner, self.helpers);
+            if !self.definitions.iter().any(|d| d.name == name) {
+                return name;
+            }
+        }
+    }
+
+    fn terminal(&mut self, terminal: &str) -> MatchToken {
+        if terminal.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return MatchToken::Word(terminal.to_string());
+        }
+        if !self.parser.lexer.token_kinds.iter().any(|t| t == terminal) {
+            self.parser.lexer.add_token(terminal.to_string());
+        }
+        MatchToken::Token(TokenKinds::Token(terminal.to_string()))
+    }
+
+    /// Lowers an expression that can be matched by a single rule
+    fn atom(
+        &mut self,
+        owner: &str,
+        expr: &Expr,
+        repeated: bool,
+        scope: &mut Scope,
+    ) -> (MatchToken, Vec<Parameters>) {
+        let name = match expr {
+            Expr::Terminal(terminal) => return (self.terminal(terminal), Vec::new()),
+            Expr::Seq(items) if items.len() == 1 => {
+                return self.atom(owner, &items[0], repeated, scope)
+            }
+            Expr::Rule(name, _) => name.clone(),
+            _ => {
+                let name = self.helper(owner);
+                self.node(owner, name.clone(), expr);
+                name
+            }
+        };
+        let entry = scope.entry(name.clone()).or_insert((0, false));
+        entry.0 += 1;
+        entry.1 |= repeated;
+        (MatchToken::Node(name.clone()), vec![Parameters::Set(name)])
+    }
+
+    fn options(
+        &mut self,
+        owner: &str,
+        alternatives: &[&Expr],
+        repeated: bool,
+        scope: &mut Scope,
+    ) -> Vec<OneOf> {
+        alternatives
+            .iter()
+            .map(|alternative| {
+                let (token, parameters) = self.atom(owner, alternative, repeated, scope);
+                OneOf {
+                    token,
+                    rules: Vec::new(),
+                    parameters,
+                }
+            })
+            .collect()
+    }
+
+    fn optional(&mut self, owner: &str, expr: &Expr, repeated: bool, scope: &mut Scope) -> Rule {
+        if let Expr::Alt(alternatives) = expr {
+            let alternatives = alternatives
+                .iter()
+                .filter(|alternative| **alternative != Expr::Seq(Vec::new()))
+                .collect::<Vec<_>>();
+            return Rule::MaybeOneOf {
+                is_one_of: self.options(owner, &alternatives, repeated, scope),
+                isnt: Vec::new(),
+            };
+        }
+        let (token, parameters) = self.atom(owner, expr, repeated, scope);
+        Rule::Maybe {
+            token,
+            is: Vec::new(),
+            isnt: Vec::new(),
+            parameters,
+        }
+    }
+
+    fn sequence(
+        &mut self,
+        owner: &str,
+        items: &[Expr],
+        repeated: bool,
+        scope: &mut Scope,
+    ) -> Rules {
+        let mut rules = Vec::new();
+        for item in items {
+            match item {
+                Expr::Seq(items) => rules.extend(self.sequence(owner, items, repeated, scope)),
+                Expr::Optional(inner) => rules.push(self.optional(owner, inner, repeated, scope)),
+                Expr::Alt(alternatives) => {
+                    // an empty alternative makes the whole group optional
+                    if alternatives.contains(&Expr::Seq(Vec::new())) {
+                        rules.push(self.optional(owner, item, repeated, scope));
+                        continue;
+                    }
+                    let alternatives = alternatives.iter().collect::<Vec<_>>();
+                    rules.push(Rule::IsOneOf {
+                        tokens: self.options(owner, &alternatives, repeated, scope),
+                    });
+                }
+                Expr::Repeat(inner) => {
+                    let (token, parameters) = self.atom(owner, inner, true, scope);
+                    rules.push(Rule::While {
+                        token,
+                        rules: Vec::new(),
+                        parameters,
+                    });
+                }
+                Expr::Repeat1(inner) => {
+                    let (token, parameters) = self.atom(owner, inner, true, scope);
+                    rules.push(Rule::Is {
+                        token: token.clone(),
+                        rules: Vec::new(),
+                        parameters: parameters.clone(),
+                    });
+                    rules.push(Rule::While {
+                        token,
+                        rules: Vec::new(),
+                        parameters,
+                    });
+                }
+                Expr::Terminal(_) | Expr::Rule(..) | Expr::Invalid => {
+                    let (token, parameters) = self.atom(owner, item, repeated, scope);
+                    rules.push(Rule::Is {
+                        token,
+                        rules: Vec::new(),
+                        parameters,
+                    });
+                }
+            }
+        }
+        rules
+    }
+}
+
+/// Writes names that can not be read back in angle brackets
+fn rule_name(name: &str) -> String {
+    let mut chars = name.chars();
+    let plain = chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        format!("<{}>", name)
+    }
+}
+
+fn comment(text: &str) -> String {
+    format!("/* {} */", text.replace("*/", "* /"))
+}
+
+/// Puts parentheses around items unless it is a single element
+fn group(items: Vec<String>, suffix: &str) -> String {
+    if items.len() == 1 && !items[0].contains(' ') {
+        return format!("{}{}", items[0], suffix);
+    }
+    format!("( {} ){}", items.join(" "), suffix)
+}
+
+fn alternatives(alternatives: Vec<Vec<String>>, suffix: &str) -> String {
+    if alternatives.len() == 1 {
+        return group(alternatives.into_iter().next().unwrap(), suffix);
+    }
+    let alternatives = alternatives
+        .into_iter()
+        .map(|items| items.join(" "))
+        .collect::<Vec<_>>();
+    format!("( {} ){}", alternatives.join(" | "), suffix)
+}
+
+struct Exporter {
+    /// Tokens that come from the lexer instead of the grammar
+    lexical: Vec<String>,
+}
+
+impl Exporter {
+    fn node(&mut self, node: &Node, out: &mut String) {
+        if !node.variables.is_empty() {
+            let mut variables = node.variables.iter().collect::<Vec<_>>();
+            variables.sort_by(|a, b| a.0.cmp(b.0));
+            let variables = variables
+                .iter()
+                .map(|(name, kind)| format!("{}: {:?}", name, kind))
+                .collect::<Vec<_>>();
+            out.push_str(&comment(&format!("variables: {}", variables.join(", "))));
+            out.push('\n');
+        }
+        let name = rule_name(&node.name);
+        let items = self.rules(&node.rules);
+        let indent = " ".repeat(name.len() + 5);
+        out.push_str(&format!(
+            "{} ::= {}\n",
+            name,
+            items.join(&format!("\n{}", indent))
+        ));
+    }
+
+    fn lexical(&mut self, name: String) -> String {
+        if !self.lexical.contains(&name) {
+            self.lexical.push(name.clone());
+        }
+        name
+    }
+
+    fn token(&mut self, token: &MatchToken) -> String {
+        match token {
+            MatchToken::Token(TokenKinds::Token(token)) | MatchToken::Word(token) => {
+                if token.contains('"') {
+                    format!("'{}'", token)
+                } else {
+                    format!("\"{}\"", token)
+                }
+            }
+            MatchToken::Token(TokenKinds::Complex(complex)) => self.lexical(complex.to_uppercase()),
+            MatchToken::Token(TokenKinds::Text) => self.lexical("TEXT".to_string()),
+            MatchToken::Token(TokenKinds::Whitespace) => self.lexical("WHITESPACE".to_string()),
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eol)) => {
+                self.lexical("EOL".to_string())
+            }
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eof)) => {
+                self.lexical("EOF".to_string())
+            }
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Indent)) => {
+                self.lexical("INDENT".to_string())
+            }
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Dedent)) => {
+                self.lexical("DEDENT".to_string())
+            }
+            MatchToken::Any => self.lexical("ANY".to_string()),
+            MatchToken::Node(name) | MatchToken::Enumerator(name) => rule_name(name),
+            MatchToken::Template(name, arguments) => rule_name(&instance_name(name, arguments)),
+        }
+    }
+
+    /// Matched token followed by the parameters that change the flow of the parser
+    fn matched(
+        &mut self,
+        token: &MatchToken,
+        parameters: &[Parameters],
+        rules: &Rules,
+    ) -> Vec<String> {
+        let mut items = vec![self.token(token)];
+        for parameter in parameters {
+            match parameter {
+                Parameters::Back(steps) => items.push(comment(&format!("back {}", steps))),
+                Parameters::Return => items.push(comment("return")),
+                Parameters::Break(n) => items.push(comment(&format!("break {}", n))),
+                Parameters::Goto(label) => items.push(comment(&format!("goto {}", label))),
+                _ => (),
+            }
+        }
+        items.extend(self.rules(rules));
+        items
+    }
+
+    fn options(&mut self, options: &[OneOf]) -> Vec<Vec<String>> {
+        options
+            .iter()
+            .map(|option| self.matched(&option.token, &option.parameters, &option.rules))
+            .collect()
+    }
+
+    fn rules(&mut self, rules: &Rules) -> Vec<String> {
+        let mut items = Vec::new();
+        for rule in rules {
+            match rule {
+                Rule::Is {
+                    token,
+                    rules,
+                    parameters,
+                } => items.extend(self.matched(token, parameters, rules)),
+                Rule::Isnt { token, rules, .. } => {
+                    items.push(comment(&format!("not {}", self.token(token))));
+                    items.extend(self.rules(rules));
+                }
+                Rule::IsOneOf { tokens } => {
+                    let options = self.options(tokens);
+                    items.push(alternatives(options, ""));
+                }
+                Rule::Maybe {
+                    token,
+                    is,
+                    isnt,
+                    parameters,
+                } => {
+                    let matched = self.matched(token, parameters, is);
+                    if isnt.is_empty() {
+                        items.push(group(matched, "?"));
+                    } else {
+                        let otherwise = self.rules(isnt);
+                        items.push(alternatives(vec![matched, otherwise], ""));
+                    }
+                }
+                Rule::MaybeOneOf { is_one_of, isnt } => {
+                    let mut options = self.options(is_one_of);
+                    if isnt.is_empty() {
+                        items.push(alternatives(options, "?"));
+                    } else {
+                        options.push(self.rules(isnt));
+                        items.push(alternatives(options, ""));
+                    }
+                }
+                Rule::While {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    let matched = self.matched(token, parameters, rules);
+                    items.push(group(matched, "*"));
+                }
+                Rule::Loop { rules } => {
+                    let mut looped = vec![comment("loop")];
+                    looped.extend(self.rules(rules));
+                    items.push(group(looped, "*"));
+                }
+                Rule::Until {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    let any = self.token(&MatchToken::Any);
+                    items.push(format!("( {} - {} )*", any, self.token(token)));
+                    items.extend(self.matched(token, parameters, rules));
+                }
+                Rule::UntilOneOf { tokens } => {
+                    let any = self.token(&MatchToken::Any);
+                    let stops = tokens
+                        .iter()
+                        .map(|option| vec![self.token(&option.token)])
+                        .collect();
+                    items.push(format!("( {} - {} )*", any, alternatives(stops, "")));
+                    let options = self.options(tokens);
+                    items.push(alternatives(options, ""));
+                }
+                Rule::Command { command } => match command {
+                    Commands::Compare {
+                        left,
+                        right,
+                        comparison,
+                        rules,
+                    } => {
+                        let comparison = match comparison {
+                            Comparison::Equal => "==",
+                            Comparison::NotEqual => "!=",
+                            Comparison::GreaterThan => ">",
+                            Comparison::LessThan => "<",
+                            Comparison::GreaterThanOrEqual => ">=",
+                            Comparison::LessThanOrEqual => "<=",
+                        };
+                        let mut compared =
+                            vec![comment(&format!("if {} {} {}", left, comparison, right))];
+                        compared.extend(self.rules(rules));
+                        items.push(group(compared, "?"));
+                    }
+                    Commands::Error { message } => {
+                        items.push(comment(&format!("error {:?}", message)))
+                    }
+                    Commands::Goto { label } => items.push(comment(&format!("goto {}", label))),
+                    Commands::Label { name } => items.push(comment(&format!("label {}", name))),
+                    Commands::HardError { .. } | Commands::Print { .. } => (),
+                },
+                Rule::Debug { .. } => (),
+            }
+        }
+        items
+    }
+}
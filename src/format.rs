@@ -0,0 +1,1728 @@
+//! Textual grammar format
+//!
+//! Grammars can be written as plain text instead of building `grammar::Rule` trees by hand
+//! or loading a json dump. The loader produces a ready `Parser` (lexer tokens, grammar and entry).
+//!
+//! ```text
+//! // lexer tokens
+//! tokens "=" ":" ";" "+" "-" "\"";
+//! // lexer patterns, see the `pattern` module
+//! pattern int "\\d+";
+//! // strings and comments, see `lexer::Literal`
+//! literal comment line_comment("//");
+//! literal char string("'", escape: "\\");
+//! // tokens that only match whole words, `ignore_case` matches `NULL` as well
+//! keywords "fn" "return";
+//! keywords ignore_case "null";
+//! // indent and dedent tokens, see `lexer::Lexer::set_indentation`
+//! indentation;
+//! // preprocessor from the registry of `load_with`, see `lexer::Preprocessors`
+//! preprocessor "numbers";
+//! // lexer modes, see `lexer::LexerMode`
+//! on "\"" push string;
+//! mode string {
+//!     tokens "\"" "\\\"";
+//!     merge string;
+//!     on "\"" pop;
+//! }
+//! entry KWLet;
+//!
+//! enum operators { "+", "-" }
+//!
+//! node KWLet {
+//!     var ident: node;
+//!     var value: node;
+//!
+//!     is 'let' [hard_error(true)];
+//!     is #text [set(ident)];
+//!     maybe "=" {
+//!         is value [set(value)];
+//!     }
+//!     maybe ";";
+//! }
+//! ```
+//!
+//! Tokens that can be matched:
+//!
+//! - `"+"` - token defined in the lexer
+//! - `'let'` - constant word
+//! - `value` - node
+//! - `@operators` - enumerator
+//! - `%int` - complex token generated by a pattern or a preprocessor
+//! - `#text`, `#whitespace`, `#eol`, `#eof`, `#indent`, `#dedent`, `#any` - builtin token kinds
+//! - `list<value, ",">` - template instantiated with arguments
+//!
+//! Templates are nodes with parameters, the parameters are used like nodes:
+//!
+//! ```text
+//! template list<Item, Sep> {
+//!     var items: list;
+//!
+//!     while Item [set(items)] {
+//!         maybe Sep;
+//!     }
+//! }
+//! ```
+//!
+//! Names that are not plain identifiers can be written in backticks: `` `my node` ``
+use crate::{
+    grammar::{
+        Commands, Comparison, Enumerator, MatchToken, Node, OneOf, Parameters, Rule, Rules,
+        Template, TemplateError, VariableKind,
+    },
+    lexer::{
+        ControlTokenKind, LexerMode, Literal, ModeAction, Preprocessors, TextLocation, TokenKinds,
+        TokenPattern,
+    },
+    pattern::PatternError,
+    Map, Parser,
+};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+        use std::fmt;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::format;
+        use core::fmt;
+    }
+}
+
+/// Loads a parser from the textual grammar format
+///
+/// Fails if the grammar uses preprocessors, use `load_with` to bind them
+pub fn load(source: &str) -> Result<Parser, FormatError> {
+    load_with(source, &Preprocessors::new())
+}
+
+/// Loads a parser and binds its `preprocessor` statements from the registry
+pub fn load_with(source: &str, preprocessors: &Preprocessors) -> Result<Parser, FormatError> {
+    let tokens = scan(source)?;
+    let mut loader = Loader {
+        tokens,
+        idx: 0,
+        parser: Parser::new(),
+        uses: Vec::new(),
+        preprocessors,
+    };
+    loader.load()?;
+    Ok(loader.parser)
+}
+
+/// Prints a parser in the textual grammar format
+///
+/// The output is stable (nodes, enumerators and variables are sorted by name)
+/// and loading it back results in an equivalent parser
+///
+/// Preprocessors are printed by their name, the ones without a name are lost
+pub fn print(parser: &Parser) -> String {
+    let mut printer = Printer {
+        out: String::new(),
+        indent: 0,
+    };
+    printer.parser(parser);
+    printer.out
+}
+
+pub struct FormatError {
+    pub kind: FormatErrors,
+    pub location: TextLocation,
+}
+
+impl fmt::Debug for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} at line {} column {}",
+            self.kind, self.location.line, self.location.column
+        )
+    }
+}
+
+#[derive(Clone)]
+pub enum FormatErrors {
+    /// Character that can not start any token
+    UnexpectedChar(char),
+    /// String, word or name that is missing its closing quote
+    UnterminatedString,
+    /// Unknown escape sequence in a string
+    InvalidEscape(char),
+    /// Something else was expected
+    Expected { expected: String, found: String },
+    /// Unknown statement at the top level
+    UnknownStatement(String),
+    /// Unknown rule inside of a node
+    UnknownRule(String),
+    /// Unknown parameter of a rule
+    UnknownParameter(String),
+    /// Unknown variable kind
+    UnknownVariableKind(String),
+    /// Unknown builtin token kind (`#...`)
+    UnknownBuiltin(String),
+    /// Unknown kind of lexer literal
+    UnknownLiteral(String),
+    /// Number could not be parsed
+    InvalidNumber(String),
+    /// Node defined more than once
+    DuplicateNode(String),
+    /// Enumerator defined more than once
+    DuplicateEnumerator(String),
+    /// Variable declared more than once
+    DuplicateVariable(String),
+    /// Template defined more than once
+    DuplicateTemplate(String),
+    /// Lexer mode defined more than once
+    DuplicateMode(String),
+    /// Template could not be instantiated
+    Template(TemplateError),
+    /// Lexer pattern could not be compiled
+    Pattern(PatternError),
+    /// Preprocessor is not in the registry
+    MissingPreprocessor(String),
+}
+
+impl fmt::Debug for FormatErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatErrors::UnexpectedChar(c) => write!(f, "Unexpected character {:?}", c),
+            FormatErrors::UnterminatedString => write!(f, "Unterminated string"),
+            FormatErrors::InvalidEscape(c) => write!(f, "Invalid escape sequence \\{}", c),
+            FormatErrors::Expected { expected, found } => {
+                write!(f, "Expected {}, found {}", expected, found)
+            }
+            FormatErrors::UnknownStatement(name) => write!(f, "Unknown statement: {}", name),
+            FormatErrors::UnknownRule(name) => write!(f, "Unknown rule: {}", name),
+            FormatErrors::UnknownParameter(name) => write!(f, "Unknown parameter: {}", name),
+            FormatErrors::UnknownVariableKind(name) => {
+                write!(f, "Unknown variable kind: {}", name)
+            }
+            FormatErrors::UnknownBuiltin(name) => write!(f, "Unknown builtin token: #{}", name),
+            FormatErrors::UnknownLiteral(name) => write!(f, "Unknown literal: {}", name),
+            FormatErrors::InvalidNumber(num) => write!(f, "Invalid number: {}", num),
+            FormatErrors::DuplicateNode(name) => write!(f, "Node defined twice: {}", name),
+            FormatErrors::DuplicateEnumerator(name) => {
+                write!(f, "Enumerator defined twice: {}", name)
+            }
+            FormatErrors::DuplicateVariable(name) => {
+                write!(f, "Variable declared twice: {}", name)
+            }
+            FormatErrors::DuplicateTemplate(name) => {
+                write!(f, "Template defined twice: {}", name)
+            }
+            FormatErrors::DuplicateMode(name) => write!(f, "Lexer mode defined twice: {}", name),
+            FormatErrors::Template(err) => write!(f, "{:?}", err),
+            FormatErrors::Pattern(err) => write!(f, "{:?}", err),
+            FormatErrors::MissingPreprocessor(name) => {
+                write!(f, "Missing preprocessor: {}", name)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    /// Plain identifier, possibly with `::` separated segments
+    Ident(String),
+    /// Name in backticks
+    Name(String),
+    /// "..."
+    Str(String),
+    /// '...'
+    Word(String),
+    Number(String),
+    Punct(&'static str),
+    Eof,
+}
+
+impl Tok {
+    fn describe(&self) -> String {
+        match self {
+            Tok::Ident(name) => format!("`{}`", name),
+            Tok::Name(name) => format!("`{}`", name),
+            Tok::Str(s) => format!("{:?}", s),
+            Tok::Word(s) => format!("'{}'", s),
+            Tok::Number(n) => n.clone(),
+            Tok::Punct(p) => format!("`{}`", p),
+            Tok::Eof => "end of file".to_string(),
+        }
+    }
+}
+
+const PUNCTUATION: [&str; 17] = [
+    "==", "!=", ">=", "<=", "{", "}", "[", "]", "(", ")", ";", ",", ":", "@", "%", "#", ">",
+];
+
+fn scan(source: &str) -> Result<Vec<(Tok, TextLocation)>, FormatError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    let mut line = 0;
+    let mut column = 0;
+    // `<` is not part of PUNCTUATION because it has to be checked after `<=`
+    'outer: while let Some(&(i, c)) = chars.peek() {
+        let location = TextLocation::new(line, column);
+        if c == '\n' {
+            line += 1;
+            column = 0;
+            chars.next();
+            continue;
+        }
+        if c.is_whitespace() {
+            column += 1;
+            chars.next();
+            continue;
+        }
+        let rest = &source[i..];
+        // comments
+        if rest.starts_with("//") {
+            while let Some(&(_, c)) = chars.peek() {
+                if c == '\n' {
+                    continue 'outer;
+                }
+                chars.next();
+            }
+            continue;
+        }
+        if rest.starts_with("/*") {
+            chars.next();
+            chars.next();
+            column += 2;
+            loop {
+                match chars.next() {
+                    Some((j, '*')) if source[j..].starts_with("*/") => {
+                        chars.next();
+                        column += 2;
+                        continue 'outer;
+                    }
+                    Some((_, '\n')) => {
+                        line += 1;
+                        column = 0;
+                    }
+                    Some(_) => column += 1,
+                    None => {
+                        return Err(FormatError {
+                            kind: FormatErrors::UnterminatedString,
+                            location,
+                        })
+                    }
+                }
+            }
+        }
+        // quoted
+        if c == '"' || c == '\'' || c == '`' {
+            chars.next();
+            column += 1;
+            let mut value = String::new();
+            loop {
+                let (_, ch) = match chars.next() {
+                    Some(ch) => ch,
+                    None => {
+                        return Err(FormatError {
+                            kind: FormatErrors::UnterminatedString,
+                            location,
+                        })
+                    }
+                };
+                column += 1;
+                if ch == c {
+                    break;
+                }
+                if ch == '\n' {
+                    line += 1;
+                    column = 0;
+                }
+                if ch != '\\' {
+                    value.push(ch);
+                    continue;
+                }
+                let (_, escaped) = match chars.next() {
+                    Some(ch) => ch,
+                    None => {
+                        return Err(FormatError {
+                            kind: FormatErrors::UnterminatedString,
+                            location,
+                        })
+                    }
+                };
+                column += 1;
+                value.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    'r' => '\r',
+                    '0' => '\0',
+                    '\\' | '"' | '\'' | '`' => escaped,
+                    'u' => {
+                        let mut code = String::new();
+                        if !matches!(chars.next(), Some((_, '{'))) {
+                            return Err(FormatError {
+                                kind: FormatErrors::InvalidEscape('u'),
+                                location: TextLocation::new(line, column),
+                            });
+                        }
+                        column += 1;
+                        for (_, ch) in chars.by_ref() {
+                            column += 1;
+                            if ch == '}' {
+                                break;
+                            }
+                            code.push(ch);
+                        }
+                        match u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                            Some(ch) => ch,
+                            None => {
+                                return Err(FormatError {
+                                    kind: FormatErrors::InvalidEscape('u'),
+                                    location: TextLocation::new(line, column),
+                                })
+                            }
+                        }
+                    }
+                    other => {
+                        return Err(FormatError {
+                            kind: FormatErrors::InvalidEscape(other),
+                            location: TextLocation::new(line, column),
+                        })
+                    }
+                });
+            }
+            tokens.push((
+                match c {
+                    '"' => Tok::Str(value),
+                    '\'' => Tok::Word(value),
+                    _ => Tok::Name(value),
+                },
+                location,
+            ));
+            continue;
+        }
+        // identifiers
+        if c.is_alphabetic() || c == '_' {
+            let mut end = i;
+            while let Some(&(j, ch)) = chars.peek() {
+                if ch.is_alphanumeric() || ch == '_' {
+                    end = j + ch.len_utf8();
+                    column += 1;
+                    chars.next();
+                    continue;
+                }
+                // path separator
+                let rest = &source[j..];
+                if rest.starts_with("::")
+                    && rest[2..]
+                        .chars()
+                        .next()
+                        .is_some_and(|ch| ch.is_alphabetic() || ch == '_')
+                {
+                    chars.next();
+                    chars.next();
+                    column += 2;
+                    continue;
+                }
+                break;
+            }
+            tokens.push((Tok::Ident(source[i..end].to_string()), location));
+            continue;
+        }
+        // numbers
+        if c.is_ascii_digit() {
+            let mut end = i + 1;
+            chars.next();
+            column += 1;
+            while let Some(&(j, ch)) = chars.peek() {
+                if !ch.is_ascii_digit() {
+                    break;
+                }
+                end = j + 1;
+                column += 1;
+                chars.next();
+            }
+            tokens.push((Tok::Number(source[i..end].to_string()), location));
+            continue;
+        }
+        for punct in PUNCTUATION.iter().chain(["<"].iter()) {
+            if rest.starts_with(punct) {
+                for _ in 0..punct.len() {
+                    chars.next();
+                }
+                column += punct.len();
+                tokens.push((Tok::Punct(punct), location));
+                continue 'outer;
+            }
+        }
+        return Err(FormatError {
+            kind: FormatErrors::UnexpectedChar(c),
+            location,
+        });
+    }
+    tokens.push((Tok::Eof, TextLocation::new(line, column)));
+    Ok(tokens)
+}
+
+struct Loader<'a> {
+    tokens: Vec<(Tok, TextLocation)>,
+    idx: usize,
+    parser: Parser,
+    /// Where the templates are used, for error messages
+    uses: Vec<(String, TextLocation)>,
+    preprocessors: &'a Preprocessors,
+}
+
+impl Loader<'_> {
+    fn peek(&self) -> &Tok {
+        &self.tokens[self.idx].0
+    }
+
+    fn location(&self) -> TextLocation {
+        self.tokens[self.idx].1.clone()
+    }
+
+    fn next(&mut self) -> Tok {
+        let tok = self.tokens[self.idx].0.clone();
+        if self.idx + 1 < self.tokens.len() {
+            self.idx += 1;
+        }
+        tok
+    }
+
+    fn error<T>(&self, kind: FormatErrors) -> Result<T, FormatError> {
+        Err(FormatError {
+            kind,
+            location: self.location(),
+        })
+    }
+
+    fn expected<T>(&self, expected: &str) -> Result<T, FormatError> {
+        self.error(FormatErrors::Expected {
+            expected: expected.to_string(),
+            found: self.peek().describe(),
+        })
+    }
+
+    /// Consumes the punctuation if it is next
+    fn eat(&mut self, punct: &str) -> bool {
+        if let Tok::Punct(p) = self.peek() {
+            if *p == punct {
+                self.next();
+                return true;
+            }
+        }
+        false
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), FormatError> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        self.expected(&format!("`{}`", punct))
+    }
+
+    fn name(&mut self) -> Result<String, FormatError> {
+        match self.peek() {
+            Tok::Ident(_) | Tok::Name(_) => match self.next() {
+                Tok::Ident(name) | Tok::Name(name) => Ok(name),
+                _ => unreachable!(),
+            },
+            _ => self.expected("name"),
+        }
+    }
+
+    fn string(&mut self) -> Result<String, FormatError> {
+        match self.peek() {
+            Tok::Str(_) => match self.next() {
+                Tok::Str(s) => Ok(s),
+                _ => unreachable!(),
+            },
+            _ => self.expected("string"),
+        }
+    }
+
+    fn number<T: core::str::FromStr>(&mut self) -> Result<T, FormatError> {
+        match self.peek() {
+            Tok::Number(num) => match num.parse() {
+                Ok(num) => {
+                    self.next();
+                    Ok(num)
+                }
+                Err(_) => self.error(FormatErrors::InvalidNumber(num.clone())),
+            },
+            _ => self.expected("number"),
+        }
+    }
+
+    fn boolean(&mut self) -> Result<bool, FormatError> {
+        match self.peek() {
+            Tok::Ident(b) if b == "true" || b == "false" => {
+                let b = b == "true";
+                self.next();
+                Ok(b)
+            }
+            _ => self.expected("`true` or `false`"),
+        }
+    }
+
+    /// Reads `name:` of a named argument
+    fn argument(&mut self, argument: &str) -> Result<(), FormatError> {
+        match self.peek() {
+            Tok::Ident(name) if name == argument => {
+                self.next();
+                self.expect(":")
+            }
+            _ => self.expected(&format!("`{}`", argument)),
+        }
+    }
+
+    /// Reads `string("\"", escape: "\\")`, `line_comment("//")`,
+    /// `block_comment("/*", "*/", nested: true)` or `raw_string("r#\"", "\"#")`
+    fn literal(&mut self) -> Result<Literal, FormatError> {
+        let location = self.location();
+        let kind = self.name()?;
+        self.expect("(")?;
+        let start = self.string()?;
+        let literal = match kind.as_str() {
+            "string" => {
+                let mut escape = None;
+                if self.eat(",") {
+                    self.argument("escape")?;
+                    let mut chars = self.string()?.chars().collect::<Vec<_>>();
+                    if chars.len() != 1 {
+                        return Err(FormatError {
+                            kind: FormatErrors::Expected {
+                                expected: "single character".to_string(),
+                                found: format!("{:?}", chars.iter().collect::<String>()),
+                            },
+                            location,
+                        });
+                    }
+                    escape = chars.pop();
+                }
+                Literal::String {
+                    quote: start,
+                    escape,
+                }
+            }
+            "line_comment" => Literal::LineComment { start },
+            "block_comment" => {
+                self.expect(",")?;
+                let end = self.string()?;
+                let mut nested = false;
+                if self.eat(",") {
+                    self.argument("nested")?;
+                    nested = self.boolean()?;
+                }
+                Literal::BlockComment { start, end, nested }
+            }
+            "raw_string" => {
+                self.expect(",")?;
+                let end = self.string()?;
+                Literal::RawString { start, end }
+            }
+            _ => {
+                return Err(FormatError {
+                    kind: FormatErrors::UnknownLiteral(kind),
+                    location,
+                })
+            }
+        };
+        self.expect(")")?;
+        Ok(literal)
+    }
+
+    /// Reads `"token" push mode;` or `"token" pop;`
+    fn action(&mut self) -> Result<(String, ModeAction), FormatError> {
+        let token = match self.peek() {
+            Tok::Str(_) => self.string()?,
+            _ => self.name()?,
+        };
+        let action = match self.peek() {
+            Tok::Ident(action) if action == "push" => {
+                self.next();
+                ModeAction::Push(self.name()?)
+            }
+            Tok::Ident(action) if action == "pop" => {
+                self.next();
+                ModeAction::Pop
+            }
+            _ => return self.expected("`push` or `pop`"),
+        };
+        self.expect(";")?;
+        Ok((token, action))
+    }
+
+    fn mode(&mut self) -> Result<(), FormatError> {
+        let location = self.location();
+        let name = self.name()?;
+        if self.parser.lexer.modes.contains_key(&name) {
+            return Err(FormatError {
+                kind: FormatErrors::DuplicateMode(name),
+                location,
+            });
+        }
+        let mut mode = LexerMode::new(&name);
+        self.expect("{")?;
+        while !self.eat("}") {
+            let location = self.location();
+            let statement = match self.next() {
+                Tok::Ident(statement) => statement,
+                tok => {
+                    return Err(FormatError {
+                        kind: FormatErrors::Expected {
+                            expected: "mode statement".to_string(),
+                            found: tok.describe(),
+                        },
+                        location,
+                    })
+                }
+            };
+            match statement.as_str() {
+                "tokens" => {
+                    while let Tok::Str(_) = self.peek() {
+                        mode.add_token(&self.string()?);
+                        self.eat(",");
+                    }
+                    self.expect(";")?;
+                }
+                "pattern" => {
+                    let name = self.name()?;
+                    let pattern = self.string()?;
+                    if let Err(err) = mode.add_pattern(&name, &pattern) {
+                        return Err(FormatError {
+                            kind: FormatErrors::Pattern(err),
+                            location,
+                        });
+                    }
+                    self.expect(";")?;
+                }
+                "merge" => {
+                    mode.merge(&self.name()?);
+                    self.expect(";")?;
+                }
+                "inherit" => {
+                    mode.inherit(true);
+                    self.expect(";")?;
+                }
+                "on" => {
+                    let (token, action) = self.action()?;
+                    mode.on(&token, action);
+                }
+                _ => {
+                    return Err(FormatError {
+                        kind: FormatErrors::UnknownStatement(statement),
+                        location,
+                    })
+                }
+            }
+        }
+        self.parser.lexer.modes.insert(name, mode);
+        Ok(())
+    }
+
+    fn load(&mut self) -> Result<(), FormatError> {
+        loop {
+            let location = self.location();
+            let statement = match self.next() {
+                Tok::Eof => return self.instantiate(),
+                Tok::Ident(statement) => statement,
+                tok => {
+                    return Err(FormatError {
+                        kind: FormatErrors::Expected {
+                            expected: "statement".to_string(),
+                            found: tok.describe(),
+                        },
+                        location,
+                    })
+                }
+            };
+            match statement.as_str() {
+                "tokens" => {
+                    while let Tok::Str(_) = self.peek() {
+                        let token = self.string()?;
+                        self.parser.lexer.add_token(token);
+                        self.eat(",");
+                    }
+                    self.expect(";")?;
+                }
+                "pattern" => {
+                    let name = self.name()?;
+                    let pattern = self.string()?;
+                    if let Err(err) = self.parser.lexer.add_pattern(&name, &pattern) {
+                        return Err(FormatError {
+                            kind: FormatErrors::Pattern(err),
+                            location,
+                        });
+                    }
+                    self.expect(";")?;
+                }
+                "literal" => {
+                    let name = self.name()?;
+                    let literal = self.literal()?;
+                    self.parser.lexer.add_literal(&name, literal);
+                    self.expect(";")?;
+                }
+                "keywords" => {
+                    let case_insensitive =
+                        matches!(self.peek(), Tok::Ident(word) if word == "ignore_case");
+                    if case_insensitive {
+                        self.next();
+                    }
+                    while let Tok::Str(_) = self.peek() {
+                        let word = self.string()?;
+                        self.parser.lexer.add_keyword(&word, case_insensitive);
+                        self.eat(",");
+                    }
+                    self.expect(";")?;
+                }
+                "on" => {
+                    let (token, action) = self.action()?;
+                    self.parser.lexer.on(&token, action);
+                }
+                "mode" => self.mode()?,
+                "indentation" => {
+                    self.parser.lexer.set_indentation(true);
+                    self.expect(";")?;
+                }
+                "preprocessor" => {
+                    let name = self.string()?;
+                    let preprocessor = match self.preprocessors.get(&name) {
+                        Some(preprocessor) => preprocessor,
+                        None => {
+                            return Err(FormatError {
+                                kind: FormatErrors::MissingPreprocessor(name),
+                                location,
+                            })
+                        }
+                    };
+                    self.parser.lexer.add_preprocessor(&name, preprocessor);
+                    self.expect(";")?;
+                }
+                "entry" => {
+                    self.parser.parser.entry = self.name()?;
+                    self.expect(";")?;
+                }
+                "eof" => {
+                    self.parser.grammar.eof = self.boolean()?;
+                    self.expect(";")?;
+                }
+                "global" => {
+                    let (name, kind) = self.variable()?;
+                    self.parser.grammar.globals.insert(name, kind);
+                }
+                "enum" => {
+                    let name = self.name()?;
+                    if self.parser.grammar.enumerators.contains_key(&name) {
+                        return Err(FormatError {
+                            kind: FormatErrors::DuplicateEnumerator(name),
+                            location,
+                        });
+                    }
+                    self.expect("{")?;
+                    let mut values = Vec::new();
+                    while !self.eat("}") {
+                        values.push(self.token()?);
+                        self.eat(",");
+                    }
+                    self.parser
+                        .grammar
+                        .enumerators
+                        .insert(name.clone(), Enumerator { name, values });
+                }
+                "node" => {
+                    let node = self.node()?;
+                    if self.parser.grammar.nodes.contains_key(&node.name) {
+                        return Err(FormatError {
+                            kind: FormatErrors::DuplicateNode(node.name),
+                            location,
+                        });
+                    }
+                    self.parser.grammar.add_node(node);
+                }
+                "template" => {
+                    let template = self.template()?;
+                    if self.parser.grammar.templates.contains_key(&template.name) {
+                        return Err(FormatError {
+                            kind: FormatErrors::DuplicateTemplate(template.name),
+                            location,
+                        });
+                    }
+                    self.parser.grammar.add_template(template);
+                }
+                _ => {
+                    return Err(FormatError {
+                        kind: FormatErrors::UnknownStatement(statement),
+                        location,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Creates the nodes for the used templates
+    fn instantiate(&mut self) -> Result<(), FormatError> {
+        let err = match self.parser.grammar.instantiate() {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        let template = match &err {
+            TemplateError::NotFound(template)
+            | TemplateError::Arguments { template, .. }
+            | TemplateError::TooDeep { template, .. } => template,
+        };
+        // point at the first use of the template
+        let location = match self.uses.iter().find(|(name, _)| name == template) {
+            Some((_, location)) => location.clone(),
+            None => self.location(),
+        };
+        Err(FormatError {
+            kind: FormatErrors::Template(err),
+            location,
+        })
+    }
+
+    /// name: kind;
+    fn variable(&mut self) -> Result<(String, VariableKind), FormatError> {
+        let name = self.name()?;
+        self.expect(":")?;
+        let kind = match self.peek() {
+            Tok::Ident(kind) => match kind.as_str() {
+                "node" => VariableKind::Node,
+                "list" => VariableKind::NodeList,
+                "bool" => VariableKind::Boolean,
+                "number" => VariableKind::Number,
+                _ => return self.error(FormatErrors::UnknownVariableKind(kind.clone())),
+            },
+            _ => return self.expected("variable kind"),
+        };
+        self.next();
+        self.expect(";")?;
+        Ok((name, kind))
+    }
+
+    fn node(&mut self) -> Result<Node, FormatError> {
+        let name = self.name()?;
+        let (rules, variables) = self.body()?;
+        Ok(Node {
+            name,
+            rules,
+            variables,
+        })
+    }
+
+    /// name<Parameter, ...> { body }
+    fn template(&mut self) -> Result<Template, FormatError> {
+        let name = self.name()?;
+        self.expect("<")?;
+        let mut parameters = Vec::new();
+        while !self.eat(">") {
+            parameters.push(self.name()?);
+            if !self.eat(",") {
+                self.expect(">")?;
+                break;
+            }
+        }
+        let (rules, variables) = self.body()?;
+        Ok(Template {
+            name,
+            parameters,
+            rules,
+            variables,
+        })
+    }
+
+    /// { variables and rules }
+    fn body(&mut self) -> Result<(Rules, Map<String, VariableKind>), FormatError> {
+        self.expect("{")?;
+        let mut variables = Map::new();
+        let mut rules = Vec::new();
+        while !self.eat("}") {
+            if let Tok::Ident(var) = self.peek() {
+                if var == "var" {
+                    self.next();
+                    let location = self.location();
+                    let (name, kind) = self.variable()?;
+                    if variables.contains_key(&name) {
+                        return Err(FormatError {
+                            kind: FormatErrors::DuplicateVariable(name),
+                            location,
+                        });
+                    }
+                    variables.insert(name, kind);
+                    continue;
+                }
+            }
+            rules.push(self.rule()?);
+        }
+        Ok((rules, variables))
+    }
+
+    /// { rules }
+    fn block(&mut self) -> Result<Rules, FormatError> {
+        self.expect("{")?;
+        let mut rules = Vec::new();
+        while !self.eat("}") {
+            rules.push(self.rule()?);
+        }
+        Ok(rules)
+    }
+
+    /// Either `;` or a block of rules
+    fn block_or_end(&mut self) -> Result<Rules, FormatError> {
+        if self.eat(";") {
+            return Ok(Vec::new());
+        }
+        self.block()
+    }
+
+    /// `TOKEN [parameters] { rules }`
+    fn matched(&mut self) -> Result<(MatchToken, Vec<Parameters>, Rules), FormatError> {
+        let token = self.token()?;
+        let parameters = self.parameters()?;
+        let rules = self.block_or_end()?;
+        Ok((token, parameters, rules))
+    }
+
+    /// { TOKEN [parameters] { rules } ... }
+    fn options(&mut self) -> Result<Vec<OneOf>, FormatError> {
+        self.expect("{")?;
+        let mut options = Vec::new();
+        while !self.eat("}") {
+            let (token, parameters, rules) = self.matched()?;
+            options.push(OneOf {
+                token,
+                rules,
+                parameters,
+            });
+        }
+        Ok(options)
+    }
+
+    /// Optional `else { rules }`
+    fn otherwise(&mut self) -> Result<Rules, FormatError> {
+        if let Tok::Ident(kw) = self.peek() {
+            if kw == "else" {
+                self.next();
+                return self.block();
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    fn rule(&mut self) -> Result<Rule, FormatError> {
+        let location = self.location();
+        let keyword = match self.next() {
+            Tok::Ident(keyword) => keyword,
+            tok => {
+                return Err(FormatError {
+                    kind: FormatErrors::Expected {
+                        expected: "rule".to_string(),
+                        found: tok.describe(),
+                    },
+                    location,
+                })
+            }
+        };
+        let rule = match keyword.as_str() {
+            "is" => {
+                let (token, parameters, rules) = self.matched()?;
+                Rule::Is {
+                    token,
+                    rules,
+                    parameters,
+                }
+            }
+            "isnt" => {
+                let (token, parameters, rules) = self.matched()?;
+                Rule::Isnt {
+                    token,
+                    rules,
+                    parameters,
+                }
+            }
+            "one_of" => Rule::IsOneOf {
+                tokens: self.options()?,
+            },
+            "maybe" => {
+                let (token, parameters, is) = self.matched()?;
+                let isnt = self.otherwise()?;
+                Rule::Maybe {
+                    token,
+                    is,
+                    isnt,
+                    parameters,
+                }
+            }
+            "maybe_one_of" => {
+                let is_one_of = self.options()?;
+                let isnt = self.otherwise()?;
+                Rule::MaybeOneOf { is_one_of, isnt }
+            }
+            "while" => {
+                let (token, parameters, rules) = self.matched()?;
+                Rule::While {
+                    token,
+                    rules,
+                    parameters,
+                }
+            }
+            "loop" => Rule::Loop {
+                rules: self.block()?,
+            },
+            "until" => {
+                let (token, parameters, rules) = self.matched()?;
+                Rule::Until {
+                    token,
+                    rules,
+                    parameters,
+                }
+            }
+            "until_one_of" => Rule::UntilOneOf {
+                tokens: self.options()?,
+            },
+            "compare" => {
+                let left = self.name()?;
+                let comparison = match self.peek() {
+                    Tok::Punct("==") => Comparison::Equal,
+                    Tok::Punct("!=") => Comparison::NotEqual,
+                    Tok::Punct(">") => Comparison::GreaterThan,
+                    Tok::Punct("<") => Comparison::LessThan,
+                    Tok::Punct(">=") => Comparison::GreaterThanOrEqual,
+                    Tok::Punct("<=") => Comparison::LessThanOrEqual,
+                    _ => return self.expected("comparison operator"),
+                };
+                self.next();
+                let right = self.name()?;
+                let rules = self.block_or_end()?;
+                Rule::Command {
+                    command: Commands::Compare {
+                        left,
+                        right,
+                        comparison,
+                        rules,
+                    },
+                }
+            }
+            "error" | "hard_error" | "goto" | "label" | "print" => {
+                self.expect("(")?;
+                let command = match keyword.as_str() {
+                    "error" => Commands::Error {
+                        message: self.string()?,
+                    },
+                    "hard_error" => Commands::HardError {
+                        set: self.boolean()?,
+                    },
+                    "goto" => Commands::Goto {
+                        label: self.name()?,
+                    },
+                    "label" => Commands::Label { name: self.name()? },
+                    _ => Commands::Print {
+                        message: self.string()?,
+                    },
+                };
+                self.expect(")")?;
+                self.expect(";")?;
+                Rule::Command { command }
+            }
+            "debug" => {
+                let target = if self.eat("(") {
+                    let target = self.name()?;
+                    self.expect(")")?;
+                    Some(target)
+                } else {
+                    None
+                };
+                self.expect(";")?;
+                Rule::Debug { target }
+            }
+            _ => {
+                return Err(FormatError {
+                    kind: FormatErrors::UnknownRule(keyword),
+                    location,
+                })
+            }
+        };
+        Ok(rule)
+    }
+
+    /// Optional `[parameter, ...]`
+    fn parameters(&mut self) -> Result<Vec<Parameters>, FormatError> {
+        let mut parameters = Vec::new();
+        if !self.eat("[") {
+            return Ok(parameters);
+        }
+        while !self.eat("]") {
+            let location = self.location();
+            let name = match self.next() {
+                Tok::Ident(name) => name,
+                tok => {
+                    return Err(FormatError {
+                        kind: FormatErrors::Expected {
+                            expected: "parameter".to_string(),
+                            found: tok.describe(),
+                        },
+                        location,
+                    })
+                }
+            };
+            let parameter = match name.as_str() {
+                "return" => Parameters::Return,
+                "node_start" => Parameters::NodeStart,
+                "node_end" => Parameters::NodeEnd,
+                "debug" => {
+                    if self.eat("(") {
+                        let target = self.name()?;
+                        self.expect(")")?;
+                        Parameters::Debug(Some(target))
+                    } else {
+                        Parameters::Debug(None)
+                    }
+                }
+                _ => {
+                    self.expect("(")?;
+                    let parameter = match name.as_str() {
+                        "set" => Parameters::Set(self.name()?),
+                        "global" => Parameters::Global(self.name()?),
+                        "increment" => Parameters::Increment(self.name()?),
+                        "decrement" => Parameters::Decrement(self.name()?),
+                        "increment_global" => Parameters::IncrementGlobal(self.name()?),
+                        "true" => Parameters::True(self.name()?),
+                        "false" => Parameters::False(self.name()?),
+                        "true_global" => Parameters::TrueGlobal(self.name()?),
+                        "false_global" => Parameters::FalseGlobal(self.name()?),
+                        "print" => Parameters::Print(self.string()?),
+                        "back" => Parameters::Back(self.number()?),
+                        "break" => Parameters::Break(self.number()?),
+                        "hard_error" => Parameters::HardError(self.boolean()?),
+                        "goto" => Parameters::Goto(self.name()?),
+                        _ => {
+                            return Err(FormatError {
+                                kind: FormatErrors::UnknownParameter(name),
+                                location,
+                            })
+                        }
+                    };
+                    self.expect(")")?;
+                    parameter
+                }
+            };
+            parameters.push(parameter);
+            if !self.eat(",") {
+                self.expect("]")?;
+                break;
+            }
+        }
+        Ok(parameters)
+    }
+
+    fn token(&mut self) -> Result<MatchToken, FormatError> {
+        let location = self.location();
+        let token = match self.next() {
+            Tok::Str(token) => MatchToken::Token(TokenKinds::Token(token)),
+            Tok::Word(word) => MatchToken::Word(word),
+            Tok::Ident(name) | Tok::Name(name) => {
+                if !self.eat("<") {
+                    return Ok(MatchToken::Node(name));
+                }
+                let mut arguments = Vec::new();
+                while !self.eat(">") {
+                    arguments.push(self.token()?);
+                    if !self.eat(",") {
+                        self.expect(">")?;
+                        break;
+                    }
+                }
+                self.uses.push((name.clone(), location));
+                MatchToken::Template(name, arguments)
+            }
+            Tok::Punct("@") => MatchToken::Enumerator(self.name()?),
+            Tok::Punct("%") => match self.peek() {
+                Tok::Str(_) => MatchToken::Token(TokenKinds::Complex(self.string()?)),
+                _ => MatchToken::Token(TokenKinds::Complex(self.name()?)),
+            },
+            Tok::Punct("#") => {
+                let location = self.location();
+                let builtin = self.name()?;
+                match builtin.as_str() {
+                    "text" => MatchToken::Token(TokenKinds::Text),
+                    "whitespace" => MatchToken::Token(TokenKinds::Whitespace),
+                    "eol" => MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eol)),
+                    "eof" => MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eof)),
+                    "indent" => MatchToken::Token(TokenKinds::Control(ControlTokenKind::Indent)),
+                    "dedent" => MatchToken::Token(TokenKinds::Control(ControlTokenKind::Dedent)),
+                    "any" => MatchToken::Any,
+                    _ => {
+                        return Err(FormatError {
+                            kind: FormatErrors::UnknownBuiltin(builtin),
+                            location,
+                        })
+                    }
+                }
+            }
+            tok => {
+                return Err(FormatError {
+                    kind: FormatErrors::Expected {
+                        expected: "token".to_string(),
+                        found: tok.describe(),
+                    },
+                    location,
+                })
+            }
+        };
+        Ok(token)
+    }
+}
+
+/// Returns true if the name can be written without backticks
+fn is_ident(name: &str) -> bool {
+    name.split("::").all(|segment| {
+        let mut chars = segment.chars();
+        match chars.next() {
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                chars.all(|c| c.is_alphanumeric() || c == '_')
+            }
+            _ => false,
+        }
+    })
+}
+
+fn quote(text: &str, delimiter: char) -> String {
+    let mut result = String::with_capacity(text.len() + 2);
+    result.push(delimiter);
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\0' => result.push_str("\\0"),
+            c if c == delimiter => {
+                result.push('\\');
+                result.push(c);
+            }
+            c if c.is_control() => result.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push(delimiter);
+    result
+}
+
+fn name(name: &str) -> String {
+    if is_ident(name) {
+        name.to_string()
+    } else {
+        quote(name, '`')
+    }
+}
+
+pub(crate) fn token(token: &MatchToken) -> String {
+    match token {
+        MatchToken::Token(kind) => match kind {
+            TokenKinds::Token(token) => quote(token, '"'),
+            TokenKinds::Complex(complex) => match is_ident(complex) {
+                true => format!("%{}", complex),
+                false => format!("%{}", quote(complex, '"')),
+            },
+            TokenKinds::Text => "#text".to_string(),
+            TokenKinds::Whitespace => "#whitespace".to_string(),
+            TokenKinds::Control(ControlTokenKind::Eol) => "#eol".to_string(),
+            TokenKinds::Control(ControlTokenKind::Eof) => "#eof".to_string(),
+            TokenKinds::Control(ControlTokenKind::Indent) => "#indent".to_string(),
+            TokenKinds::Control(ControlTokenKind::Dedent) => "#dedent".to_string(),
+        },
+        MatchToken::Node(node) => name(node),
+        MatchToken::Word(word) => quote(word, '\''),
+        MatchToken::Enumerator(enumerator) => format!("@{}", name(enumerator)),
+        MatchToken::Template(template, arguments) => {
+            let arguments = arguments.iter().map(self::token).collect::<Vec<_>>();
+            format!("{}<{}>", name(template), arguments.join(", "))
+        }
+        MatchToken::Any => "#any".to_string(),
+    }
+}
+
+fn parameter(parameter: &Parameters) -> String {
+    match parameter {
+        Parameters::Set(var) => format!("set({})", name(var)),
+        Parameters::Global(var) => format!("global({})", name(var)),
+        Parameters::Increment(var) => format!("increment({})", name(var)),
+        Parameters::Decrement(var) => format!("decrement({})", name(var)),
+        Parameters::IncrementGlobal(var) => format!("increment_global({})", name(var)),
+        Parameters::True(var) => format!("true({})", name(var)),
+        Parameters::False(var) => format!("false({})", name(var)),
+        Parameters::TrueGlobal(var) => format!("true_global({})", name(var)),
+        Parameters::FalseGlobal(var) => format!("false_global({})", name(var)),
+        Parameters::Print(message) => format!("print({})", quote(message, '"')),
+        Parameters::Debug(None) => "debug".to_string(),
+        Parameters::Debug(Some(var)) => format!("debug({})", name(var)),
+        Parameters::Back(steps) => format!("back({})", steps),
+        Parameters::Return => "return".to_string(),
+        Parameters::Break(n) => format!("break({})", n),
+        Parameters::HardError(set) => format!("hard_error({})", set),
+        Parameters::Goto(label) => format!("goto({})", name(label)),
+        Parameters::NodeStart => "node_start".to_string(),
+        Parameters::NodeEnd => "node_end".to_string(),
+    }
+}
+
+fn literal(literal: &Literal) -> String {
+    match literal {
+        Literal::String { quote, escape } => match escape {
+            Some(escape) => format!(
+                "string({}, escape: {})",
+                self::quote(quote, '"'),
+                self::quote(&escape.to_string(), '"')
+            ),
+            None => format!("string({})", self::quote(quote, '"')),
+        },
+        Literal::LineComment { start } => format!("line_comment({})", quote(start, '"')),
+        Literal::BlockComment { start, end, nested } => format!(
+            "block_comment({}, {}, nested: {})",
+            quote(start, '"'),
+            quote(end, '"'),
+            nested
+        ),
+        Literal::RawString { start, end } => {
+            format!("raw_string({}, {})", quote(start, '"'), quote(end, '"'))
+        }
+    }
+}
+
+fn variable_kind(kind: &VariableKind) -> &'static str {
+    match kind {
+        VariableKind::Node => "node",
+        VariableKind::NodeList => "list",
+        VariableKind::Boolean => "bool",
+        VariableKind::Number => "number",
+    }
+}
+
+fn comparison(comparison: &Comparison) -> &'static str {
+    match comparison {
+        Comparison::Equal => "==",
+        Comparison::NotEqual => "!=",
+        Comparison::GreaterThan => ">",
+        Comparison::LessThan => "<",
+        Comparison::GreaterThanOrEqual => ">=",
+        Comparison::LessThanOrEqual => "<=",
+    }
+}
+
+/// Returns the keys of a map in a stable order
+fn sorted<V>(map: &Map<String, V>) -> Vec<&String> {
+    let mut keys = map.keys().collect::<Vec<_>>();
+    keys.sort();
+    keys
+}
+
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn line(&mut self, line: &str) {
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    /// Tokens, patterns and mode changes of the lexer or of a mode
+    fn lexer(
+        &mut self,
+        tokens: &[String],
+        patterns: &[TokenPattern],
+        actions: &Map<String, ModeAction>,
+    ) {
+        if !tokens.is_empty() {
+            let mut line = String::from("tokens");
+            for tok in tokens {
+                let tok = quote(tok, '"');
+                if line.len() + tok.len() + 1 > 80 {
+                    self.line(&line);
+                    line = String::from("   ");
+                }
+                line.push(' ');
+                line.push_str(&tok);
+            }
+            line.push(';');
+            self.line(&line);
+        }
+        for pattern in patterns {
+            self.line(&format!(
+                "pattern {} {};",
+                name(&pattern.name),
+                quote(pattern.pattern.source(), '"')
+            ));
+        }
+        for token in sorted(actions) {
+            let action = match &actions[token] {
+                ModeAction::Push(mode) => format!("push {}", name(mode)),
+                ModeAction::Pop => "pop".to_string(),
+            };
+            self.line(&format!("on {} {};", quote(token, '"'), action));
+        }
+    }
+
+    fn parser(&mut self, parser: &Parser) {
+        let lexer = &parser.lexer;
+        // preprocessors pushed without a name are only functions
+        let unnamed = lexer
+            .preprocessors
+            .len()
+            .saturating_sub(lexer.preprocessor_names.len());
+        if unnamed > 0 {
+            self.line(&format!(
+                "// {} unnamed preprocessor(s) can not be written in the grammar file",
+                unnamed
+            ));
+        }
+        self.lexer(&lexer.token_kinds, &lexer.patterns, &lexer.actions);
+        for literal in &lexer.literals {
+            self.line(&format!(
+                "literal {} {};",
+                name(&literal.name),
+                self::literal(&literal.literal)
+            ));
+        }
+        for case_insensitive in [false, true] {
+            let keywords = lexer
+                .keywords
+                .iter()
+                .filter(|keyword| keyword.case_insensitive == case_insensitive)
+                .map(|keyword| quote(&keyword.word, '"'))
+                .collect::<Vec<_>>();
+            if !keywords.is_empty() {
+                let prefix = if case_insensitive { "ignore_case " } else { "" };
+                self.line(&format!("keywords {}{};", prefix, keywords.join(" ")));
+            }
+        }
+        if lexer.indentation {
+            self.line("indentation;");
+        }
+        for preprocessor in &lexer.preprocessor_names {
+            self.line(&format!("preprocessor {};", quote(preprocessor, '"')));
+        }
+        for mode in sorted(&lexer.modes) {
+            let mode = &lexer.modes[mode];
+            self.line(&format!("mode {} {{", name(&mode.name)));
+            self.indent += 1;
+            self.lexer(mode.tokens(), mode.patterns(), mode.actions());
+            if let Some(merged) = mode.merged() {
+                self.line(&format!("merge {};", name(merged)));
+            }
+            if mode.inherits() {
+                self.line("inherit;");
+            }
+            self.indent -= 1;
+            self.line("}");
+        }
+        self.line(&format!("entry {};", name(&parser.parser.entry)));
+        self.line(&format!("eof {};", parser.grammar.eof));
+
+        let grammar = &parser.grammar;
+        if !grammar.globals.is_empty() {
+            self.out.push('\n');
+            for global in sorted(&grammar.globals) {
+                let kind = variable_kind(&grammar.globals[global]);
+                self.line(&format!("global {}: {};", name(global), kind));
+            }
+        }
+        for enumerator in sorted(&grammar.enumerators) {
+            self.out.push('\n');
+            self.line(&format!("enum {} {{", name(enumerator)));
+            self.indent += 1;
+            for value in &grammar.enumerators[enumerator].values {
+                self.line(&format!("{},", token(value)));
+            }
+            self.indent -= 1;
+            self.line("}");
+        }
+        for template in sorted(&grammar.templates) {
+            let template = &grammar.templates[template];
+            let parameters = template
+                .parameters
+                .iter()
+                .map(|p| name(p))
+                .collect::<Vec<_>>();
+            self.out.push('\n');
+            self.line(&format!(
+                "template {}<{}> {{",
+                name(&template.name),
+                parameters.join(", ")
+            ));
+            self.body(&template.variables, &template.rules);
+        }
+        for node in sorted(&grammar.nodes) {
+            // instances are created again when loading
+            if grammar.is_instance(node) {
+                continue;
+            }
+            self.out.push('\n');
+            self.node(&grammar.nodes[node]);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        self.line(&format!("node {} {{", name(&node.name)));
+        self.body(&node.variables, &node.rules);
+    }
+
+    /// Variables and rules of a node followed by the closing brace
+    fn body(&mut self, variables: &Map<String, VariableKind>, rules: &Rules) {
+        self.indent += 1;
+        for var in sorted(variables) {
+            let kind = variable_kind(&variables[var]);
+            self.line(&format!("var {}: {};", name(var), kind));
+        }
+        if !variables.is_empty() && !rules.is_empty() {
+            self.out.push('\n');
+        }
+        for rule in rules {
+            self.rule(rule);
+        }
+        self.indent -= 1;
+        self.line("}");
+    }
+
+    /// Prints the head of a rule followed by its block
+    fn block(&mut self, head: &str, rules: &Rules) {
+        if rules.is_empty() {
+            self.line(&format!("{};", head));
+            return;
+        }
+        self.line(&format!("{} {{", head));
+        self.rules(rules);
+        self.line("}");
+    }
+
+    fn rules(&mut self, rules: &Rules) {
+        self.indent += 1;
+        for rule in rules {
+            self.rule(rule);
+        }
+        self.indent -= 1;
+    }
+
+    /// `keyword TOKEN [parameters]`
+    fn head(keyword: &str, tok: &MatchToken, parameters: &[Parameters]) -> String {
+        let mut head = format!("{} {}", keyword, token(tok));
+        if !parameters.is_empty() {
+            let parameters = parameters.iter().map(parameter).collect::<Vec<_>>();
+            head.push_str(&format!(" [{}]", parameters.join(", ")));
+        }
+        head
+    }
+
+    fn options(&mut self, keyword: &str, options: &[OneOf]) {
+        self.line(&format!("{} {{", keyword));
+        self.indent += 1;
+        for option in options {
+            let head = Self::head("", &option.token, &option.parameters);
+            self.block(head.trim_start(), &option.rules);
+        }
+        self.indent -= 1;
+    }
+
+    /// Closes a block, adding the `else` branch if there is one
+    fn otherwise(&mut self, isnt: &Rules) {
+        if isnt.is_empty() {
+            self.line("}");
+            return;
+        }
+        self.line("} else {");
+        self.rules(isnt);
+        self.line("}");
+    }
+
+    fn rule(&mut self, rule: &Rule) {
+        match rule {
+            Rule::Is {
+                token,
+                rules,
+                parameters,
+            } => self.block(&Self::head("is", token, parameters), rules),
+            Rule::Isnt {
+                token,
+                rules,
+                parameters,
+            } => self.block(&Self::head("isnt", token, parameters), rules),
+            Rule::IsOneOf { tokens } => {
+                self.options("one_of", tokens);
+                self.line("}");
+            }
+            Rule::Maybe {
+                token,
+                is,
+                isnt,
+                parameters,
+            } => {
+                let head = Self::head("maybe", token, parameters);
+                if isnt.is_empty() {
+                    self.block(&head, is);
+                    return;
+                }
+                if is.is_empty() {
+                    self.line(&format!("{} {{}} else {{", head));
+                    self.rules(isnt);
+                    self.line("}");
+                    return;
+                }
+                self.line(&format!("{} {{", head));
+                self.rules(is);
+                self.otherwise(isnt);
+            }
+            Rule::MaybeOneOf { is_one_of, isnt } => {
+                self.options("maybe_one_of", is_one_of);
+                self.otherwise(isnt);
+            }
+            Rule::While {
+                token,
+                rules,
+                parameters,
+            } => self.block(&Self::head("while", token, parameters), rules),
+            Rule::Loop { rules } => {
+                self.line("loop {");
+                self.rules(rules);
+                self.line("}");
+            }
+            Rule::Until {
+                token,
+                rules,
+                parameters,
+            } => self.block(&Self::head("until", token, parameters), rules),
+            Rule::UntilOneOf { tokens } => {
+                self.options("until_one_of", tokens);
+                self.line("}");
+            }
+            Rule::Command { command } => match command {
+                Commands::Compare {
+                    left,
+                    right,
+                    comparison: cmp,
+                    rules,
+                } => {
+                    let head =
+                        format!("compare {} {} {}", name(left), comparison(cmp), name(right));
+                    self.block(&head, rules);
+                }
+                Commands::Error { message } => {
+                    self.line(&format!("error({});", quote(message, '"')))
+                }
+                Commands::HardError { set } => self.line(&format!("hard_error({});", set)),
+                Commands::Goto { label } => self.line(&format!("goto({});", name(label))),
+                Commands::Label { name: label } => self.line(&format!("label({});", name(label))),
+                Commands::Print { message } => {
+                    self.line(&format!("print({});", quote(message, '"')))
+                }
+            },
+            Rule::Debug { target } => match target {
+                Some(target) => self.line(&format!("debug({});", name(target))),
+                None => self.line("debug;"),
+            },
+        }
+    }
+}
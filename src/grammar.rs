@@ -1,1040 +1,1530 @@
-use crate::Map;
-
-use crate::lexer::TokenKinds;
-
-use serde::{Deserialize, Serialize};
-
-// Choose between std and alloc
-cfg_if::cfg_if! {
-    if #[cfg(feature = "std")] {
-        extern crate std;
-        use std::prelude::v1::*;
-    } else {
-        extern crate alloc;
-        use alloc::string::*;
-        use alloc::vec::*;
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Grammar {
-    pub nodes: Map<String, Node>,
-    pub enumerators: Map<String, Enumerator>,
-    pub globals: Map<String, VariableKind>,
-    /// If true, the parser will throw an error if the last token is not EOF
-    pub eof: bool,
-}
-
-impl Grammar {
-    pub fn new() -> Grammar {
-        Grammar {
-            nodes: Map::new(),
-            enumerators: Map::new(),
-            globals: Map::new(),
-            eof: true,
-        }
-    }
-
-    pub fn add_node(&mut self, node: Node) {
-        self.nodes.insert(node.name.clone(), node);
-    }
-}
-
-/// A collection of rules
-pub type Rules = Vec<Rule>;
-
-/// A rule defines how a token will be matched and what will happen if it is matched
-///
-/// It also contains parameters that can be used if the rule is matched
-///
-/// Special kind of rules are commands that can be executed without matching a token
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum Rule {
-    /// Matches a token
-    ///
-    /// If the token is matched, the rules will be executed
-    ///
-    /// If the token is not matched, the node will end with an error
-    Is {
-        token: MatchToken,
-        rules: Rules,
-        parameters: Vec<Parameters>,
-    },
-    /// Matches a token
-    ///
-    /// If the token is matched, the node will end with an error
-    ///
-    /// If the token is not matched, the rules will be executed
-    Isnt {
-        token: MatchToken,
-        rules: Rules,
-        parameters: Vec<Parameters>,
-    },
-    /// Matches one of the tokens
-    ///
-    /// If one of the tokens is matched, the rules will be executed
-    ///
-    /// If none of the tokens is matched, the node will end with an error
-    IsOneOf { tokens: Vec<OneOf> },
-    /// Matches a token
-    ///
-    /// If the token is matched, the rules will be executed
-    ///
-    /// If the token is not matched, the rules for the else branch will be executed
-    Maybe {
-        /// Token that will be matched
-        token: MatchToken,
-        /// Rules that will be executed if the token is matched
-        is: Rules,
-        /// Rules that will be executed if the token is not matched
-        isnt: Rules,
-        /// Parameters that can be used if the token is matched
-        parameters: Vec<Parameters>,
-    },
-    /// Matches one of the tokens
-    ///
-    /// If one of the tokens is matched, the rules will be executed
-    ///
-    /// If none of the tokens is matched, the rules for the else branch will be executed
-    MaybeOneOf {
-        /// Tokens that will be matched
-        is_one_of: Vec<OneOf>,
-        /// Rules that will be executed if none of the tokens is matched
-        isnt: Rules,
-    },
-    /// Matches a token
-    ///
-    /// If the token is matched, the rules will be executed
-    ///
-    /// After the rules are executed, the token will be matched again
-    /// and the rules will be executed again (if the token is matched)
-    While {
-        token: MatchToken,
-        rules: Rules,
-        /// Parameters that can be used if the token is matched
-        ///
-        /// The parameters will be used once every time the token is matched
-        parameters: Vec<Parameters>,
-    },
-    /// Loop that will be executed until a break command is executed
-    Loop { rules: Rules },
-    /// Searches in the tokens until a token is matched
-    Until {
-        token: MatchToken,
-        rules: Rules,
-        parameters: Vec<Parameters>,
-    },
-    /// Searches in the tokens until one of the tokens is matched
-    UntilOneOf { tokens: Vec<OneOf> },
-    /// Performs a command
-    ///
-    /// The command will be executed without matching a token
-    Command { command: Commands },
-    Debug { target: Option<String> },
-}
-
-/// One of the tokens that will be matched
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct OneOf {
-    pub token: MatchToken,
-    pub rules: Rules,
-    pub parameters: Vec<Parameters>,
-}
-
-/// Commands that can be executed
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum Commands {
-    /// Compares two variables/numbers and executes rules if the comparison is true
-    Compare {
-        /// Left side of the comparison
-        left: String,
-        /// Right side of the comparison
-        right: String,
-        /// Comparison operator
-        comparison: Comparison,
-        /// Rules that will be executed if the comparison is true
-        rules: Rules,
-    },
-    /// Returns an error from node
-    Error {
-        message: String,
-    },
-    HardError {
-        set: bool,
-    },
-    Goto {
-        label: String,
-    },
-    Label {
-        name: String,
-    },
-    Print {
-        message: String,
-    },
-}
-
-/// Comparison operators
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub enum Comparison {
-    /// ==
-    Equal,
-    /// !=
-    NotEqual,
-    /// >
-    GreaterThan,
-    /// <
-    LessThan,
-    /// >=
-    GreaterThanOrEqual,
-    /// <=
-    LessThanOrEqual,
-}
-
-/// A token that will be matched
-///
-/// Can be a token kind or a node name
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub enum MatchToken {
-    /// A token kind
-    Token(TokenKinds),
-    /// A node name
-    Node(String),
-    /// A constant word
-    Word(String),
-    /// An enumerator
-    Enumerator(String),
-    /// Any token
-    Any,
-}
-
-/// A node is a collection of rules that will be executed when the node is matched
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Node {
-    /// Name of the node
-    pub name: String,
-    /// Rules that will be executed when the node is matched
-    pub rules: Rules,
-    /// Variables that can be used in the node and will be accessible from the outside
-    pub variables: Map<String, VariableKind>,
-}
-
-/// A variable that can be used in a node
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
-pub enum VariableKind {
-    /// Holds a single node
-    Node,
-    /// Holds a list of nodes
-    NodeList,
-    /// Holds a boolean
-    Boolean,
-    /// Holds a number
-    Number,
-}
-
-/// Parameters that can be used on a rule if it is matched
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum Parameters {
-    /// Sets a variable to a value
-    Set(String),
-    /// Sets a global variable to a value
-    Global(String),
-    /// Adds 1 to a variable of type Count
-    Increment(String),
-    /// Subtracts 1 from a variable of type Count
-    Decrement(String),
-    /// Adds 1 to a global variable of type Count
-    IncrementGlobal(String),
-    /// Sets a variable to true
-    True(String),
-    /// Sets a variable to false
-    False(String),
-    /// Sets a global variable to true
-    TrueGlobal(String),
-    /// Sets a global variable to false
-    FalseGlobal(String),
-    /// Prints string
-    Print(String),
-    /// Prints current token or variable
-    Debug(Option<String>),
-    /// Goes back in rules
-    Back(u8),
-    /// Returns from node
-    Return,
-    /// Breaks from rule blocks(n)
-    Break(usize),
-    /// If the node ends with an error, it will be a hard error
-    /// resulting in the parent node to also end with an error
-    ///
-    /// This is a way of telling the parser that the current node MUST match
-    ///
-    /// This is useful for using nodes in optional rules
-    HardError(bool),
-    /// Sets the current node to the label with the given name
-    Goto(String),
-    /// Hints to the parser that the node starts here
-    ///
-    /// This should be used at the start of every node
-    /// because it will prevent the parser from counting
-    /// whitespace in front of the node
-    NodeStart,
-    /// Hints to the parser that the node ends here
-    NodeEnd,
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Enumerator {
-    pub name: String,
-    pub values: Vec<MatchToken>,
-}
-
-/// validation module for grammar that is otherwise dynamically typed
-///
-/// This module is used to validate the grammar and make sure that it is correct
-///
-/// The grammar is validated by checking if the rules are correct and if the variables are used correctly
-///
-/// > note: Grammar errors have caused me a lot of headache in the past so using this module is highly recommended
-pub mod validator {
-    use super::*;
-    use crate::lexer::*;
-
-    impl Lexer {
-        pub fn validate_tokens(&self, result: &mut ValidationResult) {
-            let mut tokens: Vec<String> = Vec::new();
-            for token in &self.token_kinds {
-                // tokens that have already been validated can be ignored
-                if tokens.contains(token) {
-                    continue;
-                }
-                tokens.push(token.clone());
-                // check for collisions
-                if self.token_kinds.iter().filter(|t| *t == token).count() > 1 {
-                    result.errors.push(ValidationError {
-                        kind: ValidationErrors::TokenCollision(token.clone()),
-                        node_name: "__lexer__".to_string(),
-                    });
-                }
-                // check if token is empty
-                if token.is_empty() {
-                    result.errors.push(ValidationError {
-                        kind: ValidationErrors::EmptyToken,
-                        node_name: "__lexer__".to_string(),
-                    });
-                }
-                // check if it starts with a number
-                let first = token.chars().next().unwrap();
-                if first.is_numeric() {
-                    result.warnings.push(ValidationWarning {
-                        kind: ValidationWarnings::UnusualToken(
-                            token.clone(),
-                            TokenErrors::StartsNumeric,
-                        ),
-                        node_name: "__lexer__".to_string(),
-                    });
-                }
-
-                // check if it contains a whitespace
-                if token.chars().any(|c| c.is_whitespace()) {
-                    result.warnings.push(ValidationWarning {
-                        kind: ValidationWarnings::UnusualToken(
-                            token.clone(),
-                            TokenErrors::ContainsWhitespace,
-                        ),
-                        node_name: "__lexer__".to_string(),
-                    });
-                }
-
-                // check if it is longer than 2 characters
-                if token.len() > 2 {
-                    result.warnings.push(ValidationWarning {
-                        kind: ValidationWarnings::UnusualToken(token.clone(), TokenErrors::TooLong),
-                        node_name: "__lexer__".to_string(),
-                    });
-                }
-
-                // check if it is not ascii
-                if !token.chars().all(|c| c.is_ascii()) {
-                    result.warnings.push(ValidationWarning {
-                        kind: ValidationWarnings::UnusualToken(
-                            token.clone(),
-                            TokenErrors::NotAscii,
-                        ),
-                        node_name: "__lexer__".to_string(),
-                    });
-                }
-            }
-        }
-    }
-
-    impl Grammar {
-        /// Validates the grammar
-        pub fn validate(&self, lexer: &Lexer) -> ValidationResult {
-            let mut result = ValidationResult::new();
-            lexer.validate_tokens(&mut result);
-
-            for node in self.nodes.values() {
-                self.validate_node(node, lexer, &mut result);
-            }
-
-            result
-        }
-
-        pub fn validate_node(&self, node: &Node, lexer: &Lexer, result: &mut ValidationResult) {
-            let mut laf = LostAndFound::new();
-            for rule in &node.rules {
-                self.validate_rule(rule, node, lexer, &mut laf, result);
-            }
-            laf.pass(result, &node.name);
-        }
-
-        pub fn validate_rule(
-            &self,
-            rule: &Rule,
-            node: &Node,
-            lexer: &Lexer,
-            laf: &mut LostAndFound,
-            result: &mut ValidationResult,
-        ) {
-            match rule {
-                Rule::Is {
-                    token,
-                    rules,
-                    parameters,
-                } => {
-                    self.validate_token(token, node, lexer, laf, result);
-                    self.validate_parameters(parameters, node, laf, result);
-                    self.validate_ruleblock(rules, node, lexer, laf, result)
-                }
-                Rule::Isnt {
-                    token,
-                    rules,
-                    parameters,
-                } => {
-                    self.validate_token(token, node, lexer, laf, result);
-                    self.validate_parameters(parameters, node, laf, result);
-                    self.validate_ruleblock(rules, node, lexer, laf, result)
-                }
-                Rule::IsOneOf { tokens } => {
-                    for one_of in tokens {
-                        self.validate_token(&one_of.token, node, lexer, laf, result);
-                        self.validate_parameters(&one_of.parameters, node, laf, result);
-                        self.validate_ruleblock(&one_of.rules, node, lexer, laf, result)
-                    }
-                }
-                Rule::Maybe {
-                    token,
-                    is,
-                    isnt,
-                    parameters,
-                } => {
-                    self.validate_token(token, node, lexer, laf, result);
-                    self.validate_parameters(parameters, node, laf, result);
-                    self.validate_ruleblock(is, node, lexer, laf, result);
-                    self.validate_ruleblock(isnt, node, lexer, laf, result);
-                }
-                Rule::MaybeOneOf { is_one_of, isnt } => {
-                    for OneOf { token, rules, parameters } in is_one_of {
-                        self.validate_token(token, node, lexer, laf, result);
-                        self.validate_parameters(parameters, node, laf, result);
-                        self.validate_ruleblock(rules, node, lexer, laf, result);
-                    }
-                    self.validate_ruleblock(isnt, node, lexer, laf, result);
-                }
-                Rule::While {
-                    token,
-                    rules,
-                    parameters,
-                } => {
-                    self.validate_token(token, node, lexer, laf, result);
-                    self.validate_parameters(parameters, node, laf, result);
-                    self.validate_ruleblock(rules, node, lexer, laf, result)
-                }
-                Rule::Loop { rules } => {
-                    self.validate_ruleblock(rules, node, lexer, laf, result)
-                }
-                Rule::Until {
-                    token,
-                    rules,
-                    parameters,
-                } => {
-                    self.validate_token(token, node, lexer, laf, result);
-                    self.validate_parameters(parameters, node, laf, result);
-                    self.validate_ruleblock(rules, node, lexer, laf, result)
-                }
-                Rule::UntilOneOf { tokens } => {
-                    for one_of in tokens {
-                        self.validate_token(&one_of.token, node, lexer, laf, result);
-                        self.validate_parameters(&one_of.parameters, node, laf, result);
-                        self.validate_ruleblock(&one_of.rules, node, lexer, laf, result)
-                    }
-                }
-                Rule::Command { command } => match command {
-                    Commands::Compare {
-                        left,
-                        right,
-                        comparison: _,
-                        rules,
-                    } => {
-                        match self.globals.get(left) {
-                            Some(var) => match var {
-                                VariableKind::Number => (),
-                                _ => result.errors.push(ValidationError {
-                                    kind: ValidationErrors::CantUseVariable(left.clone()),
-                                    node_name: node.name.clone(),
-                                }),
-                            },
-                            None => {
-                                match node.variables.get(left) {
-                                    Some(var) => match var {
-                                        VariableKind::Number => (),
-                                        _ => result.errors.push(ValidationError {
-                                            kind: ValidationErrors::CantUseVariable(left.clone()),
-                                            node_name: node.name.clone(),
-                                        }),
-                                    },
-                                    None => {
-                                        result.errors.push(ValidationError {
-                                            kind: ValidationErrors::GlobalNotFound(left.clone()),
-                                            node_name: node.name.clone(),
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                        match self.globals.get(right) {
-                            Some(var) => match var {
-                                VariableKind::Number => (),
-                                _ => result.errors.push(ValidationError {
-                                    kind: ValidationErrors::CantUseVariable(right.clone()),
-                                    node_name: node.name.clone(),
-                                }),
-                            },
-                            None => {
-                                match node.variables.get(right) {
-                                    Some(var) => match var {
-                                        VariableKind::Number => (),
-                                        _ => result.errors.push(ValidationError {
-                                            kind: ValidationErrors::CantUseVariable(right.clone()),
-                                            node_name: node.name.clone(),
-                                        }),
-                                    },
-                                    None => {
-                                        result.errors.push(ValidationError {
-                                            kind: ValidationErrors::GlobalNotFound(right.clone()),
-                                            node_name: node.name.clone(),
-                                        });
-                                    }
-                                }
-                            }
-                        }
-                        for rule in rules {
-                            self.validate_rule(rule, node, lexer, laf, result);
-                        }
-                    }
-                    Commands::Error { message: _ } => (),
-                    Commands::HardError { set: _ } => (),
-                    Commands::Goto { label } => {
-                        laf.lost_labels.push(label.clone());
-                    }
-                    Commands::Label { name } => {
-                        if laf.found_labels.contains(&name) {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::DuplicateLabel(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                        laf.found_labels.push(name.clone());
-                    }
-                    Commands::Print { message: _ } => (),
-                },
-                Rule::Debug { target } => {
-                    match target {
-                        Some(name) => match node.variables.get(name) {
-                            Some(_) => (),
-                            None => {
-                                result.errors.push(ValidationError {
-                                    kind: ValidationErrors::VariableNotFound(name.clone()),
-                                    node_name: node.name.clone(),
-                                });
-                            }
-                        },
-                        None => (),
-                    }
-                }
-            }
-        }
-
-        pub fn validate_ruleblock(
-            &self,
-            ruleblock: &Vec<Rule>,
-            node: &Node,
-            lexer: &Lexer,
-            laf: &mut LostAndFound,
-            result: &mut ValidationResult,
-        ) {
-            let steps = laf.steps;
-            for rule in ruleblock {
-                laf.steps += 1;
-                self.validate_rule(rule, node, lexer, laf, result);
-            }
-            laf.steps = steps;
-        }
-
-        pub fn validate_token(
-            &self,
-            token: &MatchToken,
-            node: &Node,
-            lexer: &Lexer,
-            _laf: &mut LostAndFound,
-            result: &mut ValidationResult,
-        ) {
-            match token {
-                MatchToken::Node(name) => {
-                    if !self.nodes.contains_key(name) {
-                        result.errors.push(ValidationError {
-                            kind: ValidationErrors::NodeNotFound(name.clone()),
-                            node_name: node.name.clone(),
-                        });
-                    }
-                }
-                MatchToken::Enumerator(enumerator) => {
-                    if !self.enumerators.contains_key(enumerator) {
-                        result.errors.push(ValidationError {
-                            kind: ValidationErrors::EnumeratorNotFound(enumerator.clone()),
-                            node_name: node.name.clone(),
-                        });
-                    }
-                }
-                MatchToken::Any => result.warnings.push(ValidationWarning {
-                    kind: ValidationWarnings::UsedDepricated(Depricated::Any),
-                    node_name: node.name.clone(),
-                }),
-                MatchToken::Token(kind) => match kind {
-                    TokenKinds::Token(txt) => {
-                        if txt.is_empty() {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::EmptyToken,
-                                node_name: node.name.clone(),
-                            });
-                            return;
-                        }
-                        // check if token is in the lexer
-                        if !lexer.token_kinds.iter().any(|k| k == txt) {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::TokenNotFound(txt.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    }
-                    _ => {}
-                },
-                _ => {}
-            }
-        }
-
-        pub fn validate_parameters(
-            &self,
-            parameters: &Vec<Parameters>,
-            node: &Node,
-            laf: &mut LostAndFound,
-            result: &mut ValidationResult,
-        ) {
-            for parameter in parameters {
-                match parameter {
-                    Parameters::Set(name) => match node.variables.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Node => (),
-                            VariableKind::NodeList => (),
-                            VariableKind::Boolean => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Number => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::VariableNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::Global(name) => match self.globals.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Node => (),
-                            VariableKind::NodeList => (),
-                            VariableKind::Boolean => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Number => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::GlobalNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::Increment(name) => match node.variables.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Number => (),
-                            VariableKind::Node => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::NodeList => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Boolean => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::VariableNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::Decrement(name) => match node.variables.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Number => (),
-                            VariableKind::Node => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::NodeList => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Boolean => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::VariableNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::IncrementGlobal(name) => match self.globals.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Number => (),
-                            VariableKind::Node => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::NodeList => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Boolean => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::GlobalNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::True(name) => match node.variables.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Boolean => (),
-                            VariableKind::Node => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::NodeList => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Number => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::VariableNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::False(name) => match node.variables.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Boolean => (),
-                            VariableKind::Node => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::NodeList => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Number => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::VariableNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::TrueGlobal(name) => match self.globals.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Boolean => (),
-                            VariableKind::Node => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::NodeList => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Number => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::GlobalNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::FalseGlobal(name) => match self.globals.get(name) {
-                        Some(var) => match var {
-                            VariableKind::Boolean => (),
-                            VariableKind::Node => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::NodeList => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                            VariableKind::Number => result.errors.push(ValidationError {
-                                kind: ValidationErrors::CantUseVariable(name.clone()),
-                                node_name: node.name.clone(),
-                            }),
-                        },
-                        None => {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::GlobalNotFound(name.clone()),
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    },
-                    Parameters::Print(_) => {
-                        result.warnings.push(ValidationWarning {
-                            kind: ValidationWarnings::UsedPrint,
-                            node_name: node.name.clone(),
-                        });
-                    }
-                    Parameters::Debug(node_option) => {
-                        match node_option {
-                            Some(name) => match node.variables.get(name) {
-                                Some(_) => (),
-                                None => {
-                                    result.errors.push(ValidationError {
-                                        kind: ValidationErrors::VariableNotFound(name.clone()),
-                                        node_name: node.name.clone(),
-                                    });
-                                }
-                            },
-                            None => (),
-                        }
-                        result.warnings.push(ValidationWarning {
-                            kind: ValidationWarnings::UsedDebug,
-                            node_name: node.name.clone(),
-                        });
-                    }
-                    Parameters::Back(n) => {
-                        result.warnings.push(ValidationWarning {
-                            kind: ValidationWarnings::UsedDepricated(Depricated::Back),
-                            node_name: node.name.clone(),
-                        });
-                        if *n as usize > laf.steps {
-                            result.errors.push(ValidationError {
-                                kind: ValidationErrors::CannotGoBackMoreThan {
-                                    steps: *n as usize,
-                                    max: laf.steps,
-                                },
-                                node_name: node.name.clone(),
-                            });
-                        }
-                    }
-                    Parameters::Return => (),
-                    Parameters::Break(_) => (),
-                    Parameters::HardError(_) => (),
-                    Parameters::Goto(label) => {
-                        laf.lost_labels.push(label.clone());
-                    }
-                    Parameters::NodeStart => (),
-                    Parameters::NodeEnd => (),
-                }
-            }
-        }
-    }
-
-    pub struct ValidationResult {
-        pub errors: Vec<ValidationError>,
-        pub warnings: Vec<ValidationWarning>,
-    }
-
-    impl ValidationResult {
-        pub fn new() -> Self {
-            Self {
-                errors: Vec::new(),
-                warnings: Vec::new(),
-            }
-        }
-
-        /// Returns true if there are no errors and no warnings
-        ///
-        /// Choose this over `pass` for production code
-        ///
-        /// ```rust
-        /// let result = grammar.validate(&lexer);
-        /// if result.success() {
-        ///    println!("Grammar is valid and production ready");
-        /// } else {
-        ///   println!("Grammar is not valid");
-        /// }
-        /// ```
-        ///
-        pub fn success(&self) -> bool {
-            self.errors.is_empty() && self.warnings.is_empty()
-        }
-
-        /// Returns true if there are no errors
-        ///
-        /// Choose this over `success` for testing code
-        ///
-        /// ```rust
-        /// let result = grammar.validate(&lexer);
-        /// if result.pass() {
-        ///   println!("Grammar is valid and good for testing");
-        /// } else {
-        ///  println!("Grammar is not valid");
-        /// }
-        /// ```
-        ///
-        pub fn pass(&self) -> bool {
-            self.errors.is_empty()
-        }
-    }
-
-    #[derive(Serialize, Deserialize, Debug, Clone)]
-    pub struct ValidationError {
-        pub kind: ValidationErrors,
-        pub node_name: String,
-    }
-
-    #[derive(Serialize, Deserialize, Debug, Clone)]
-    pub enum ValidationErrors {
-        NodeNotFound(String),
-        EnumeratorNotFound(String),
-        VariableNotFound(String),
-        GlobalNotFound(String),
-        CantUseVariable(String),
-        EmptyToken,
-        TokenNotFound(String),
-        DuplicateLabel(String),
-        LabelNotFound(String),
-        TokenCollision(String),
-        CannotGoBackMoreThan{
-            steps: usize,
-            max: usize,
-        }
-    }
-
-    #[derive(Serialize, Deserialize, Debug, Clone)]
-    pub struct ValidationWarning {
-        pub kind: ValidationWarnings,
-        pub node_name: String,
-    }
-
-    #[derive(Serialize, Deserialize, Debug, Clone)]
-    pub enum ValidationWarnings {
-        UnusedVariable(String),
-        UsedDebug,
-        UsedPrint,
-        UsedDepricated(Depricated),
-        UnusualToken(String, TokenErrors),
-        UnusedLabel(String),
-    }
-
-    #[derive(Serialize, Deserialize, Debug, Clone)]
-    pub enum TokenErrors {
-        NotAscii,
-        ContainsWhitespace,
-        TooLong,
-        StartsNumeric,
-    }
-
-    #[derive(Serialize, Deserialize, Debug, Clone)]
-    pub enum Depricated {
-        /// The node is depricated
-        ///
-        /// It is advised to use Goto instead
-        Back,
-        /// Maybe you should use a different approach
-        Any,
-    }
-
-    /// This is a structure that keeps track of things that are hard to find
-    #[derive(Debug)]
-    pub struct LostAndFound {
-        pub lost_labels: Vec<String>,
-        pub found_labels: Vec<String>,
-        /// The maximum number of steps that can be taken back
-        pub steps: usize,
-    }
-
-    impl LostAndFound {
-        pub fn new() -> Self {
-            Self {
-                lost_labels: Vec::new(),
-                found_labels: Vec::new(),
-                steps: 0,
-            }
-        }
-
-        pub fn pass(&self, result: &mut ValidationResult, node_name: &str) {
-            for looking_for in &self.lost_labels {
-                if !self.found_labels.contains(looking_for) {
-                    result.errors.push(ValidationError {
-                        kind: ValidationErrors::LabelNotFound(looking_for.clone()),
-                        node_name: node_name.to_string(),
-                    });
-                }
-            }
-            for found in &self.found_labels {
-                if !self.lost_labels.contains(found) {
-                    result.warnings.push(ValidationWarning {
-                        kind: ValidationWarnings::UnusedLabel(found.clone()),
-                        node_name: node_name.to_string(),
-                    });
-                }
-            }
-        }
-    }
-}
+use crate::Map;
+
+use crate::lexer::TokenKinds;
+
+use serde::{Deserialize, Serialize};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+        use std::fmt;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::format;
+        use core::fmt;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Grammar {
+    pub nodes: Map<String, Node>,
+    pub enumerators: Map<String, Enumerator>,
+    pub globals: Map<String, VariableKind>,
+    /// If true, the parser will throw an error if the last token is not EOF
+    pub eof: bool,
+    /// Names that were already taken when importing other grammars
+    #[serde(default)]
+    pub clashes: Vec<Clash>,
+    /// Nodes that take tokens as arguments, see `Grammar::instantiate`
+    #[serde(default)]
+    pub templates: Map<String, Template>,
+}
+
+impl Grammar {
+    pub fn new() -> Grammar {
+        Grammar {
+            nodes: Map::new(),
+            enumerators: Map::new(),
+            globals: Map::new(),
+            eof: true,
+            clashes: Vec::new(),
+            templates: Map::new(),
+        }
+    }
+
+    pub fn add_node(&mut self, node: Node) {
+        self.nodes.insert(node.name.clone(), node);
+    }
+
+    pub fn add_template(&mut self, template: Template) {
+        self.templates.insert(template.name.clone(), template);
+    }
+
+    /// Returns true if the node was created by instantiating a template
+    pub fn is_instance(&self, name: &str) -> bool {
+        match name.split_once('<') {
+            Some((template, _)) => self.templates.contains_key(template),
+            None => false,
+        }
+    }
+
+    /// Creates a node for every `MatchToken::Template` used in the grammar
+    ///
+    /// The node is a copy of the template with the parameters replaced by the arguments
+    /// and it is named after the instantiation, for example `SeparatedList<value, ",">`.
+    /// Templates used inside of templates are instantiated as well.
+    ///
+    /// This has to be called after changing the grammar, otherwise the parser
+    /// will not find the nodes. Instances that already exist are kept.
+    pub fn instantiate(&mut self) -> Result<(), TemplateError> {
+        let mut pending = Vec::new();
+        for node in self.nodes.values_mut() {
+            for_each_token(&mut node.rules, &mut |token| {
+                template_uses(token, 0, &mut pending)
+            });
+        }
+        for enumerator in self.enumerators.values() {
+            for value in &enumerator.values {
+                template_uses(value, 0, &mut pending);
+            }
+        }
+        while let Some((name, arguments, depth)) = pending.pop() {
+            let instance = instance_name(&name, &arguments);
+            if self.nodes.contains_key(&instance) {
+                continue;
+            }
+            let template = match self.templates.get(&name) {
+                Some(template) => template,
+                None => return Err(TemplateError::NotFound(name)),
+            };
+            if template.parameters.len() != arguments.len() {
+                return Err(TemplateError::Arguments {
+                    template: name,
+                    expected: template.parameters.len(),
+                    found: arguments.len(),
+                });
+            }
+            if depth >= MAX_TEMPLATE_DEPTH {
+                return Err(TemplateError::TooDeep {
+                    template: name,
+                    instance,
+                });
+            }
+            let mut rules = template.rules.clone();
+            for_each_token(&mut rules, &mut |token| {
+                substitute(token, &template.parameters, &arguments);
+                template_uses(token, depth + 1, &mut pending);
+            });
+            let node = Node {
+                name: instance.clone(),
+                rules,
+                variables: template.variables.clone(),
+            };
+            self.nodes.insert(instance, node);
+        }
+        Ok(())
+    }
+
+    /// Imports nodes, enumerators and globals of another grammar under a namespace
+    ///
+    /// Everything is renamed to `namespace::name` (including the references inside
+    /// of the imported rules), so the imported nodes can be matched using
+    /// `MatchToken::Node("namespace::name")`
+    ///
+    /// If a name is already taken, the existing definition is kept and the clash
+    /// is reported by the validator
+    ///
+    /// Templates are imported as well and their instances are created again under the new names
+    pub fn import(&mut self, namespace: &str, other: &Grammar) {
+        for node in other.nodes.values() {
+            if other.is_instance(&node.name) {
+                continue;
+            }
+            let mut node = node.clone();
+            node.name = namespaced(namespace, &node.name);
+            other.namespace_rules(namespace, &mut node.rules);
+            if self.nodes.contains_key(&node.name) {
+                self.clashes.push(Clash::Node(node.name));
+                continue;
+            }
+            self.add_node(node);
+        }
+        for enumerator in other.enumerators.values() {
+            let mut enumerator = enumerator.clone();
+            enumerator.name = namespaced(namespace, &enumerator.name);
+            for value in enumerator.values.iter_mut() {
+                other.namespace_token(namespace, value);
+            }
+            if self.enumerators.contains_key(&enumerator.name) {
+                self.clashes.push(Clash::Enumerator(enumerator.name));
+                continue;
+            }
+            self.enumerators.insert(enumerator.name.clone(), enumerator);
+        }
+        for (name, kind) in &other.globals {
+            let name = namespaced(namespace, name);
+            if self.globals.contains_key(&name) {
+                self.clashes.push(Clash::Global(name));
+                continue;
+            }
+            self.globals.insert(name, kind.clone());
+        }
+        for template in other.templates.values() {
+            let mut template = template.clone();
+            template.name = namespaced(namespace, &template.name);
+            other.namespace_rules(namespace, &mut template.rules);
+            if self.templates.contains_key(&template.name) {
+                self.clashes.push(Clash::Template(template.name));
+                continue;
+            }
+            self.add_template(template);
+        }
+        for clash in &other.clashes {
+            self.clashes.push(match clash {
+                Clash::Node(name) => Clash::Node(namespaced(namespace, name)),
+                Clash::Enumerator(name) => Clash::Enumerator(namespaced(namespace, name)),
+                Clash::Global(name) => Clash::Global(namespaced(namespace, name)),
+                Clash::Template(name) => Clash::Template(namespaced(namespace, name)),
+                clash => clash.clone(),
+            });
+        }
+        // missing templates are reported by the validator
+        let _ = self.instantiate();
+    }
+
+    /// Renames references to the nodes and enumerators of this grammar
+    fn namespace_token(&self, namespace: &str, token: &mut MatchToken) {
+        match token {
+            MatchToken::Node(name) if self.nodes.contains_key(name) => {
+                *name = namespaced(namespace, name);
+            }
+            MatchToken::Enumerator(name) if self.enumerators.contains_key(name) => {
+                *name = namespaced(namespace, name);
+            }
+            MatchToken::Template(name, arguments) => {
+                if self.templates.contains_key(name) {
+                    *name = namespaced(namespace, name);
+                }
+                for argument in arguments {
+                    self.namespace_token(namespace, argument);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Renames references to the globals of this grammar
+    fn namespace_global(&self, namespace: &str, name: &mut String) {
+        if self.globals.contains_key(name) {
+            *name = namespaced(namespace, name);
+        }
+    }
+
+    fn namespace_parameters(&self, namespace: &str, parameters: &mut [Parameters]) {
+        for parameter in parameters {
+            match parameter {
+                Parameters::Global(name)
+                | Parameters::IncrementGlobal(name)
+                | Parameters::TrueGlobal(name)
+                | Parameters::FalseGlobal(name) => self.namespace_global(namespace, name),
+                _ => (),
+            }
+        }
+    }
+
+    fn namespace_rules(&self, namespace: &str, rules: &mut Rules) {
+        for rule in rules {
+            match rule {
+                Rule::Is {
+                    token,
+                    rules,
+                    parameters,
+                }
+                | Rule::Isnt {
+                    token,
+                    rules,
+                    parameters,
+                }
+                | Rule::While {
+                    token,
+                    rules,
+                    parameters,
+                }
+                | Rule::Until {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    self.namespace_token(namespace, token);
+                    self.namespace_parameters(namespace, parameters);
+                    self.namespace_rules(namespace, rules);
+                }
+                Rule::IsOneOf { tokens } | Rule::UntilOneOf { tokens } => {
+                    self.namespace_options(namespace, tokens);
+                }
+                Rule::Maybe {
+                    token,
+                    is,
+                    isnt,
+                    parameters,
+                } => {
+                    self.namespace_token(namespace, token);
+                    self.namespace_parameters(namespace, parameters);
+                    self.namespace_rules(namespace, is);
+                    self.namespace_rules(namespace, isnt);
+                }
+                Rule::MaybeOneOf { is_one_of, isnt } => {
+                    self.namespace_options(namespace, is_one_of);
+                    self.namespace_rules(namespace, isnt);
+                }
+                Rule::Loop { rules } => self.namespace_rules(namespace, rules),
+                Rule::Command {
+                    command:
+                        Commands::Compare {
+                            left, right, rules, ..
+                        },
+                } => {
+                    self.namespace_global(namespace, left);
+                    self.namespace_global(namespace, right);
+                    self.namespace_rules(namespace, rules);
+                }
+                Rule::Command { .. } | Rule::Debug { .. } => (),
+            }
+        }
+    }
+
+    fn namespace_options(&self, namespace: &str, options: &mut [OneOf]) {
+        for option in options {
+            self.namespace_token(namespace, &mut option.token);
+            self.namespace_parameters(namespace, &mut option.parameters);
+            self.namespace_rules(namespace, &mut option.rules);
+        }
+    }
+}
+
+fn namespaced(namespace: &str, name: &str) -> String {
+    format!("{}::{}", namespace, name)
+}
+
+/// Templates instantiating templates deeper than this are most likely infinite
+const MAX_TEMPLATE_DEPTH: usize = 32;
+
+/// Name of the node created by instantiating a template
+///
+/// The arguments are written the same way as in the grammar file: `SeparatedList<value, ",">`
+pub fn instance_name(template: &str, arguments: &[MatchToken]) -> String {
+    let arguments = arguments
+        .iter()
+        .map(crate::format::token)
+        .collect::<Vec<_>>();
+    format!("{}<{}>", template, arguments.join(", "))
+}
+
+/// Collects the templates used by a token (including the ones used as arguments)
+fn template_uses(
+    token: &MatchToken,
+    depth: usize,
+    uses: &mut Vec<(String, Vec<MatchToken>, usize)>,
+) {
+    if let MatchToken::Template(name, arguments) = token {
+        for argument in arguments {
+            template_uses(argument, depth, uses);
+        }
+        uses.push((name.clone(), arguments.clone(), depth));
+    }
+}
+
+/// Replaces template parameters with the arguments
+fn substitute(token: &mut MatchToken, parameters: &[String], arguments: &[MatchToken]) {
+    match token {
+        MatchToken::Node(name) => {
+            if let Some(i) = parameters.iter().position(|p| p == name) {
+                *token = arguments[i].clone();
+            }
+        }
+        MatchToken::Template(_, args) => {
+            for arg in args {
+                substitute(arg, parameters, arguments);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Calls `f` on every token matched by the rules
+fn for_each_token(rules: &mut Rules, f: &mut dyn FnMut(&mut MatchToken)) {
+    for rule in rules {
+        match rule {
+            Rule::Is { token, rules, .. }
+            | Rule::Isnt { token, rules, .. }
+            | Rule::While { token, rules, .. }
+            | Rule::Until { token, rules, .. } => {
+                f(token);
+                for_each_token(rules, f);
+            }
+            Rule::IsOneOf { tokens } | Rule::UntilOneOf { tokens } => {
+                for option in tokens {
+                    f(&mut option.token);
+                    for_each_token(&mut option.rules, f);
+                }
+            }
+            Rule::Maybe {
+                token, is, isnt, ..
+            } => {
+                f(token);
+                for_each_token(is, f);
+                for_each_token(isnt, f);
+            }
+            Rule::MaybeOneOf { is_one_of, isnt } => {
+                for option in is_one_of {
+                    f(&mut option.token);
+                    for_each_token(&mut option.rules, f);
+                }
+                for_each_token(isnt, f);
+            }
+            Rule::Loop { rules }
+            | Rule::Command {
+                command: Commands::Compare { rules, .. },
+            } => for_each_token(rules, f),
+            Rule::Command { .. } | Rule::Debug { .. } => (),
+        }
+    }
+}
+
+/// A name that was defined more than once while importing a grammar
+///
+/// Names of the lexer (patterns, literals, keywords, modes and the tokens of mode
+/// changes) are not namespaced, they only clash when the definitions are different
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Clash {
+    Node(String),
+    Enumerator(String),
+    Global(String),
+    Template(String),
+    Pattern(String),
+    Literal(String),
+    Keyword(String),
+    Mode(String),
+    ModeAction(String),
+}
+
+/// A node that takes tokens as arguments
+///
+/// Parameters are used in the rules like nodes and get replaced by the arguments
+/// when the template is instantiated
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Template {
+    pub name: String,
+    pub parameters: Vec<String>,
+    pub rules: Rules,
+    pub variables: Map<String, VariableKind>,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum TemplateError {
+    /// Template that is used does not exist
+    NotFound(String),
+    /// Template is used with a different number of arguments than it has parameters
+    Arguments {
+        template: String,
+        expected: usize,
+        found: usize,
+    },
+    /// Template keeps instantiating itself with new arguments
+    TooDeep { template: String, instance: String },
+}
+
+impl fmt::Debug for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::NotFound(name) => write!(f, "Template not found: {}", name),
+            TemplateError::Arguments {
+                template,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Template {} expects {} argument(s), found {}",
+                template, expected, found
+            ),
+            TemplateError::TooDeep { instance, .. } => {
+                write!(f, "Template instantiation is too deep: {}", instance)
+            }
+        }
+    }
+}
+
+/// A collection of rules
+pub type Rules = Vec<Rule>;
+
+/// A rule defines how a token will be matched and what will happen if it is matched
+///
+/// It also contains parameters that can be used if the rule is matched
+///
+/// Special kind of rules are commands that can be executed without matching a token
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// Matches a token
+    ///
+    /// If the token is matched, the rules will be executed
+    ///
+    /// If the token is not matched, the node will end with an error
+    Is {
+        token: MatchToken,
+        rules: Rules,
+        parameters: Vec<Parameters>,
+    },
+    /// Matches a token
+    ///
+    /// If the token is matched, the node will end with an error
+    ///
+    /// If the token is not matched, the rules will be executed
+    Isnt {
+        token: MatchToken,
+        rules: Rules,
+        parameters: Vec<Parameters>,
+    },
+    /// Matches one of the tokens
+    ///
+    /// If one of the tokens is matched, the rules will be executed
+    ///
+    /// If none of the tokens is matched, the node will end with an error
+    IsOneOf { tokens: Vec<OneOf> },
+    /// Matches a token
+    ///
+    /// If the token is matched, the rules will be executed
+    ///
+    /// If the token is not matched, the rules for the else branch will be executed
+    Maybe {
+        /// Token that will be matched
+        token: MatchToken,
+        /// Rules that will be executed if the token is matched
+        is: Rules,
+        /// Rules that will be executed if the token is not matched
+        isnt: Rules,
+        /// Parameters that can be used if the token is matched
+        parameters: Vec<Parameters>,
+    },
+    /// Matches one of the tokens
+    ///
+    /// If one of the tokens is matched, the rules will be executed
+    ///
+    /// If none of the tokens is matched, the rules for the else branch will be executed
+    MaybeOneOf {
+        /// Tokens that will be matched
+        is_one_of: Vec<OneOf>,
+        /// Rules that will be executed if none of the tokens is matched
+        isnt: Rules,
+    },
+    /// Matches a token
+    ///
+    /// If the token is matched, the rules will be executed
+    ///
+    /// After the rules are executed, the token will be matched again
+    /// and the rules will be executed again (if the token is matched)
+    While {
+        token: MatchToken,
+        rules: Rules,
+        /// Parameters that can be used if the token is matched
+        ///
+        /// The parameters will be used once every time the token is matched
+        parameters: Vec<Parameters>,
+    },
+    /// Loop that will be executed until a break command is executed
+    Loop { rules: Rules },
+    /// Searches in the tokens until a token is matched
+    Until {
+        token: MatchToken,
+        rules: Rules,
+        parameters: Vec<Parameters>,
+    },
+    /// Searches in the tokens until one of the tokens is matched
+    UntilOneOf { tokens: Vec<OneOf> },
+    /// Performs a command
+    ///
+    /// The command will be executed without matching a token
+    Command { command: Commands },
+    Debug { target: Option<String> },
+}
+
+/// One of the tokens that will be matched
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OneOf {
+    pub token: MatchToken,
+    pub rules: Rules,
+    pub parameters: Vec<Parameters>,
+}
+
+/// Commands that can be executed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Commands {
+    /// Compares two variables/numbers and executes rules if the comparison is true
+    Compare {
+        /// Left side of the comparison
+        left: String,
+        /// Right side of the comparison
+        right: String,
+        /// Comparison operator
+        comparison: Comparison,
+        /// Rules that will be executed if the comparison is true
+        rules: Rules,
+    },
+    /// Returns an error from node
+    Error {
+        message: String,
+    },
+    HardError {
+        set: bool,
+    },
+    Goto {
+        label: String,
+    },
+    Label {
+        name: String,
+    },
+    Print {
+        message: String,
+    },
+}
+
+/// Comparison operators
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Comparison {
+    /// ==
+    Equal,
+    /// !=
+    NotEqual,
+    /// >
+    GreaterThan,
+    /// <
+    LessThan,
+    /// >=
+    GreaterThanOrEqual,
+    /// <=
+    LessThanOrEqual,
+}
+
+/// A token that will be matched
+///
+/// Can be a token kind or a node name
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum MatchToken {
+    /// A token kind
+    Token(TokenKinds),
+    /// A node name
+    Node(String),
+    /// A constant word
+    Word(String),
+    /// An enumerator
+    Enumerator(String),
+    /// A template instantiated with arguments
+    Template(String, Vec<MatchToken>),
+    /// Any token
+    Any,
+}
+
+/// A node is a collection of rules that will be executed when the node is matched
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Node {
+    /// Name of the node
+    pub name: String,
+    /// Rules that will be executed when the node is matched
+    pub rules: Rules,
+    /// Variables that can be used in the node and will be accessible from the outside
+    pub variables: Map<String, VariableKind>,
+}
+
+/// A variable that can be used in a node
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum VariableKind {
+    /// Holds a single node
+    Node,
+    /// Holds a list of nodes
+    NodeList,
+    /// Holds a boolean
+    Boolean,
+    /// Holds a number
+    Number,
+}
+
+/// Parameters that can be used on a rule if it is matched
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum Parameters {
+    /// Sets a variable to a value
+    Set(String),
+    /// Sets a global variable to a value
+    Global(String),
+    /// Adds 1 to a variable of type Count
+    Increment(String),
+    /// Subtracts 1 from a variable of type Count
+    Decrement(String),
+    /// Adds 1 to a global variable of type Count
+    IncrementGlobal(String),
+    /// Sets a variable to true
+    True(String),
+    /// Sets a variable to false
+    False(String),
+    /// Sets a global variable to true
+    TrueGlobal(String),
+    /// Sets a global variable to false
+    FalseGlobal(String),
+    /// Prints string
+    Print(String),
+    /// Prints current token or variable
+    Debug(Option<String>),
+    /// Goes back in rules
+    Back(u8),
+    /// Returns from node
+    Return,
+    /// Breaks from rule blocks(n)
+    Break(usize),
+    /// If the node ends with an error, it will be a hard error
+    /// resulting in the parent node to also end with an error
+    ///
+    /// This is a way of telling the parser that the current node MUST match
+    ///
+    /// This is useful for using nodes in optional rules
+    HardError(bool),
+    /// Sets the current node to the label with the given name
+    Goto(String),
+    /// Hints to the parser that the node starts here
+    ///
+    /// This should be used at the start of every node
+    /// because it will prevent the parser from counting
+    /// whitespace in front of the node
+    NodeStart,
+    /// Hints to the parser that the node ends here
+    NodeEnd,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Enumerator {
+    pub name: String,
+    pub values: Vec<MatchToken>,
+}
+
+/// validation module for grammar that is otherwise dynamically typed
+///
+/// This module is used to validate the grammar and make sure that it is correct
+///
+/// The grammar is validated by checking if the rules are correct and if the variables are used correctly
+///
+/// > note: Grammar errors have caused me a lot of headache in the past so using this module is highly recommended
+pub mod validator {
+    use super::*;
+    use crate::lexer::*;
+
+    impl Lexer {
+        pub fn validate_tokens(&self, result: &mut ValidationResult) {
+            let mut tokens: Vec<String> = Vec::new();
+            for token in &self.token_kinds {
+                // tokens that have already been validated can be ignored
+                if tokens.contains(token) {
+                    continue;
+                }
+                tokens.push(token.clone());
+                // check for collisions
+                if self.token_kinds.iter().filter(|t| *t == token).count() > 1 {
+                    result.errors.push(ValidationError {
+                        kind: ValidationErrors::TokenCollision(token.clone()),
+                        node_name: "__lexer__".to_string(),
+                    });
+                }
+                // check if token is empty
+                if token.is_empty() {
+                    result.errors.push(ValidationError {
+                        kind: ValidationErrors::EmptyToken,
+                        node_name: "__lexer__".to_string(),
+                    });
+                }
+                // check if it starts with a number
+                let first = token.chars().next().unwrap();
+                if first.is_numeric() {
+                    result.warnings.push(ValidationWarning {
+                        kind: ValidationWarnings::UnusualToken(
+                            token.clone(),
+                            TokenErrors::StartsNumeric,
+                        ),
+                        node_name: "__lexer__".to_string(),
+                    });
+                }
+
+                // check if it contains a whitespace
+                if token.chars().any(|c| c.is_whitespace()) {
+                    result.warnings.push(ValidationWarning {
+                        kind: ValidationWarnings::UnusualToken(
+                            token.clone(),
+                            TokenErrors::ContainsWhitespace,
+                        ),
+                        node_name: "__lexer__".to_string(),
+                    });
+                }
+
+                // check if it is longer than 2 characters
+                if token.len() > 2 {
+                    result.warnings.push(ValidationWarning {
+                        kind: ValidationWarnings::UnusualToken(token.clone(), TokenErrors::TooLong),
+                        node_name: "__lexer__".to_string(),
+                    });
+                }
+
+                // check if it is not ascii
+                if !token.chars().all(|c| c.is_ascii()) {
+                    result.warnings.push(ValidationWarning {
+                        kind: ValidationWarnings::UnusualToken(
+                            token.clone(),
+                            TokenErrors::NotAscii,
+                        ),
+                        node_name: "__lexer__".to_string(),
+                    });
+                }
+            }
+
+            for keyword in &self.keywords {
+                if keyword.word.is_empty() {
+                    result.errors.push(ValidationError {
+                        kind: ValidationErrors::EmptyToken,
+                        node_name: "__lexer__".to_string(),
+                    });
+                    continue;
+                }
+                // a token that starts the keyword splits the words the keyword does not match
+                for token in &self.token_kinds {
+                    let overlaps = match keyword.case_insensitive {
+                        true => keyword
+                            .word
+                            .to_lowercase()
+                            .starts_with(&token.to_lowercase()),
+                        false => keyword.word.starts_with(token.as_str()),
+                    };
+                    if overlaps && !token.is_empty() {
+                        result.warnings.push(ValidationWarning {
+                            kind: ValidationWarnings::KeywordOverlap(
+                                keyword.word.clone(),
+                                token.clone(),
+                            ),
+                            node_name: "__lexer__".to_string(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    impl Grammar {
+        /// Validates the grammar
+        pub fn validate(&self, lexer: &Lexer) -> ValidationResult {
+            let mut result = ValidationResult::new();
+            lexer.validate_tokens(&mut result);
+
+            for clash in &self.clashes {
+                let name = match clash {
+                    Clash::Node(name)
+                    | Clash::Enumerator(name)
+                    | Clash::Global(name)
+                    | Clash::Template(name)
+                    | Clash::Pattern(name)
+                    | Clash::Literal(name)
+                    | Clash::Keyword(name)
+                    | Clash::Mode(name)
+                    | Clash::ModeAction(name) => name,
+                };
+                result.errors.push(ValidationError {
+                    kind: ValidationErrors::NameClash(clash.clone()),
+                    node_name: name.clone(),
+                });
+            }
+
+            for node in self.nodes.values() {
+                self.validate_node(node, lexer, &mut result);
+            }
+
+            result
+        }
+
+        pub fn validate_node(&self, node: &Node, lexer: &Lexer, result: &mut ValidationResult) {
+            let mut laf = LostAndFound::new();
+            for rule in &node.rules {
+                self.validate_rule(rule, node, lexer, &mut laf, result);
+            }
+            laf.pass(result, &node.name);
+        }
+
+        pub fn validate_rule(
+            &self,
+            rule: &Rule,
+            node: &Node,
+            lexer: &Lexer,
+            laf: &mut LostAndFound,
+            result: &mut ValidationResult,
+        ) {
+            match rule {
+                Rule::Is {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    self.validate_token(token, node, lexer, laf, result);
+                    self.validate_parameters(parameters, node, laf, result);
+                    self.validate_ruleblock(rules, node, lexer, laf, result)
+                }
+                Rule::Isnt {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    self.validate_token(token, node, lexer, laf, result);
+                    self.validate_parameters(parameters, node, laf, result);
+                    self.validate_ruleblock(rules, node, lexer, laf, result)
+                }
+                Rule::IsOneOf { tokens } => {
+                    for one_of in tokens {
+                        self.validate_token(&one_of.token, node, lexer, laf, result);
+                        self.validate_parameters(&one_of.parameters, node, laf, result);
+                        self.validate_ruleblock(&one_of.rules, node, lexer, laf, result)
+                    }
+                }
+                Rule::Maybe {
+                    token,
+                    is,
+                    isnt,
+                    parameters,
+                } => {
+                    self.validate_token(token, node, lexer, laf, result);
+                    self.validate_parameters(parameters, node, laf, result);
+                    self.validate_ruleblock(is, node, lexer, laf, result);
+                    self.validate_ruleblock(isnt, node, lexer, laf, result);
+                }
+                Rule::MaybeOneOf { is_one_of, isnt } => {
+                    for OneOf { token, rules, parameters } in is_one_of {
+                        self.validate_token(token, node, lexer, laf, result);
+                        self.validate_parameters(parameters, node, laf, result);
+                        self.validate_ruleblock(rules, node, lexer, laf, result);
+                    }
+                    self.validate_ruleblock(isnt, node, lexer, laf, result);
+                }
+                Rule::While {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    self.validate_token(token, node, lexer, laf, result);
+                    self.validate_parameters(parameters, node, laf, result);
+                    self.validate_ruleblock(rules, node, lexer, laf, result)
+                }
+                Rule::Loop { rules } => {
+                    self.validate_ruleblock(rules, node, lexer, laf, result)
+                }
+                Rule::Until {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    self.validate_token(token, node, lexer, laf, result);
+                    self.validate_parameters(parameters, node, laf, result);
+                    self.validate_ruleblock(rules, node, lexer, laf, result)
+                }
+                Rule::UntilOneOf { tokens } => {
+                    for one_of in tokens {
+                        self.validate_token(&one_of.token, node, lexer, laf, result);
+                        self.validate_parameters(&one_of.parameters, node, laf, result);
+                        self.validate_ruleblock(&one_of.rules, node, lexer, laf, result)
+                    }
+                }
+                Rule::Command { command } => match command {
+                    Commands::Compare {
+                        left,
+                        right,
+                        comparison: _,
+                        rules,
+                    } => {
+                        match self.globals.get(left) {
+                            Some(var) => match var {
+                                VariableKind::Number => (),
+                                _ => result.errors.push(ValidationError {
+                                    kind: ValidationErrors::CantUseVariable(left.clone()),
+                                    node_name: node.name.clone(),
+                                }),
+                            },
+                            None => {
+                                match node.variables.get(left) {
+                                    Some(var) => match var {
+                                        VariableKind::Number => (),
+                                        _ => result.errors.push(ValidationError {
+                                            kind: ValidationErrors::CantUseVariable(left.clone()),
+                                            node_name: node.name.clone(),
+                                        }),
+                                    },
+                                    None => {
+                                        result.errors.push(ValidationError {
+                                            kind: ValidationErrors::GlobalNotFound(left.clone()),
+                                            node_name: node.name.clone(),
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                        match self.globals.get(right) {
+                            Some(var) => match var {
+                                VariableKind::Number => (),
+                                _ => result.errors.push(ValidationError {
+                                    kind: ValidationErrors::CantUseVariable(right.clone()),
+                                    node_name: node.name.clone(),
+                                }),
+                            },
+                            None => {
+                                match node.variables.get(right) {
+                                    Some(var) => match var {
+                                        VariableKind::Number => (),
+                                        _ => result.errors.push(ValidationError {
+                                            kind: ValidationErrors::CantUseVariable(right.clone()),
+                                            node_name: node.name.clone(),
+                                        }),
+                                    },
+                                    None => {
+                                        result.errors.push(ValidationError {
+                                            kind: ValidationErrors::GlobalNotFound(right.clone()),
+                                            node_name: node.name.clone(),
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                        for rule in rules {
+                            self.validate_rule(rule, node, lexer, laf, result);
+                        }
+                    }
+                    Commands::Error { message: _ } => (),
+                    Commands::HardError { set: _ } => (),
+                    Commands::Goto { label } => {
+                        laf.lost_labels.push(label.clone());
+                    }
+                    Commands::Label { name } => {
+                        if laf.found_labels.contains(&name) {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::DuplicateLabel(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                        laf.found_labels.push(name.clone());
+                    }
+                    Commands::Print { message: _ } => (),
+                },
+                Rule::Debug { target } => {
+                    match target {
+                        Some(name) => match node.variables.get(name) {
+                            Some(_) => (),
+                            None => {
+                                result.errors.push(ValidationError {
+                                    kind: ValidationErrors::VariableNotFound(name.clone()),
+                                    node_name: node.name.clone(),
+                                });
+                            }
+                        },
+                        None => (),
+                    }
+                }
+            }
+        }
+
+        pub fn validate_ruleblock(
+            &self,
+            ruleblock: &Vec<Rule>,
+            node: &Node,
+            lexer: &Lexer,
+            laf: &mut LostAndFound,
+            result: &mut ValidationResult,
+        ) {
+            let steps = laf.steps;
+            for rule in ruleblock {
+                laf.steps += 1;
+                self.validate_rule(rule, node, lexer, laf, result);
+            }
+            laf.steps = steps;
+        }
+
+        pub fn validate_token(
+            &self,
+            token: &MatchToken,
+            node: &Node,
+            lexer: &Lexer,
+            _laf: &mut LostAndFound,
+            result: &mut ValidationResult,
+        ) {
+            match token {
+                MatchToken::Node(name) => {
+                    if !self.nodes.contains_key(name) {
+                        result.errors.push(ValidationError {
+                            kind: ValidationErrors::NodeNotFound(name.clone()),
+                            node_name: node.name.clone(),
+                        });
+                    }
+                }
+                MatchToken::Enumerator(enumerator) => {
+                    if !self.enumerators.contains_key(enumerator) {
+                        result.errors.push(ValidationError {
+                            kind: ValidationErrors::EnumeratorNotFound(enumerator.clone()),
+                            node_name: node.name.clone(),
+                        });
+                    }
+                }
+                MatchToken::Template(name, arguments) => {
+                    let kind = match self.templates.get(name) {
+                        None => ValidationErrors::TemplateNotFound(name.clone()),
+                        Some(template) if template.parameters.len() != arguments.len() => {
+                            ValidationErrors::TemplateArguments {
+                                template: name.clone(),
+                                expected: template.parameters.len(),
+                                found: arguments.len(),
+                            }
+                        }
+                        Some(_) => {
+                            let instance = instance_name(name, arguments);
+                            if self.nodes.contains_key(&instance) {
+                                return;
+                            }
+                            // Grammar::instantiate was not called
+                            ValidationErrors::NodeNotFound(instance)
+                        }
+                    };
+                    result.errors.push(ValidationError {
+                        kind,
+                        node_name: node.name.clone(),
+                    });
+                }
+                MatchToken::Any => result.warnings.push(ValidationWarning {
+                    kind: ValidationWarnings::UsedDepricated(Depricated::Any),
+                    node_name: node.name.clone(),
+                }),
+                MatchToken::Token(kind) => match kind {
+                    TokenKinds::Token(txt) => {
+                        if txt.is_empty() {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::EmptyToken,
+                                node_name: node.name.clone(),
+                            });
+                            return;
+                        }
+                        // check if token is in the lexer
+                        if !lexer.token_kinds.iter().any(|k| k == txt) {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::TokenNotFound(txt.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        pub fn validate_parameters(
+            &self,
+            parameters: &Vec<Parameters>,
+            node: &Node,
+            laf: &mut LostAndFound,
+            result: &mut ValidationResult,
+        ) {
+            for parameter in parameters {
+                match parameter {
+                    Parameters::Set(name) => match node.variables.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Node => (),
+                            VariableKind::NodeList => (),
+                            VariableKind::Boolean => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Number => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::VariableNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::Global(name) => match self.globals.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Node => (),
+                            VariableKind::NodeList => (),
+                            VariableKind::Boolean => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Number => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::GlobalNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::Increment(name) => match node.variables.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Number => (),
+                            VariableKind::Node => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::NodeList => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Boolean => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::VariableNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::Decrement(name) => match node.variables.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Number => (),
+                            VariableKind::Node => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::NodeList => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Boolean => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::VariableNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::IncrementGlobal(name) => match self.globals.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Number => (),
+                            VariableKind::Node => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::NodeList => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Boolean => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::GlobalNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::True(name) => match node.variables.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Boolean => (),
+                            VariableKind::Node => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::NodeList => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Number => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::VariableNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::False(name) => match node.variables.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Boolean => (),
+                            VariableKind::Node => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::NodeList => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Number => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::VariableNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::TrueGlobal(name) => match self.globals.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Boolean => (),
+                            VariableKind::Node => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::NodeList => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Number => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::GlobalNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::FalseGlobal(name) => match self.globals.get(name) {
+                        Some(var) => match var {
+                            VariableKind::Boolean => (),
+                            VariableKind::Node => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::NodeList => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                            VariableKind::Number => result.errors.push(ValidationError {
+                                kind: ValidationErrors::CantUseVariable(name.clone()),
+                                node_name: node.name.clone(),
+                            }),
+                        },
+                        None => {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::GlobalNotFound(name.clone()),
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    },
+                    Parameters::Print(_) => {
+                        result.warnings.push(ValidationWarning {
+                            kind: ValidationWarnings::UsedPrint,
+                            node_name: node.name.clone(),
+                        });
+                    }
+                    Parameters::Debug(node_option) => {
+                        match node_option {
+                            Some(name) => match node.variables.get(name) {
+                                Some(_) => (),
+                                None => {
+                                    result.errors.push(ValidationError {
+                                        kind: ValidationErrors::VariableNotFound(name.clone()),
+                                        node_name: node.name.clone(),
+                                    });
+                                }
+                            },
+                            None => (),
+                        }
+                        result.warnings.push(ValidationWarning {
+                            kind: ValidationWarnings::UsedDebug,
+                            node_name: node.name.clone(),
+                        });
+                    }
+                    Parameters::Back(n) => {
+                        result.warnings.push(ValidationWarning {
+                            kind: ValidationWarnings::UsedDepricated(Depricated::Back),
+                            node_name: node.name.clone(),
+                        });
+                        if *n as usize > laf.steps {
+                            result.errors.push(ValidationError {
+                                kind: ValidationErrors::CannotGoBackMoreThan {
+                                    steps: *n as usize,
+                                    max: laf.steps,
+                                },
+                                node_name: node.name.clone(),
+                            });
+                        }
+                    }
+                    Parameters::Return => (),
+                    Parameters::Break(_) => (),
+                    Parameters::HardError(_) => (),
+                    Parameters::Goto(label) => {
+                        laf.lost_labels.push(label.clone());
+                    }
+                    Parameters::NodeStart => (),
+                    Parameters::NodeEnd => (),
+                }
+            }
+        }
+    }
+
+    pub struct ValidationResult {
+        pub errors: Vec<ValidationError>,
+        pub warnings: Vec<ValidationWarning>,
+    }
+
+    impl ValidationResult {
+        pub fn new() -> Self {
+            Self {
+                errors: Vec::new(),
+                warnings: Vec::new(),
+            }
+        }
+
+        /// Returns true if there are no errors and no warnings
+        ///
+        /// Choose this over `pass` for production code
+        ///
+        /// ```rust
+        /// let result = grammar.validate(&lexer);
+        /// if result.success() {
+        ///    println!("Grammar is valid and production ready");
+        /// } else {
+        ///   println!("Grammar is not valid");
+        /// }
+        /// ```
+        ///
+        pub fn success(&self) -> bool {
+            self.errors.is_empty() && self.warnings.is_empty()
+        }
+
+        /// Returns true if there are no errors
+        ///
+        /// Choose this over `success` for testing code
+        ///
+        /// ```rust
+        /// let result = grammar.validate(&lexer);
+        /// if result.pass() {
+        ///   println!("Grammar is valid and good for testing");
+        /// } else {
+        ///  println!("Grammar is not valid");
+        /// }
+        /// ```
+        ///
+        pub fn pass(&self) -> bool {
+            self.errors.is_empty()
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct ValidationError {
+        pub kind: ValidationErrors,
+        pub node_name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub enum ValidationErrors {
+        NodeNotFound(String),
+        EnumeratorNotFound(String),
+        VariableNotFound(String),
+        GlobalNotFound(String),
+        CantUseVariable(String),
+        EmptyToken,
+        TokenNotFound(String),
+        DuplicateLabel(String),
+        LabelNotFound(String),
+        TokenCollision(String),
+        NameClash(Clash),
+        TemplateNotFound(String),
+        TemplateArguments {
+            template: String,
+            expected: usize,
+            found: usize,
+        },
+        CannotGoBackMoreThan{
+            steps: usize,
+            max: usize,
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub struct ValidationWarning {
+        pub kind: ValidationWarnings,
+        pub node_name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub enum ValidationWarnings {
+        UnusedVariable(String),
+        UsedDebug,
+        UsedPrint,
+        UsedDepricated(Depricated),
+        UnusualToken(String, TokenErrors),
+        UnusedLabel(String),
+        /// A token matches the start of a keyword (keyword, token)
+        KeywordOverlap(String, String),
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub enum TokenErrors {
+        NotAscii,
+        ContainsWhitespace,
+        TooLong,
+        StartsNumeric,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
+    pub enum Depricated {
+        /// The node is depricated
+        ///
+        /// It is advised to use Goto instead
+        Back,
+        /// Maybe you should use a different approach
+        Any,
+    }
+
+    /// This is a structure that keeps track of things that are hard to find
+    #[derive(Debug)]
+    pub struct LostAndFound {
+        pub lost_labels: Vec<String>,
+        pub found_labels: Vec<String>,
+        /// The maximum number of steps that can be taken back
+        pub steps: usize,
+    }
+
+    impl LostAndFound {
+        pub fn new() -> Self {
+            Self {
+                lost_labels: Vec::new(),
+                found_labels: Vec::new(),
+                steps: 0,
+            }
+        }
+
+        pub fn pass(&self, result: &mut ValidationResult, node_name: &str) {
+            for looking_for in &self.lost_labels {
+                if !self.found_labels.contains(looking_for) {
+                    result.errors.push(ValidationError {
+                        kind: ValidationErrors::LabelNotFound(looking_for.clone()),
+                        node_name: node_name.to_string(),
+                    });
+                }
+            }
+            for found in &self.found_labels {
+                if !self.lost_labels.contains(found) {
+                    result.warnings.push(ValidationWarning {
+                        kind: ValidationWarnings::UnusedLabel(found.clone()),
+                        node_name: node_name.to_string(),
+                    });
+                }
+            }
+        }
+    }
+}
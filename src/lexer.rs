@@ -1,371 +1,1868 @@
-use serde::{Deserialize, Serialize};
-
-// Choose between std and alloc
-cfg_if::cfg_if! {
-    if #[cfg(feature = "std")] {
-        extern crate std;
-        use std::prelude::v1::*;
-        use std::fmt;
-    } else {
-        extern crate alloc;
-        use alloc::string::*;
-        use alloc::vec::*;
-        use alloc::vec;
-        use alloc::format;
-        use core::fmt;
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
-pub enum TokenKinds {
-    /// A sequence of characters
-    Token(String),
-    /// A string of characters that will be generated by the preprocessor
-    Complex(String),
-    Text,
-    Whitespace,
-    Control(ControlTokenKind),
-}
-
-impl TokenKinds {
-    pub fn is_whitespace(&self) -> bool {
-        match self {
-            TokenKinds::Whitespace => true,
-            TokenKinds::Control(ControlTokenKind::Eol) => true,
-            _ => false,
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
-pub enum ControlTokenKind {
-    Eof,
-    Eol,
-}
-
-pub type Preprocessor = fn(text: &str, tokens: Vec<Token>) -> Result<Vec<Token>, PreprocessorError>;
-
-pub struct PreprocessorError {
-    pub message: String,
-    pub location: TextLocation,
-    pub len: usize,
-}
-
-impl fmt::Debug for PreprocessorError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "An error occurred during lexing at line {} column {}: {}", self.location.line, self.location.column, self.message)
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Lexer {
-    /// Possible token kinds
-    pub(crate) token_kinds: Vec<String>,
-    longest_token_size: usize,
-    #[serde(skip, default)]
-    pub preprocessors: Vec<Preprocessor>,
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
-pub struct Token {
-    /// Index of the token in the text
-    pub index: usize,
-    /// Length of the token
-    pub len: usize,
-    /// Location for debugging
-    pub location: TextLocation,
-    /// Kind of token
-    pub kind: TokenKinds,
-}
-
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
-pub struct TextLocation {
-    pub line: usize,
-    pub column: usize,
-}
-
-impl TextLocation {
-    pub fn new(line: usize, column: usize) -> TextLocation {
-        let line = line + 1;
-        let column = column + 1;
-        TextLocation { line, column }
-    }
-}
-
-impl Lexer {
-    pub fn new() -> Lexer {
-        Lexer {
-            token_kinds: Vec::new(),
-            longest_token_size: 0,
-            preprocessors: Vec::new(),
-        }
-    }
-
-    pub fn add_tokens(&mut self, tokens: &[String]) {
-        self.token_kinds.reserve(tokens.len());
-        for token in tokens {
-            if token.len() > self.longest_token_size {
-                self.longest_token_size = token.len();
-            }
-            self.add_token(token.clone());
-        }
-    }
-
-    pub fn add_token(&mut self, token: String) {
-        if token.len() > self.longest_token_size {
-            self.longest_token_size = token.len();
-        }
-        // find the right place to insert the token
-        //
-        //  1. find the first token that is longer than the new token
-        //  2. insert the new token before the first token that is longer
-        //
-        // This way the tokens are sorted by length
-        // This is important for optimization of the lexer
-        let index = self
-            .token_kinds
-            .iter()
-            .position(|x| x.len() > token.len())
-            .unwrap_or(self.token_kinds.len());
-        self.token_kinds.insert(index, token);
-    }
-
-    /// Lexer for UTF-8 text
-    pub fn lex_utf8(&self, text: &str) -> Result<Vec<Token>, PreprocessorError> {
-        let chars = text.char_indices().collect::<Vec<(usize, char)>>();
-        let len = chars.len();
-        // the allocation is a guess, but it should be close enough
-        let mut tokens = Vec::with_capacity(chars.len() / 4);
-        let mut i = 0;
-        let mut line = 0;
-        let mut column = 0;
-        'chars: while i < len {
-            // Take new line into account
-            if chars[i].1 == '\n' {
-                line += 1;
-                column = 0;
-                tokens.push(Token {
-                    index: chars[i].0,
-                    len: 1,
-                    location: TextLocation::new(line, column),
-                    kind: TokenKinds::Control(ControlTokenKind::Eol),
-                });
-                i += 1;
-                continue;
-            }
-
-            // Match token kinds
-            'tokens: for token_kind in self.token_kinds.iter().rev() {
-                let tok_chars = token_kind.char_indices();
-                let tok_len = tok_chars.count();
-                if i + tok_len > len {
-                    // All the remaining tokens are longer than the remaining text
-                    //
-                    // This is a performance optimization
-                    continue;
-                }
-                for (j, (_, c)) in token_kind.char_indices().enumerate() {
-                    if c != chars[i + j].1 {
-                        continue 'tokens;
-                    }
-                }
-                tokens.push(Token {
-                    index: chars[i].0,
-                    len: token_kind.len(),
-                    location: TextLocation::new(line, column),
-                    kind: TokenKinds::Token(token_kind.clone()),
-                });
-                i += tok_len;
-                column += tok_len;
-                continue 'chars;
-            }
-
-            // Match whitespace
-            if chars[i].1.is_whitespace() {
-                tokens.push(Token {
-                    index: chars[i].0,
-                    len: 1,
-                    location: TextLocation::new(line, column),
-                    kind: TokenKinds::Whitespace,
-                });
-                i += 1;
-                column += 1;
-                continue;
-            }
-
-            // Match text until next whitespace/token/eof
-            let mut j = 0;
-            let mut token_len = 0;
-            'word: while i + j < len {
-                if chars[i + j].1.is_whitespace() {
-                    break;
-                }
-                token_len += chars[i + j].1.len_utf8();
-                j += 1;
-                for token_kind in &self.token_kinds {
-                    let start = i + j;
-                    let tok_len = token_kind.chars().count();
-                    let end = if i + j + tok_len < len {
-                        i + j + tok_len
-                    } else {
-                        break 'word;
-                    };
-                    let token = &text[chars[start].0..chars[end].0];
-                    if token == *token_kind {
-                        break 'word;
-                    }
-                }
-            }
-            tokens.push(Token {
-                index: chars[i].0,
-                len: token_len,
-                location: TextLocation::new(line, column),
-                kind: TokenKinds::Text,
-            });
-            column += j;
-            i += j;
-        }
-        tokens.push(Token {
-            index: i,
-            len: 0,
-            location: TextLocation::new(line, column),
-            kind: TokenKinds::Control(ControlTokenKind::Eof),
-        });
-
-        for preprocessor in &self.preprocessors {
-            tokens = preprocessor(text, tokens)?;
-        }
-
-        Ok(tokens)
-    }
-
-    /// Lexer for ascii-only text
-    pub fn lex_ascii(&self, text: &str) -> Result<Vec<Token>, PreprocessorError> {
-        let chars = text.as_bytes();
-        // the allocation is a guess, but it should be close enough
-        let mut tokens = Vec::with_capacity(chars.len() / 4);
-        let mut i = 0;
-        let mut line = 0;
-        let mut column = 0;
-        let len = chars.len();
-        'chars: while i < len {
-            // Take new line into account
-            if chars[i] == '\n' as u8 {
-                line += 1;
-                column = 0;
-                i += 1;
-                tokens.push(Token {
-                    index: i,
-                    len: 1,
-                    location: TextLocation::new(line, column),
-                    kind: TokenKinds::Control(ControlTokenKind::Eol),
-                });
-                continue;
-            }
-
-            for token_kind in self.token_kinds.iter().rev() {
-                let tok_len = token_kind.len();
-                if i + tok_len > len {
-                    // All the remaining tokens are longer than the remaining text
-                    //
-                    // This is a performance optimization
-                    continue;
-                }
-                let token = &chars[i..i + tok_len];
-                if token == token_kind.as_bytes() {
-                    tokens.push(Token {
-                        index: i,
-                        len: tok_len,
-                        location: TextLocation::new(line, column),
-                        kind: TokenKinds::Token(token_kind.clone()),
-                    });
-                    i += tok_len;
-                    column += tok_len;
-                    continue 'chars;
-                }
-            }
-
-            // Match whitespace
-            if (chars[i] as char).is_whitespace() {
-                tokens.push(Token {
-                    index: i,
-                    len: 1,
-                    location: TextLocation::new(line, column),
-                    kind: TokenKinds::Whitespace,
-                });
-                i += 1;
-                column += 1;
-                continue;
-            }
-
-            // Match text until next whitespace/token/eof
-            let mut j = 0;
-            'word: while i + j < len {
-                if (chars[i + j] as char).is_whitespace() {
-                    break;
-                }
-                j += 1;
-                for token_kind in &self.token_kinds {
-                    let start = i + j;
-                    let tok_len = token_kind.len();
-                    let end = if i + j + tok_len <= len {
-                        i + j + tok_len
-                    } else {
-                        break 'word;
-                    };
-                    let token = &text[start..end];
-                    if token == *token_kind {
-                        break 'word;
-                    }
-                }
-            }
-            tokens.push(Token {
-                index: i,
-                len: j,
-                location: TextLocation::new(line, column),
-                kind: TokenKinds::Text,
-            });
-            column += j;
-            i += j;
-        }
-
-        tokens.push(Token {
-            index: i,
-            len: 0,
-            location: TextLocation::new(line, column),
-            kind: TokenKinds::Control(ControlTokenKind::Eof),
-        });
-
-        for preprocessor in &self.preprocessors {
-            tokens = preprocessor(text, tokens)?;
-        }
-
-        Ok(tokens)
-    }
-
-    /// Takes a slice of tokens and returns a string of the text
-    pub fn stringify_slice<'a>(&self, tokens: &[Token], text: &'a str) -> &'a str {
-        let start = match tokens.first() {
-            Some(token) => token.index,
-            None => return "",
-        };
-        let end = match tokens.last() {
-            Some(token) => token.index + token.len,
-            None => return "",
-        };
-        if start >= end {
-            return "";
-        }
-        if end > text.len() {
-            return "";
-        }
-        &text[start..end]
-    }
-
-    pub fn stringify<'a>(&self, token: &Token, text: &'a str) -> &'a str {
-        if token.kind == TokenKinds::Control(ControlTokenKind::Eof) {
-            return "__EOF__";
-        }
-        &text[token.index..token.index + token.len]
-    }
-}
+use core::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    lines::{Encoding, LineIndex, Position},
+    pattern::{Pattern, PatternError},
+    Map,
+};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+        use std::collections::VecDeque;
+        use std::fmt;
+        use std::sync::Arc;
+    } else {
+        extern crate alloc;
+        use alloc::collections::VecDeque;
+        use alloc::sync::Arc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::vec;
+        use alloc::format;
+        use core::fmt;
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Hash)]
+pub enum TokenKinds {
+    /// A sequence of characters
+    Token(String),
+    /// A string of characters that will be generated by a pattern or the preprocessor
+    Complex(String),
+    Text,
+    Whitespace,
+    Control(ControlTokenKind),
+}
+
+impl TokenKinds {
+    pub fn is_whitespace(&self) -> bool {
+        match self {
+            TokenKinds::Whitespace => true,
+            TokenKinds::Control(ControlTokenKind::Eol) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub enum ControlTokenKind {
+    Eof,
+    Eol,
+    /// The line is indented more than the previous one, see `Lexer::set_indentation`
+    Indent,
+    /// The line closes an indented block
+    Dedent,
+}
+
+/// Function that changes the tokens after lexing
+///
+/// Preprocessors are closures, so they can keep their configuration:
+///
+/// ```ignore
+/// let keywords = vec!["fn", "let"];
+/// lexer.preprocessors.push(Preprocessor::new(move |tokens| {
+///     while let Some(token) = tokens.peek() {
+///         if token.kind.is_whitespace() {
+///             tokens.remove();
+///             continue;
+///         }
+///         if keywords.contains(&tokens.slice(token)) {
+///             tokens.replace_kind(TokenKinds::Complex("keyword".to_string()));
+///         }
+///         tokens.advance();
+///     }
+///     Ok(())
+/// }));
+/// ```
+#[derive(Clone)]
+pub struct Preprocessor(Arc<PreprocessorFn>);
+
+type PreprocessorFn =
+    dyn Fn(&str, Vec<Token>) -> Result<Vec<Token>, PreprocessorError> + Send + Sync;
+
+impl Preprocessor {
+    /// Preprocessor that edits the tokens with a `TokenStream`
+    pub fn new<F>(preprocessor: F) -> Preprocessor
+    where
+        F: Fn(&mut TokenStream<'_>) -> Result<(), PreprocessorError> + Send + Sync + 'static,
+    {
+        Preprocessor(Arc::new(move |text: &str, tokens| {
+            let mut stream = TokenStream::new(text, tokens);
+            preprocessor(&mut stream)?;
+            Ok(stream.finish())
+        }))
+    }
+
+    /// Preprocessor that takes all of the tokens and returns the new ones
+    pub fn from_fn<F>(preprocessor: F) -> Preprocessor
+    where
+        F: Fn(&str, Vec<Token>) -> Result<Vec<Token>, PreprocessorError> + Send + Sync + 'static,
+    {
+        Preprocessor(Arc::new(preprocessor))
+    }
+
+    pub fn run(&self, text: &str, tokens: Vec<Token>) -> Result<Vec<Token>, PreprocessorError> {
+        (self.0)(text, tokens)
+    }
+}
+
+impl fmt::Debug for Preprocessor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Preprocessor")
+    }
+}
+
+/// Tokens of a preprocessor with a cursor
+///
+/// The tokens before the cursor are passed, the ones from the cursor on are edited.
+/// Looking past the last token gives `None` instead of panicking, and the tokens that
+/// were not passed when the preprocessor returns are kept.
+pub struct TokenStream<'a> {
+    text: &'a str,
+    /// The current token and the ones after it
+    input: VecDeque<Token>,
+    /// Tokens before the current one
+    output: Vec<Token>,
+}
+
+impl<'a> TokenStream<'a> {
+    pub fn new(text: &'a str, tokens: Vec<Token>) -> TokenStream<'a> {
+        TokenStream {
+            text,
+            output: Vec::with_capacity(tokens.len()),
+            input: VecDeque::from(tokens),
+        }
+    }
+
+    /// Text the tokens were lexed from
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Text of a token
+    pub fn slice(&self, token: &Token) -> &'a str {
+        &self.text[token.index..token.index + token.len]
+    }
+
+    /// Current token, `None` after the last one
+    pub fn peek(&self) -> Option<&Token> {
+        self.input.front()
+    }
+
+    /// Token `n` places after the current one, `lookahead(0)` is the current one
+    pub fn lookahead(&self, n: usize) -> Option<&Token> {
+        self.input.get(n)
+    }
+
+    /// Tokens before the current one
+    pub fn passed(&self) -> &[Token] {
+        &self.output
+    }
+
+    /// Keeps the current token and moves to the next one
+    pub fn advance(&mut self) -> Option<&Token> {
+        let token = self.input.pop_front()?;
+        self.output.push(token);
+        self.output.last()
+    }
+
+    /// Drops the current token, the next one becomes the current one
+    pub fn remove(&mut self) -> Option<Token> {
+        self.input.pop_front()
+    }
+
+    /// Changes the kind of the current token
+    pub fn replace_kind(&mut self, kind: TokenKinds) {
+        if let Some(token) = self.input.front_mut() {
+            token.kind = kind;
+        }
+    }
+
+    /// Merges the current token and the `count - 1` tokens after it into one
+    ///
+    /// The merged token spans the text from the start of the first token to the furthest
+    /// end of the merged ones and stays the current token.
+    pub fn merge(&mut self, count: usize, kind: TokenKinds) {
+        let count = count.min(self.input.len());
+        if count == 0 {
+            return;
+        }
+        // tokens of previous preprocessors do not have to be in order
+        let end = self
+            .input
+            .range(..count)
+            .map(|token| token.index + token.len)
+            .max()
+            .unwrap_or(0);
+        let first = &mut self.input[0];
+        first.len = end.saturating_sub(first.index);
+        first.kind = kind;
+        self.input.drain(1..count);
+    }
+
+    /// Replaces the current token and the `count - 1` tokens after it
+    ///
+    /// The new tokens are passed, the cursor moves to the token after the replaced ones.
+    /// `splice(0, tokens)` inserts the tokens before the current one.
+    pub fn splice(&mut self, count: usize, tokens: impl IntoIterator<Item = Token>) {
+        let count = count.min(self.input.len());
+        self.input.drain(..count);
+        self.output.extend(tokens);
+    }
+
+    /// Error at the current token, or at the end of the text after the last one
+    pub fn error(&self, message: &str) -> PreprocessorError {
+        let (location, len) = match self.peek().or(self.output.last()) {
+            Some(token) => (token.location.clone(), token.len),
+            None => (TextLocation::new(0, 0), 0),
+        };
+        PreprocessorError {
+            message: message.to_string(),
+            location,
+            len,
+        }
+    }
+
+    /// Passed tokens followed by the rest
+    pub fn finish(self) -> Vec<Token> {
+        let mut tokens = self.output;
+        tokens.extend(self.input);
+        tokens
+    }
+}
+
+/// Preprocessors by name
+///
+/// Functions can not be saved, so a dumped lexer only keeps the names of the
+/// preprocessors added with `Lexer::add_preprocessor`. The registry binds them again
+/// when the lexer is loaded, see `dump::load_with`.
+#[derive(Debug, Clone, Default)]
+pub struct Preprocessors {
+    preprocessors: Map<String, Preprocessor>,
+}
+
+impl Preprocessors {
+    pub fn new() -> Preprocessors {
+        Preprocessors {
+            preprocessors: Map::new(),
+        }
+    }
+
+    /// Adds a preprocessor, replacing the one with the same name
+    pub fn add(&mut self, name: &str, preprocessor: Preprocessor) -> &mut Preprocessors {
+        self.preprocessors.insert(name.to_string(), preprocessor);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Preprocessor> {
+        self.preprocessors.get(name).cloned()
+    }
+}
+
+pub struct PreprocessorError {
+    pub message: String,
+    pub location: TextLocation,
+    pub len: usize,
+}
+
+impl fmt::Debug for PreprocessorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "An error occurred during lexing at line {} column {}: {}", self.location.line, self.location.column, self.message)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(from = "LexerFields")]
+pub struct Lexer {
+    /// Possible token kinds
+    pub(crate) token_kinds: Vec<String>,
+    longest_token_size: usize,
+    /// Patterns that produce complex tokens
+    pub(crate) patterns: Vec<TokenPattern>,
+    /// Strings and comments that are read as a whole
+    pub(crate) literals: Vec<TokenLiteral>,
+    /// Tokens that only match whole words
+    pub(crate) keywords: Vec<Keyword>,
+    /// Modes with their own tokens
+    pub(crate) modes: Map<String, LexerMode>,
+    /// Mode changes after the tokens of the main mode
+    pub(crate) actions: Map<String, ModeAction>,
+    /// Emit indent and dedent tokens
+    pub(crate) indentation: bool,
+    /// Names of the preprocessors added with `add_preprocessor`, they are saved instead
+    /// of the preprocessors
+    pub(crate) preprocessor_names: Vec<String>,
+    #[serde(skip)]
+    pub preprocessors: Vec<Preprocessor>,
+    /// Token kinds compiled for matching, rebuilt when the lexer is loaded
+    #[serde(skip)]
+    trie: Trie,
+}
+
+/// Serialized fields of the lexer
+#[derive(Deserialize)]
+struct LexerFields {
+    token_kinds: Vec<String>,
+    longest_token_size: usize,
+    #[serde(default)]
+    patterns: Vec<TokenPattern>,
+    #[serde(default)]
+    literals: Vec<TokenLiteral>,
+    #[serde(default)]
+    keywords: Vec<Keyword>,
+    #[serde(default)]
+    modes: Map<String, LexerMode>,
+    #[serde(default)]
+    actions: Map<String, ModeAction>,
+    #[serde(default)]
+    indentation: bool,
+    #[serde(default)]
+    preprocessor_names: Vec<String>,
+}
+
+impl From<LexerFields> for Lexer {
+    fn from(fields: LexerFields) -> Self {
+        let mut trie = Trie::new();
+        for token in &fields.token_kinds {
+            trie.insert(token);
+        }
+        Lexer {
+            token_kinds: fields.token_kinds,
+            longest_token_size: fields.longest_token_size,
+            patterns: fields.patterns,
+            literals: fields.literals,
+            keywords: fields.keywords,
+            modes: fields.modes,
+            actions: fields.actions,
+            indentation: fields.indentation,
+            preprocessor_names: fields.preprocessor_names,
+            preprocessors: Vec::new(),
+            trie,
+        }
+    }
+}
+
+/// Pattern that produces `TokenKinds::Complex(name)`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenPattern {
+    pub name: String,
+    pub pattern: Pattern,
+}
+
+/// Word that produces `TokenKinds::Token(word)` when it is not followed by a part of a word
+///
+/// Unlike a token, the keyword `fn` does not match the start of `fnord`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Keyword {
+    pub word: String,
+    /// `SELECT` and `select` both match the keyword `select`
+    pub case_insensitive: bool,
+}
+
+impl Keyword {
+    /// Length of the keyword in bytes at the start of the text
+    ///
+    /// The end of the text ends the word too
+    fn read(&self, text: &str) -> Option<usize> {
+        let mut chars = text.chars();
+        let mut len = 0;
+        for expected in self.word.chars() {
+            let c = chars.next()?;
+            if c != expected
+                && !(self.case_insensitive && c.to_lowercase().eq(expected.to_lowercase()))
+            {
+                return None;
+            }
+            len += c.len_utf8();
+        }
+        match chars.next() {
+            Some(c) if is_word_char(c) => None,
+            _ => Some(len),
+        }
+    }
+}
+
+/// Letters, digits and underscores continue a word
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Literal that produces `TokenKinds::Complex(name)`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TokenLiteral {
+    pub name: String,
+    pub literal: Literal,
+}
+
+/// Strings and comments that can contain anything until their end
+///
+/// All of them can span multiple lines except for the line comment.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum Literal {
+    /// Text between two quotes, the escape character skips the next character
+    ///
+    /// If the escape is the quote itself (`'it''s'`), only doubled quotes are escaped
+    String { quote: String, escape: Option<char> },
+    /// Comment until the end of the line, the new line is not a part of it
+    LineComment { start: String },
+    /// Comment between the delimiters, nested comments have to be closed as well
+    BlockComment {
+        start: String,
+        end: String,
+        nested: bool,
+    },
+    /// Text between the delimiters without any escapes
+    RawString { start: String, end: String },
+}
+
+impl Literal {
+    pub fn string(quote: &str, escape: Option<char>) -> Literal {
+        Literal::String {
+            quote: quote.to_string(),
+            escape,
+        }
+    }
+
+    pub fn line_comment(start: &str) -> Literal {
+        Literal::LineComment {
+            start: start.to_string(),
+        }
+    }
+
+    pub fn block_comment(start: &str, end: &str, nested: bool) -> Literal {
+        Literal::BlockComment {
+            start: start.to_string(),
+            end: end.to_string(),
+            nested,
+        }
+    }
+
+    pub fn raw_string(start: &str, end: &str) -> Literal {
+        Literal::RawString {
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    /// Text that starts the literal
+    pub fn start(&self) -> &str {
+        match self {
+            Literal::String { quote, .. } => quote,
+            Literal::LineComment { start }
+            | Literal::BlockComment { start, .. }
+            | Literal::RawString { start, .. } => start,
+        }
+    }
+
+    /// Length of the literal at the start of the text in bytes
+    ///
+    /// The text has to start with `start`, returns `None` if the literal is not terminated
+    fn read(&self, text: &str) -> Option<usize> {
+        match self {
+            Literal::String { quote, escape } => {
+                let mut i = quote.len();
+                while i < text.len() {
+                    let c = next_char(text, i);
+                    if Some(c) == *escape {
+                        let next = i + c.len_utf8();
+                        if !quote.starts_with(c) {
+                            i = next + text[next..].chars().next().map_or(0, char::len_utf8);
+                            continue;
+                        }
+                        if text[next..].starts_with(quote.as_str()) {
+                            i = next + quote.len();
+                            continue;
+                        }
+                    }
+                    if text[i..].starts_with(quote.as_str()) {
+                        return Some(i + quote.len());
+                    }
+                    i += c.len_utf8();
+                }
+                None
+            }
+            Literal::LineComment { start } => match text[start.len()..].find('\n') {
+                Some(end) => Some(start.len() + end),
+                None => Some(text.len()),
+            },
+            Literal::BlockComment { start, end, nested } => {
+                let mut depth = 1;
+                let mut i = start.len();
+                while i < text.len() {
+                    if text[i..].starts_with(end.as_str()) {
+                        depth -= 1;
+                        i += end.len();
+                        if depth == 0 {
+                            return Some(i);
+                        }
+                        continue;
+                    }
+                    if *nested && text[i..].starts_with(start.as_str()) {
+                        depth += 1;
+                        i += start.len();
+                        continue;
+                    }
+                    i += next_char(text, i).len_utf8();
+                }
+                None
+            }
+            Literal::RawString { start, end } => text[start.len()..]
+                .find(end.as_str())
+                .map(|found| start.len() + found + end.len()),
+        }
+    }
+}
+
+/// Changes the mode of the lexer after a token
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ModeAction {
+    /// Enters the mode
+    Push(String),
+    /// Returns to the previous mode
+    Pop,
+}
+
+/// Named set of tokens used between a push and a pop
+///
+/// Text, whitespace and new lines are lexed like in the main mode, only the tokens
+/// and patterns are different. A string mode only needs the closing quote, escapes
+/// and the start of an interpolation, so `"a + b"` does not produce a `+` token.
+///
+/// When `merge` is set, all the tokens of the mode (with the tokens that pushed and
+/// popped it) become one `Complex(merge)` token. Pushing another mode splits it, so
+/// `"a ${b} c"` is `"a ${`, `b`, `} c"` with interpolation.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LexerMode {
+    pub name: String,
+    tokens: Trie,
+    patterns: Vec<TokenPattern>,
+    actions: Map<String, ModeAction>,
+    merge: Option<String>,
+    inherit: bool,
+}
+
+impl LexerMode {
+    pub fn new(name: &str) -> LexerMode {
+        LexerMode {
+            name: name.to_string(),
+            tokens: Trie::new(),
+            patterns: Vec::new(),
+            actions: Map::new(),
+            merge: None,
+            inherit: false,
+        }
+    }
+
+    pub fn add_token(&mut self, token: &str) -> &mut Self {
+        self.tokens.insert(token);
+        self
+    }
+
+    /// Adds a pattern, see `Lexer::add_pattern`
+    pub fn add_pattern(&mut self, name: &str, pattern: &str) -> Result<&mut Self, PatternError> {
+        insert_pattern(&mut self.patterns, name, pattern)?;
+        Ok(self)
+    }
+
+    /// Changes the mode after a token (or a pattern with the name)
+    pub fn on(&mut self, token: &str, action: ModeAction) -> &mut Self {
+        self.actions.insert(token.to_string(), action);
+        self
+    }
+
+    /// Merges the tokens of the mode into one `Complex(kind)` token
+    pub fn merge(&mut self, kind: &str) -> &mut Self {
+        self.merge = Some(kind.to_string());
+        self
+    }
+
+    /// Uses the tokens, patterns and actions of the main mode as well
+    ///
+    /// Useful for interpolation, where the mode only adds the closing brace
+    pub fn inherit(&mut self, inherit: bool) -> &mut Self {
+        self.inherit = inherit;
+        self
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens.tokens
+    }
+
+    pub fn patterns(&self) -> &[TokenPattern] {
+        &self.patterns
+    }
+
+    pub fn actions(&self) -> &Map<String, ModeAction> {
+        &self.actions
+    }
+
+    pub fn merged(&self) -> Option<&str> {
+        self.merge.as_deref()
+    }
+
+    pub fn inherits(&self) -> bool {
+        self.inherit
+    }
+}
+
+/// Modes the lexer is in, the last one is the current mode
+///
+/// An empty stack is the main mode. Lexing can continue from a saved stack
+/// with `Lexer::lex_utf8_with`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct ModeStack {
+    pub modes: Vec<String>,
+}
+
+impl ModeStack {
+    pub fn new() -> ModeStack {
+        ModeStack { modes: Vec::new() }
+    }
+
+    /// Current mode, `None` is the main mode
+    pub fn current(&self) -> Option<&str> {
+        self.modes.last().map(String::as_str)
+    }
+}
+
+/// Replaces a range of the text, see `Lexer::relex`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the text before the edit
+    pub range: Range<usize>,
+    /// Text that replaces the range
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            text: text.to_string(),
+        }
+    }
+
+    /// Text after the edit
+    pub fn apply(&self, text: &str) -> String {
+        let mut edited = String::with_capacity(text.len() + self.text.len());
+        edited.push_str(&text[..self.range.start]);
+        edited.push_str(&self.text);
+        edited.push_str(&text[self.range.end..]);
+        edited
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct Token {
+    /// Index of the token in the text
+    pub index: usize,
+    /// Length of the token
+    pub len: usize,
+    /// Location for debugging
+    pub location: TextLocation,
+    /// Kind of token
+    pub kind: TokenKinds,
+}
+
+impl Token {
+    /// Start of the token in the encoding
+    pub fn position(&self, lines: &LineIndex, encoding: Encoding) -> Position {
+        lines.position(self.index, encoding)
+    }
+
+    /// End of the token in the encoding, after its last character
+    pub fn end(&self, lines: &LineIndex, encoding: Encoding) -> Position {
+        lines.position(self.index + self.len, encoding)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Serialize, Deserialize)]
+pub struct TextLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl TextLocation {
+    pub fn new(line: usize, column: usize) -> TextLocation {
+        let line = line + 1;
+        let column = column + 1;
+        TextLocation { line, column }
+    }
+}
+
+impl Lexer {
+    pub fn new() -> Lexer {
+        Lexer {
+            token_kinds: Vec::new(),
+            longest_token_size: 0,
+            patterns: Vec::new(),
+            literals: Vec::new(),
+            keywords: Vec::new(),
+            modes: Map::new(),
+            actions: Map::new(),
+            indentation: false,
+            preprocessor_names: Vec::new(),
+            preprocessors: Vec::new(),
+            trie: Trie::new(),
+        }
+    }
+
+    pub fn add_tokens(&mut self, tokens: &[String]) {
+        self.token_kinds.reserve(tokens.len());
+        for token in tokens {
+            if token.len() > self.longest_token_size {
+                self.longest_token_size = token.len();
+            }
+            self.add_token(token.clone());
+        }
+    }
+
+    pub fn add_token(&mut self, token: String) {
+        if token.len() > self.longest_token_size {
+            self.longest_token_size = token.len();
+        }
+        // find the right place to insert the token
+        //
+        //  1. find the first token that is longer than the new token
+        //  2. insert the new token before the first token that is longer
+        //
+        // This way the tokens are sorted by length
+        // The matching is done by the trie, the order only keeps the output stable
+        let index = self
+            .token_kinds
+            .iter()
+            .position(|x| x.len() > token.len())
+            .unwrap_or(self.token_kinds.len());
+        self.trie.insert(&token);
+        self.token_kinds.insert(index, token);
+    }
+
+    /// Adds a pattern that produces `TokenKinds::Complex(name)` tokens
+    ///
+    /// Patterns are tried at the start of every token. The longest match wins, literal
+    /// tokens win over patterns of the same length and earlier patterns over later ones.
+    /// Adding a pattern with an existing name replaces it.
+    ///
+    /// The syntax is described in the `pattern` module.
+    pub fn add_pattern(&mut self, name: &str, pattern: &str) -> Result<(), PatternError> {
+        insert_pattern(&mut self.patterns, name, pattern)
+    }
+
+    /// Patterns of the lexer in the order they are tried
+    pub fn patterns(&self) -> &[TokenPattern] {
+        &self.patterns
+    }
+
+    /// Adds a literal that produces `TokenKinds::Complex(name)` tokens
+    ///
+    /// Literals are checked before tokens and patterns, the one with the longest start
+    /// wins. They are used in the main mode and in the modes that inherit from it.
+    /// Adding a literal with an existing name replaces it.
+    pub fn add_literal(&mut self, name: &str, literal: Literal) {
+        let literal = TokenLiteral {
+            name: name.to_string(),
+            literal,
+        };
+        match self
+            .literals
+            .iter_mut()
+            .find(|existing| existing.name == name)
+        {
+            Some(existing) => *existing = literal,
+            None => self.literals.push(literal),
+        }
+    }
+
+    /// Literals of the lexer in the order they were added
+    pub fn literals(&self) -> &[TokenLiteral] {
+        &self.literals
+    }
+
+    /// Adds a token that only matches when it is not followed by a letter, digit or `_`
+    ///
+    /// Keywords are used in the main mode and in the modes that inherit from it. The
+    /// longest one of the keywords and tokens wins, a keyword wins over a token or
+    /// pattern of the same length. A plain token with the same text still matches
+    /// inside of words, so it should not be added as well (`Grammar::validate` warns
+    /// about it). Adding a keyword again replaces it.
+    pub fn add_keyword(&mut self, word: &str, case_insensitive: bool) {
+        let keyword = Keyword {
+            word: word.to_string(),
+            case_insensitive,
+        };
+        match self
+            .keywords
+            .iter_mut()
+            .find(|existing| existing.word == word)
+        {
+            Some(existing) => *existing = keyword,
+            None => self.keywords.push(keyword),
+        }
+    }
+
+    /// Keywords of the lexer in the order they were added
+    pub fn keywords(&self) -> &[Keyword] {
+        &self.keywords
+    }
+
+    /// Tracks the indentation of the lines in the main mode
+    ///
+    /// A line that is indented more than the last one emits `ControlTokenKind::Indent`
+    /// before its first token, a line that goes back to an outer level emits a
+    /// `ControlTokenKind::Dedent` for every block it closes. The blocks that are still
+    /// open are closed before the end of the text. Both tokens are empty and are not
+    /// skipped by the parser like whitespace, so the grammar can match them with
+    /// `#indent` and `#dedent`.
+    ///
+    /// Lines with only whitespace or a line comment are ignored and so are the lines
+    /// that start in another mode, which makes it possible to continue a line inside
+    /// of brackets. An indentation has to start with the one of the outer block,
+    /// mixing tabs and spaces differently or dedenting to a level that was never
+    /// opened is an error.
+    pub fn set_indentation(&mut self, enabled: bool) {
+        self.indentation = enabled;
+    }
+
+    pub fn indentation(&self) -> bool {
+        self.indentation
+    }
+
+    /// Adds a preprocessor that is saved by its name
+    ///
+    /// Preprocessors pushed to `preprocessors` directly are lost when the lexer is
+    /// dumped, the named ones are bound again by `dump::load_with`.
+    pub fn add_preprocessor(&mut self, name: &str, preprocessor: Preprocessor) {
+        self.preprocessor_names.push(name.to_string());
+        self.preprocessors.push(preprocessor);
+    }
+
+    /// Names of the preprocessors in the order they run
+    pub fn preprocessor_names(&self) -> &[String] {
+        &self.preprocessor_names
+    }
+
+    /// Replaces the preprocessors with the named ones from the registry
+    ///
+    /// Returns the name of the first preprocessor that is missing in the registry
+    pub fn bind_preprocessors(&mut self, registry: &Preprocessors) -> Result<(), String> {
+        let mut preprocessors = Vec::with_capacity(self.preprocessor_names.len());
+        for name in &self.preprocessor_names {
+            match registry.get(name) {
+                Some(preprocessor) => preprocessors.push(preprocessor),
+                None => return Err(name.clone()),
+            }
+        }
+        self.preprocessors = preprocessors;
+        Ok(())
+    }
+
+    /// Adds a mode or returns the existing one
+    ///
+    /// Modes have their own tokens and patterns, tokens switch between them with `on`.
+    pub fn add_mode(&mut self, name: &str) -> &mut LexerMode {
+        self.modes
+            .entry(name.to_string())
+            .or_insert_with(|| LexerMode::new(name))
+    }
+
+    pub fn mode(&self, name: &str) -> Option<&LexerMode> {
+        self.modes.get(name)
+    }
+
+    /// Changes the mode after a token (or a pattern with the name) of the main mode
+    pub fn on(&mut self, token: &str, action: ModeAction) {
+        self.actions.insert(token.to_string(), action);
+    }
+
+    /// Longest literal token at the start of the characters
+    fn match_literal<'a>(
+        &'a self,
+        mode: Option<&'a LexerMode>,
+        chars: impl Iterator<Item = char> + Clone,
+    ) -> Option<&'a str> {
+        let mode = match mode {
+            Some(mode) => mode,
+            None => return self.trie.longest(chars).map(|found| found.0),
+        };
+        let own = mode.tokens.longest(chars.clone()).map(|found| found.0);
+        if !mode.inherit {
+            return own;
+        }
+        match (own, self.trie.longest(chars).map(|found| found.0)) {
+            (Some(own), Some(main)) if main.len() > own.len() => Some(main),
+            (None, main) => main,
+            (own, _) => own,
+        }
+    }
+
+    /// Longest keyword at the start of the text and its length in bytes
+    fn match_keyword(&self, mode: Option<&LexerMode>, text: &str) -> Option<(&str, usize)> {
+        if matches!(mode, Some(mode) if !mode.inherit) {
+            return None;
+        }
+        let mut best: Option<(&str, usize)> = None;
+        for keyword in &self.keywords {
+            let len = match keyword.read(text) {
+                Some(len) => len,
+                None => continue,
+            };
+            match best {
+                Some((_, best_len)) if best_len >= len => (),
+                _ => best = Some((&keyword.word, len)),
+            }
+        }
+        best
+    }
+
+    /// Longest keyword, token or pattern match at the start of the text
+    ///
+    /// Patterns only win when their match is longer than the literal token
+    fn match_token(
+        &self,
+        mode: Option<&LexerMode>,
+        text: &str,
+        chars: impl Iterator<Item = char> + Clone,
+    ) -> Option<(TokenKinds, usize)> {
+        let literal = self
+            .match_literal(mode, chars)
+            .map(|literal| (literal, literal.len()));
+        let literal = match (self.match_keyword(mode, text), literal) {
+            (Some(keyword), Some(literal)) if literal.1 > keyword.1 => Some(literal),
+            (None, literal) => literal,
+            (keyword, _) => keyword,
+        };
+        let patterns = match mode {
+            None => &self.patterns[..],
+            Some(mode) => &mode.patterns[..],
+        };
+        let inherited = match mode {
+            Some(mode) if mode.inherit => &self.patterns[..],
+            _ => &[],
+        };
+        let mut best: Option<(&TokenPattern, usize)> = None;
+        for pattern in patterns.iter().chain(inherited) {
+            let len = match pattern.pattern.longest(text) {
+                Some(len) => len,
+                None => continue,
+            };
+            match best {
+                Some((_, best_len)) if best_len >= len => (),
+                _ => best = Some((pattern, len)),
+            }
+        }
+        match (literal, best) {
+            (Some((literal, bytes)), Some((_, len))) if bytes >= len => {
+                Some((TokenKinds::Token(literal.to_string()), bytes))
+            }
+            (_, Some((pattern, len))) => Some((TokenKinds::Complex(pattern.name.clone()), len)),
+            (Some((literal, bytes)), None) => Some((TokenKinds::Token(literal.to_string()), bytes)),
+            (None, None) => None,
+        }
+    }
+
+    /// Literal with the longest start at the start of the text
+    fn find_literal(&self, mode: Option<&LexerMode>, text: &str) -> Option<&TokenLiteral> {
+        if matches!(mode, Some(mode) if !mode.inherit) {
+            return None;
+        }
+        let mut best: Option<&TokenLiteral> = None;
+        for literal in &self.literals {
+            let start = literal.literal.start();
+            if start.is_empty() || !text.starts_with(start) {
+                continue;
+            }
+            match best {
+                Some(best) if best.literal.start().len() >= start.len() => (),
+                _ => best = Some(literal),
+            }
+        }
+        best
+    }
+
+    /// Mode change after a token
+    fn action<'a>(
+        &'a self,
+        mode: Option<&'a LexerMode>,
+        kind: &TokenKinds,
+    ) -> Option<&'a ModeAction> {
+        let name = match kind {
+            TokenKinds::Token(name) | TokenKinds::Complex(name) => name,
+            _ => return None,
+        };
+        match mode {
+            None => self.actions.get(name),
+            Some(mode) => match mode.actions.get(name) {
+                Some(action) => Some(action),
+                None if mode.inherit => self.actions.get(name),
+                None => None,
+            },
+        }
+    }
+
+    /// Lexer for UTF-8 text
+    pub fn lex_utf8(&self, text: &str) -> Result<Vec<Token>, PreprocessorError> {
+        self.lex(text, Utf8(text), &mut ModeStack::new())
+    }
+
+    /// Lexer for UTF-8 text that starts in the modes of the stack
+    ///
+    /// The stack is left in the modes the lexer was in at the end of the text
+    pub fn lex_utf8_with(
+        &self,
+        text: &str,
+        modes: &mut ModeStack,
+    ) -> Result<Vec<Token>, PreprocessorError> {
+        self.lex(text, Utf8(text), modes)
+    }
+
+    /// Lexer for ascii-only text
+    pub fn lex_ascii(&self, text: &str) -> Result<Vec<Token>, PreprocessorError> {
+        self.lex(text, Ascii(text.as_bytes()), &mut ModeStack::new())
+    }
+
+    /// Lexer for ascii-only text that starts in the modes of the stack
+    pub fn lex_ascii_with(
+        &self,
+        text: &str,
+        modes: &mut ModeStack,
+    ) -> Result<Vec<Token>, PreprocessorError> {
+        self.lex(text, Ascii(text.as_bytes()), modes)
+    }
+
+    fn lex<S: Source>(
+        &self,
+        text: &str,
+        source: S,
+        modes: &mut ModeStack,
+    ) -> Result<Vec<Token>, PreprocessorError> {
+        let mut state = State::new(self, core::mem::take(modes))?;
+        // the allocation is a guess, but it should be close enough
+        // all tokens stay pending, so the queue can be turned into the result
+        state.pending.reserve(text.len() / 4);
+        let mut result = Ok(true);
+        while !state.done && result.is_ok() {
+            result = state.step(text, &source, true);
+        }
+        *modes = state.modes;
+        result?;
+        self.preprocess(text, Vec::from(state.pending))
+    }
+
+    /// Runs the preprocessors over the tokens
+    fn preprocess(
+        &self,
+        text: &str,
+        mut tokens: Vec<Token>,
+    ) -> Result<Vec<Token>, PreprocessorError> {
+        // a lexer that was deserialized without binding its preprocessors
+        if self.preprocessors.len() < self.preprocessor_names.len() {
+            return Err(PreprocessorError {
+                message: format!(
+                    "Preprocessors are not bound: {}",
+                    self.preprocessor_names.join(", ")
+                ),
+                location: TextLocation::new(0, 0),
+                len: 0,
+            });
+        }
+        for preprocessor in &self.preprocessors {
+            tokens = preprocessor.run(text, tokens)?;
+        }
+        Ok(tokens)
+    }
+
+    /// Lazy lexer for UTF-8 text
+    ///
+    /// The tokens are the same as the ones of `lex_utf8`, but they are lexed one by one
+    /// when they are needed. Preprocessors can not run since they need all of the tokens,
+    /// so a lexer with preprocessors returns an error instead of the first token.
+    pub fn tokens<'a>(&'a self, text: &'a str) -> Tokens<'a> {
+        Tokens {
+            text,
+            state: State::main(self),
+            error: self.lazy_error(),
+        }
+    }
+
+    /// Lazy lexer that reads the text from a reader
+    ///
+    /// The text is read line by line and dropped once it was lexed. Literals, patterns
+    /// and merging modes that continue on the next line are read until they end.
+    /// Like `tokens`, it fails if the lexer has preprocessors.
+    #[cfg(feature = "std")]
+    pub fn read_tokens<R: std::io::Read>(&self, reader: R) -> ReadTokens<'_, R> {
+        ReadTokens {
+            reader: std::io::BufReader::new(reader),
+            buffer: String::new(),
+            eof: false,
+            state: State::main(self),
+            error: self.lazy_error(),
+        }
+    }
+
+    /// Error of the lazy lexers, they would skip the preprocessors
+    fn lazy_error(&self) -> Option<PreprocessorError> {
+        if self.preprocessors.is_empty() && self.preprocessor_names.is_empty() {
+            return None;
+        }
+        Some(PreprocessorError {
+            message: "Preprocessors can not run on lazily lexed tokens".to_string(),
+            location: TextLocation::new(0, 0),
+            len: 0,
+        })
+    }
+
+    /// Lexes the text after an edit, reusing the tokens of the text before it
+    ///
+    /// `tokens` have to be the tokens of `text` from this lexer. Only the lines of the edit
+    /// are lexed again and the tokens after them are moved, the result is the same as
+    /// `lex_utf8` of the edited text. Preprocessors are run over the lexed lines, so they
+    /// have to give the same tokens for some of the lines as for the whole text.
+    ///
+    /// Lexers with modes, or with tokens, patterns and literal starts that can contain
+    /// a new line lex the whole text again since their lines depend on each other.
+    pub fn relex(
+        &self,
+        tokens: &[Token],
+        text: &str,
+        edit: &TextEdit,
+    ) -> Result<Vec<Token>, PreprocessorError> {
+        let edited = edit.apply(text);
+        if !self.independent_lines() {
+            return self.lex_utf8(&edited);
+        }
+        let eol = TokenKinds::Control(ControlTokenKind::Eol);
+        // the tokens before the line of the edit stay the same
+        let before = tokens.partition_point(|tok| tok.index < edit.range.start);
+        let kept = match tokens[..before].iter().rposition(|tok| tok.kind == eol) {
+            Some(idx) => idx + 1,
+            None => 0,
+        };
+        let mut state = State::main(self);
+        if kept > 0 {
+            state.i = tokens[kept - 1].index + 1;
+            state.line = tokens[kept - 1].location.line;
+        }
+
+        // lex until a new line that was a new line before the edit as well,
+        // the lexer does the same from there on
+        let end = edit.range.start + edit.text.len();
+        let delta = edited.len() as isize - text.len() as isize;
+        let mut resumed = None;
+        while !state.done {
+            state.step(&edited, &Utf8(&edited), true)?;
+            let token = state.pending.back().unwrap();
+            if token.kind != eol || token.index < end {
+                continue;
+            }
+            let old = (token.index as isize - delta) as usize;
+            if let Ok(idx) = tokens.binary_search_by_key(&old, |tok| tok.index) {
+                if tokens[idx].kind == eol {
+                    let lines = token.location.line as isize - tokens[idx].location.line as isize;
+                    resumed = Some((idx + 1, lines));
+                    break;
+                }
+            }
+        }
+
+        let mut lexed = self.preprocess(&edited, Vec::from(state.pending))?;
+        let mut result = Vec::with_capacity(tokens.len() + lexed.len());
+        result.extend_from_slice(&tokens[..kept]);
+        result.append(&mut lexed);
+        if let Some((idx, lines)) = resumed {
+            result.extend(tokens[idx..].iter().map(|tok| Token {
+                index: (tok.index as isize + delta) as usize,
+                location: TextLocation {
+                    line: (tok.location.line as isize + lines) as usize,
+                    column: tok.location.column,
+                },
+                ..tok.clone()
+            }));
+        }
+        Ok(result)
+    }
+
+    /// Tokens can not continue on the next line, so the lines can be lexed on their own
+    fn independent_lines(&self) -> bool {
+        !self.indentation
+            && self.modes.is_empty()
+            && self.actions.is_empty()
+            && !self.token_kinds.iter().any(|token| token.contains('\n'))
+            && !self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.pattern.matches_new_line())
+            && !self
+                .literals
+                .iter()
+                .any(|literal| literal.literal.start().contains('\n'))
+            && !self
+                .keywords
+                .iter()
+                .any(|keyword| keyword.word.contains('\n'))
+    }
+
+    fn find_mode(
+        &self,
+        name: &str,
+        location: &TextLocation,
+    ) -> Result<&LexerMode, PreprocessorError> {
+        match self.modes.get(name) {
+            Some(mode) => Ok(mode),
+            None => Err(PreprocessorError {
+                message: format!("Unknown lexer mode: {}", name),
+                location: location.clone(),
+                len: 0,
+            }),
+        }
+    }
+
+    /// Takes a slice of tokens and returns a string of the text
+    pub fn stringify_slice<'a>(&self, tokens: &[Token], text: &'a str) -> &'a str {
+        let start = match tokens.first() {
+            Some(token) => token.index,
+            None => return "",
+        };
+        let end = match tokens.last() {
+            Some(token) => token.index + token.len,
+            None => return "",
+        };
+        if start >= end {
+            return "";
+        }
+        if end > text.len() {
+            return "";
+        }
+        &text[start..end]
+    }
+
+    pub fn stringify<'a>(&self, token: &Token, text: &'a str) -> &'a str {
+        if token.kind == TokenKinds::Control(ControlTokenKind::Eof) {
+            return "__EOF__";
+        }
+        &text[token.index..token.index + token.len]
+    }
+}
+
+/// Adds a pattern or replaces the one with the same name
+fn insert_pattern(
+    patterns: &mut Vec<TokenPattern>,
+    name: &str,
+    pattern: &str,
+) -> Result<(), PatternError> {
+    let pattern = TokenPattern {
+        name: name.to_string(),
+        pattern: Pattern::new(pattern)?,
+    };
+    match patterns.iter_mut().find(|existing| existing.name == name) {
+        Some(existing) => *existing = pattern,
+        None => patterns.push(pattern),
+    }
+    Ok(())
+}
+
+/// Tokens of a text that are lexed when they are needed, see `Lexer::tokens`
+pub struct Tokens<'a> {
+    text: &'a str,
+    state: State<'a>,
+    /// Returned instead of the first token
+    error: Option<PreprocessorError>,
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Result<Token, PreprocessorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(self.state.fail(err)));
+        }
+        self.state.next(self.text, &Utf8(self.text))
+    }
+}
+
+/// Tokens of a reader that are lexed when they are needed, see `Lexer::read_tokens`
+#[cfg(feature = "std")]
+pub struct ReadTokens<'a, R: std::io::Read> {
+    reader: std::io::BufReader<R>,
+    /// Text that was read and not lexed yet
+    buffer: String,
+    /// The reader has no more text
+    eof: bool,
+    state: State<'a>,
+    /// Returned instead of the first token
+    error: Option<PreprocessorError>,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read> Iterator for ReadTokens<'_, R> {
+    type Item = Result<Token, PreprocessorError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use std::io::BufRead;
+
+        let state = &mut self.state;
+        if let Some(err) = self.error.take() {
+            return Some(Err(state.fail(err)));
+        }
+        loop {
+            if let Some(token) = state.ready() {
+                return Some(Ok(token));
+            }
+            if state.done {
+                return None;
+            }
+            match state.step(&self.buffer, &Utf8(&self.buffer), self.eof) {
+                Ok(true) => (),
+                Ok(false) => {
+                    // drop the text that was lexed and read the next line
+                    self.buffer.drain(..state.i);
+                    state.offset += state.i;
+                    state.i = 0;
+                    match self.reader.read_line(&mut self.buffer) {
+                        Ok(0) => self.eof = true,
+                        Ok(_) => (),
+                        Err(err) => {
+                            return Some(Err(state.fail(PreprocessorError {
+                                message: format!("Could not read the text: {}", err),
+                                location: TextLocation::new(state.line, state.column),
+                                len: 0,
+                            })))
+                        }
+                    }
+                }
+                Err(err) => return Some(Err(state.fail(err))),
+            }
+        }
+    }
+}
+
+/// Position of the lexer in the text and the tokens that were not returned yet
+struct State<'a> {
+    lexer: &'a Lexer,
+    mode: Option<&'a LexerMode>,
+    modes: ModeStack,
+    /// First token of every merging mode on the stack
+    starts: Vec<usize>,
+    /// Tokens that can still be merged, the first one is token number `base`
+    pending: VecDeque<Token>,
+    base: usize,
+    /// Byte index in the current text
+    i: usize,
+    /// Byte index of the current text in the whole input
+    offset: usize,
+    line: usize,
+    column: usize,
+    /// Indentation of the open blocks
+    indents: Vec<String>,
+    /// Whitespace at the start of the current line, `None` after its first token
+    indent: Option<String>,
+    done: bool,
+}
+
+impl<'a> State<'a> {
+    /// Starts in the modes of the stack
+    fn new(lexer: &'a Lexer, modes: ModeStack) -> Result<State<'a>, PreprocessorError> {
+        let mut state = State::main(lexer);
+        if let Some(name) = modes.current() {
+            state.mode = Some(lexer.find_mode(name, &TextLocation::new(0, 0))?);
+        }
+        state.starts = vec![0; modes.modes.len()];
+        state.modes = modes;
+        Ok(state)
+    }
+
+    /// Starts in the main mode
+    fn main(lexer: &'a Lexer) -> State<'a> {
+        State {
+            lexer,
+            mode: None,
+            modes: ModeStack::new(),
+            starts: Vec::new(),
+            pending: VecDeque::new(),
+            base: 0,
+            i: 0,
+            offset: 0,
+            line: 0,
+            column: 0,
+            indents: Vec::new(),
+            indent: lexer.indentation.then(String::new),
+            done: false,
+        }
+    }
+
+    fn next<S: Source>(
+        &mut self,
+        text: &str,
+        source: &S,
+    ) -> Option<Result<Token, PreprocessorError>> {
+        loop {
+            if let Some(token) = self.ready() {
+                return Some(Ok(token));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(err) = self.step(text, source, true) {
+                return Some(Err(self.fail(err)));
+            }
+        }
+    }
+
+    /// Next token that can not be merged anymore
+    fn ready(&mut self) -> Option<Token> {
+        let end = match (self.starts.last(), self.merging()) {
+            (Some(start), Some(_)) if !self.done => *start,
+            _ => self.base + self.pending.len(),
+        };
+        if self.base >= end {
+            return None;
+        }
+        self.base += 1;
+        self.pending.pop_front()
+    }
+
+    /// Stops the lexer after an error
+    fn fail(&mut self, err: PreprocessorError) -> PreprocessorError {
+        self.done = true;
+        self.pending.clear();
+        err
+    }
+
+    fn merging(&self) -> Option<&'a String> {
+        self.mode.and_then(|mode| mode.merge.as_ref())
+    }
+
+    /// Number of tokens lexed so far
+    fn count(&self) -> usize {
+        self.base + self.pending.len()
+    }
+
+    fn push(&mut self, index: usize, len: usize, location: TextLocation, kind: TokenKinds) {
+        self.pending.push_back(Token {
+            index: self.offset + index,
+            len,
+            location,
+            kind,
+        });
+    }
+
+    /// Replaces the tokens from `start` with one complex token
+    fn merge(&mut self, start: usize, kind: &str) {
+        let start = start - self.base;
+        if start >= self.pending.len() {
+            return;
+        }
+        let last = self.pending.back().unwrap();
+        let end = last.index + last.len;
+        self.pending.truncate(start + 1);
+        let first = &mut self.pending[start];
+        first.len = end - first.index;
+        first.kind = TokenKinds::Complex(kind.to_string());
+    }
+
+    /// Lexes the next token
+    ///
+    /// If the text is not complete, tokens that could continue after it are not lexed
+    /// and `false` is returned
+    fn step<S: Source>(
+        &mut self,
+        text: &str,
+        source: &S,
+        complete: bool,
+    ) -> Result<bool, PreprocessorError> {
+        let lexer = self.lexer;
+        let len = text.len();
+        let i = self.i;
+        if i >= len {
+            if !complete {
+                return Ok(false);
+            }
+            // merging modes that did not end at the end of the text
+            if let (Some(&start), Some(merge)) = (self.starts.last(), self.merging()) {
+                self.merge(start, merge);
+            }
+            let location = TextLocation::new(self.line, self.column);
+            for _ in self.indents.drain(..) {
+                self.pending.push_back(Token {
+                    index: self.offset + len,
+                    len: 0,
+                    location: location.clone(),
+                    kind: TokenKinds::Control(ControlTokenKind::Dedent),
+                });
+            }
+            self.push(len, 0, location, TokenKinds::Control(ControlTokenKind::Eof));
+            self.done = true;
+            return Ok(true);
+        }
+
+        let (c, c_len) = source.at(i);
+        // Take new line into account
+        if c == '\n' {
+            // the new line is the last character of its line
+            let location = TextLocation::new(self.line, self.column);
+            self.push(i, 1, location, TokenKinds::Control(ControlTokenKind::Eol));
+            self.line += 1;
+            self.column = 0;
+            self.i += 1;
+            if lexer.indentation && self.mode.is_none() {
+                self.indent = Some(String::new());
+            }
+            return Ok(true);
+        }
+
+        // Match literals, token kinds and patterns
+        let location = TextLocation::new(self.line, self.column);
+        let mut comment = false;
+        let matched = match lexer.find_literal(self.mode, &text[i..]) {
+            Some(literal) => match literal.literal.read(&text[i..]) {
+                Some(bytes) => {
+                    comment = matches!(literal.literal, Literal::LineComment { .. });
+                    Some((TokenKinds::Complex(literal.name.clone()), bytes))
+                }
+                None if !complete => return Ok(false),
+                None => {
+                    return Err(PreprocessorError {
+                        message: format!("Unterminated {}", literal.name),
+                        location,
+                        len: len - i,
+                    })
+                }
+            },
+            None => lexer.match_token(self.mode, &text[i..], source.chars(i)),
+        };
+        if let Some((kind, bytes)) = matched {
+            if !complete && i + bytes == len {
+                return Ok(false);
+            }
+            if !comment {
+                self.indentation(i, &location)?;
+            }
+            let action = lexer.action(self.mode, &kind);
+            self.push(i, bytes, location.clone(), kind);
+            advance_location(&text[i..i + bytes], &mut self.line, &mut self.column);
+            self.i += bytes;
+            match action {
+                Some(ModeAction::Push(name)) => self.push_mode(name, &location)?,
+                Some(ModeAction::Pop) => self.pop_mode(&location, bytes)?,
+                None => (),
+            }
+            return Ok(true);
+        }
+
+        // Match whitespace
+        if c.is_whitespace() {
+            if let Some(indent) = &mut self.indent {
+                indent.push(c);
+            }
+            self.push(i, c_len, location, TokenKinds::Whitespace);
+            self.i += c_len;
+            self.column += 1;
+            return Ok(true);
+        }
+
+        // Match text until next whitespace/token/eof
+        let mut end = i;
+        let mut chars = 0;
+        while end < len {
+            let (c, c_len) = source.at(end);
+            if c.is_whitespace() {
+                break;
+            }
+            end += c_len;
+            chars += 1;
+            // keywords start after a character that can not be a part of a word
+            if end < len
+                && (lexer.match_literal(self.mode, source.chars(end)).is_some()
+                    || lexer.find_literal(self.mode, &text[end..]).is_some()
+                    || (!is_word_char(c) && lexer.match_keyword(self.mode, &text[end..]).is_some()))
+            {
+                break;
+            }
+        }
+        if !complete && end == len {
+            return Ok(false);
+        }
+        self.indentation(i, &location)?;
+        self.push(i, end - i, location, TokenKinds::Text);
+        self.i = end;
+        self.column += chars;
+        Ok(true)
+    }
+
+    /// Compares the indentation of the line with the open blocks before its first token
+    fn indentation(
+        &mut self,
+        index: usize,
+        location: &TextLocation,
+    ) -> Result<(), PreprocessorError> {
+        let indent = match self.indent.take() {
+            Some(indent) => indent,
+            None => return Ok(()),
+        };
+        let error = |message: &str| PreprocessorError {
+            message: message.to_string(),
+            location: TextLocation::new(location.line - 1, 0),
+            len: indent.len(),
+        };
+        let inconsistent = "Inconsistent use of tabs and spaces in indentation";
+        let last = self.indents.last().map_or("", String::as_str);
+        if indent == last {
+            return Ok(());
+        }
+        if indent.starts_with(last) {
+            self.indents.push(indent);
+            self.push(
+                index,
+                0,
+                location.clone(),
+                TokenKinds::Control(ControlTokenKind::Indent),
+            );
+            return Ok(());
+        }
+
+        let mut dedents = 0;
+        while let Some(last) = self.indents.last() {
+            if last.len() <= indent.len() {
+                break;
+            }
+            if !last.starts_with(indent.as_str()) {
+                return Err(error(inconsistent));
+            }
+            self.indents.pop();
+            dedents += 1;
+        }
+        let outer = self.indents.last().map_or("", String::as_str);
+        if indent != outer {
+            return Err(error(match indent.starts_with(outer) {
+                true => "Dedent does not match any outer indentation level",
+                false => inconsistent,
+            }));
+        }
+        for _ in 0..dedents {
+            self.push(
+                index,
+                0,
+                location.clone(),
+                TokenKinds::Control(ControlTokenKind::Dedent),
+            );
+        }
+        Ok(())
+    }
+
+    fn push_mode(&mut self, name: &str, location: &TextLocation) -> Result<(), PreprocessorError> {
+        let next = self.lexer.find_mode(name, location)?;
+        // the first part of a merging mode ends where the pushed mode starts
+        let merging = self.merging();
+        if let Some(merge) = merging {
+            self.merge(*self.starts.last().unwrap(), merge);
+        }
+        self.starts.push(match merging {
+            Some(_) => self.count(),
+            None => self.count() - 1,
+        });
+        self.modes.modes.push(name.to_string());
+        self.mode = Some(next);
+        Ok(())
+    }
+
+    fn pop_mode(&mut self, location: &TextLocation, len: usize) -> Result<(), PreprocessorError> {
+        let popped = match self.mode {
+            Some(popped) => popped,
+            None => {
+                return Err(PreprocessorError {
+                    message: "Can not pop the main mode".to_string(),
+                    location: location.clone(),
+                    len,
+                })
+            }
+        };
+        let start = self.starts.pop().unwrap();
+        if let Some(merge) = &popped.merge {
+            self.merge(start, merge);
+        }
+        self.modes.modes.pop();
+        self.mode = match self.modes.current() {
+            Some(name) => Some(self.lexer.find_mode(name, location)?),
+            None => None,
+        };
+        // the next part of a merging mode starts with the token that returned to it
+        if self.merging().is_some() {
+            let count = self.count();
+            if let Some(start) = self.starts.last_mut() {
+                *start = match popped.merge {
+                    Some(_) => count,
+                    None => count - 1,
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Text that is lexed, positions are byte indexes
+trait Source {
+    /// Character at the index and its length in bytes
+    fn at(&self, idx: usize) -> (char, usize);
+    /// Characters from the index
+    fn chars(&self, idx: usize) -> impl Iterator<Item = char> + Clone;
+}
+
+struct Utf8<'a>(&'a str);
+
+impl Source for Utf8<'_> {
+    fn at(&self, idx: usize) -> (char, usize) {
+        let c = self.0[idx..].chars().next().unwrap();
+        (c, c.len_utf8())
+    }
+
+    fn chars(&self, idx: usize) -> impl Iterator<Item = char> + Clone {
+        self.0[idx..].chars()
+    }
+}
+
+/// Ascii text skips the UTF-8 decoding
+struct Ascii<'a>(&'a [u8]);
+
+impl Source for Ascii<'_> {
+    fn at(&self, idx: usize) -> (char, usize) {
+        (self.0[idx] as char, 1)
+    }
+
+    fn chars(&self, idx: usize) -> impl Iterator<Item = char> + Clone {
+        self.0[idx..].iter().map(|&c| c as char)
+    }
+}
+
+/// Character at the byte index
+fn next_char(text: &str, idx: usize) -> char {
+    text[idx..].chars().next().unwrap()
+}
+
+/// Moves the location over a token, patterns can match new lines
+fn advance_location(token: &str, line: &mut usize, column: &mut usize) {
+    for c in token.chars() {
+        if c == '\n' {
+            *line += 1;
+            *column = 0;
+        } else {
+            *column += 1;
+        }
+    }
+}
+
+/// Literal tokens compiled into a trie
+///
+/// Finding the longest token at a position only walks the characters of that token,
+/// instead of comparing every token kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "Vec<String>", into = "Vec<String>")]
+struct Trie {
+    /// Tokens in the order they were added, the trie is serialized as this list
+    tokens: Vec<String>,
+    nodes: Vec<TrieNode>,
+    /// Children of the root for ascii characters, 0 is no child (the root is never a child)
+    ascii: [usize; 128],
+}
+
+#[derive(Debug, Clone, Default)]
+struct TrieNode {
+    /// Sorted by the character
+    children: Vec<(char, usize)>,
+    /// Token that ends in this node
+    token: Option<String>,
+}
+
+impl Trie {
+    fn new() -> Trie {
+        Trie {
+            tokens: Vec::new(),
+            nodes: vec![TrieNode::default()],
+            ascii: [0; 128],
+        }
+    }
+
+    fn insert(&mut self, token: &str) {
+        self.tokens.push(token.to_string());
+        let mut node = 0;
+        for c in token.chars() {
+            node = match self.nodes[node]
+                .children
+                .binary_search_by_key(&c, |child| child.0)
+            {
+                Ok(idx) => self.nodes[node].children[idx].1,
+                Err(idx) => {
+                    let child = self.nodes.len();
+                    self.nodes.push(TrieNode::default());
+                    self.nodes[node].children.insert(idx, (c, child));
+                    if node == 0 && c.is_ascii() {
+                        self.ascii[c as usize] = child;
+                    }
+                    child
+                }
+            };
+        }
+        // the empty token can not be matched
+        if node != 0 {
+            self.nodes[node].token = Some(token.to_string());
+        }
+    }
+
+    /// Returns the longest token at the start of the characters and its length in characters
+    fn longest(&self, mut chars: impl Iterator<Item = char>) -> Option<(&str, usize)> {
+        let mut node = match chars.next() {
+            Some(c) if c.is_ascii() => self.ascii[c as usize],
+            Some(c) => self.child(0, c)?,
+            None => return None,
+        };
+        if node == 0 {
+            return None;
+        }
+        let mut len = 1;
+        let mut longest = None;
+        loop {
+            if let Some(token) = &self.nodes[node].token {
+                longest = Some((token.as_str(), len));
+            }
+            node = match chars.next().and_then(|c| self.child(node, c)) {
+                Some(child) => child,
+                None => return longest,
+            };
+            len += 1;
+        }
+    }
+
+    fn child(&self, node: usize, c: char) -> Option<usize> {
+        let children = &self.nodes[node].children;
+        children
+            .binary_search_by_key(&c, |child| child.0)
+            .ok()
+            .map(|idx| children[idx].1)
+    }
+}
+
+impl PartialEq for Trie {
+    fn eq(&self, other: &Self) -> bool {
+        self.tokens == other.tokens
+    }
+}
+
+impl From<Vec<String>> for Trie {
+    fn from(tokens: Vec<String>) -> Self {
+        let mut trie = Trie::new();
+        for token in &tokens {
+            trie.insert(token);
+        }
+        trie
+    }
+}
+
+impl From<Trie> for Vec<String> {
+    fn from(trie: Trie) -> Self {
+        trie.tokens
+    }
+}
@@ -1,464 +1,2460 @@
-#![cfg_attr(not(feature = "std"), no_std)]
-
-pub mod api;
-pub mod grammar;
-pub mod lexer;
-pub mod parser;
-
-// Choose between std and alloc
-cfg_if::cfg_if! {
-    if #[cfg(feature = "std")] {
-        extern crate std;
-        use std::prelude::v1::*;
-
-        pub type Map<K, V> = std::collections::HashMap<K, V>;
-    } else {
-        extern crate alloc;
-        pub use alloc::string::*;
-        pub use alloc::vec::*;
-        use alloc::vec;
-
-        pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
-    }
-}
-
-use serde::{Deserialize, Serialize};
-
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct Parser {
-    pub lexer: lexer::Lexer,
-    pub grammar: grammar::Grammar,
-    pub parser: parser::Parser,
-}
-
-impl Parser {
-    pub fn new() -> Parser {
-        let lexer = lexer::Lexer::new();
-        let grammar = grammar::Grammar::new();
-        Parser {
-            lexer,
-            grammar,
-            parser: parser::Parser::new(),
-        }
-    }
-
-    pub fn parse(
-        &self,
-        tokens: &Vec<lexer::Token>,
-        text: &str,
-    ) -> Result<parser::ParseResult, parser::ParseError> {
-        self.parser.parse(&self.grammar, &self.lexer, text, tokens)
-    }
-}
-
-#[cfg(test)]
-#[cfg(feature = "std")]
-mod tests {
-
-    use std::io::Write;
-
-    use crate::lexer::TokenKinds;
-
-    use self::grammar::{Parameters, VariableKind};
-
-    use super::*;
-
-    #[test]
-    fn arithmetic_tokens() {
-        let mut parser = Parser::new();
-        let txt = "Function 1 +\n 2 * 3 - 4 /= 5";
-        // Tokens that will be recognized by the lexer
-        //
-        // White space is ignored by default
-        //
-        // Everything else is a text token
-        parser.lexer.add_tokens(&[
-            "+".to_string(),
-            "-".to_string(),
-            "*".to_string(),
-            "/=".to_string(),
-            "Function".to_string(),
-        ]);
-
-        // Parse the text
-        let tokens = parser.lexer.lex_utf8(txt).unwrap();
-
-        assert_eq!(tokens.len(), 21);
-    }
-
-    #[test]
-    fn stringify() {
-        let mut parser = Parser::new();
-        let txt = "Functiond\t 1 +\n 2 * 3 - 4 /= 5";
-        // Tokens that will be recognized by the lexer
-        //
-        // White space is ignored by default
-        //
-        // Everything else is a text token
-        parser.lexer.add_tokens(&[
-            "+".to_string(),
-            "-".to_string(),
-            "*".to_string(),
-            "/=".to_string(),
-            "Function".to_string(),
-        ]);
-
-        // Parse the text
-        let tokens = parser.lexer.lex_utf8(txt).unwrap();
-
-        assert_eq!(parser.lexer.stringify_slice(&tokens, txt), txt);
-        assert_eq!(parser.lexer.stringify_slice(&tokens[0..1], txt), "Function");
-        assert_eq!(parser.lexer.stringify_slice(&tokens[1..5], txt), "d\t 1");
-    }
-
-    #[test]
-    fn unfinished_token() {
-        let mut parser = Parser::new();
-        let txt = "fun";
-        parser.lexer.add_token("function".to_string());
-        let tokens = parser.lexer.lex_utf8(txt).unwrap();
-        assert_eq!(tokens[0].kind, TokenKinds::Text);
-    }
-
-    #[test]
-    fn rules() {
-        let mut parser = Parser::new();
-        let txt = "let   danda=  1+60;";
-        parser.lexer.add_token("=".to_string());
-        parser.lexer.add_token(":".to_string());
-        parser.lexer.add_token("+".to_string());
-        parser.lexer.add_token(";".to_string());
-        parser.lexer.add_token("-".to_string());
-        parser.lexer.add_token("*".to_string());
-        parser.lexer.add_token("/".to_string());
-
-        let tokens = parser.lexer.lex_utf8(txt).unwrap();
-
-        let mut variables = Map::new();
-        variables.insert("ident".to_string(), VariableKind::Node);
-        variables.insert("type".to_string(), VariableKind::Node);
-        variables.insert("value".to_string(), VariableKind::Node);
-
-        parser.grammar.enumerators.insert(
-            "operators".to_string(),
-            grammar::Enumerator {
-                name: "operators".to_string(),
-                values: vec![
-                    grammar::MatchToken::Token(TokenKinds::Token("+".to_string())),
-                    grammar::MatchToken::Token(TokenKinds::Token("-".to_string())),
-                    grammar::MatchToken::Token(TokenKinds::Token("*".to_string())),
-                    grammar::MatchToken::Token(TokenKinds::Token("/".to_string())),
-                ],
-            },
-        );
-
-        parser.grammar.add_node(grammar::Node {
-            name: "KWLet".to_string(),
-            rules: vec![
-                // detect the keyword
-                grammar::Rule::Is {
-                    token: grammar::MatchToken::Word("let".to_string()),
-                    rules: vec![],
-                    parameters: vec![Parameters::HardError(true)],
-                },
-                // detect the ident
-                grammar::Rule::Is {
-                    token: grammar::MatchToken::Token(TokenKinds::Text),
-                    rules: vec![],
-                    parameters: vec![Parameters::Set("ident".to_string())],
-                },
-                // detect the type if it exists
-                grammar::Rule::Maybe {
-                    token: grammar::MatchToken::Token(TokenKinds::Token(":".to_string())),
-                    is: vec![grammar::Rule::Is {
-                        token: grammar::MatchToken::Token(TokenKinds::Text),
-                        rules: vec![],
-                        parameters: vec![Parameters::Set("type".to_string())],
-                    }],
-                    isnt: vec![],
-                    parameters: vec![],
-                },
-                // detect the value if it exists
-                grammar::Rule::Maybe {
-                    token: grammar::MatchToken::Token(TokenKinds::Token("=".to_string())),
-                    is: vec![grammar::Rule::Is {
-                        token: grammar::MatchToken::Node("value".to_string()),
-                        rules: vec![],
-                        parameters: vec![Parameters::Set("value".to_string())],
-                    }],
-                    isnt: vec![],
-                    parameters: vec![],
-                },
-                // consume the semicolon (optional)
-                grammar::Rule::Maybe {
-                    token: grammar::MatchToken::Token(TokenKinds::Token(";".to_string())),
-                    is: vec![],
-                    isnt: vec![],
-                    parameters: vec![],
-                },
-            ],
-            variables,
-        });
-        let mut variables = Map::new();
-        variables.insert("nodes".to_string(), VariableKind::NodeList);
-        parser.grammar.add_node(grammar::Node {
-            name: "value".to_string(),
-            rules: vec![
-                // detect the value[0]
-                grammar::Rule::Is {
-                    token: grammar::MatchToken::Token(TokenKinds::Text),
-                    rules: vec![],
-                    parameters: vec![Parameters::Set("nodes".to_string())],
-                },
-                // detect the operator
-                grammar::Rule::While {
-                    token: grammar::MatchToken::Enumerator("operators".to_string()),
-                    // detect the value[n]
-                    rules: vec![grammar::Rule::Is {
-                        token: grammar::MatchToken::Token(TokenKinds::Text),
-                        rules: vec![],
-                        parameters: vec![Parameters::Set("nodes".to_string())],
-                    }],
-                    parameters: vec![Parameters::Set("nodes".to_string())],
-                },
-            ],
-            variables,
-        });
-        parser.parser.entry = String::from("KWLet");
-
-        let dump = serde_json::to_string(&parser);
-
-        let mut file = std::fs::File::create("KWLet.json").unwrap();
-        match dump {
-            Ok(ref dump) => {
-                file.write_all(dump.as_bytes()).unwrap();
-            }
-            Err(err) => panic!("Failed to dump grammar: {}", err),
-        }
-
-        parser.parse(&tokens, txt).unwrap();
-    }
-
-    #[test]
-    fn string() {
-        let txt = r#"
-
-
-"úťf-8 štring"
-"second string"
-"#;
-
-        let mut parser = Parser::new();
-        parser.lexer.add_token("\"".to_string());
-
-        // add random tokens to test the lexer
-        parser.lexer.add_token("=".to_string());
-        parser.lexer.add_token(";".to_string());
-        parser.lexer.add_token(":".to_string());
-        parser.lexer.add_token("+".to_string());
-        parser.lexer.add_token("-".to_string());
-        parser.lexer.add_token("*".to_string());
-        parser.lexer.add_token("/".to_string());
-        parser.lexer.add_token("let".to_string());
-        parser.lexer.add_token("function".to_string());
-        parser.lexer.add_token("danda".to_string());
-        parser.lexer.add_token("1".to_string());
-        parser.lexer.add_token("60".to_string());
-        parser.lexer.add_token("string".to_string());
-        parser.lexer.add_token(" ".to_string());
-
-        let tokens = parser.lexer.lex_utf8(txt).unwrap();
-
-        let mut variables = Map::new();
-        variables.insert("start".to_string(), VariableKind::Node);
-        variables.insert("end".to_string(), VariableKind::Node);
-        parser.grammar.add_node(grammar::Node {
-            name: "string".to_string(),
-            rules: vec![
-                // detect the start
-                grammar::Rule::Is {
-                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
-                    rules: vec![],
-                    parameters: vec![Parameters::Set("start".to_string()), Parameters::NodeStart],
-                },
-                grammar::Rule::Until {
-                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
-                    rules: vec![],
-                    parameters: vec![Parameters::Set("end".to_string()), Parameters::NodeEnd],
-                },
-            ],
-            variables,
-        });
-
-        let mut variables = Map::new();
-        variables.insert("strings".to_string(), VariableKind::NodeList);
-        variables.insert("count".to_string(), VariableKind::Number);
-        variables.insert("zero".to_string(), VariableKind::Number);
-
-        parser.grammar.add_node(grammar::Node {
-            name: "entry".to_string(),
-            rules: vec![
-                grammar::Rule::While {
-                    token: grammar::MatchToken::Node("string".to_string()),
-                    rules: vec![],
-                    parameters: vec![
-                        Parameters::Set("strings".to_string()),
-                        Parameters::Increment("count".to_string()),
-                    ],
-                },
-                grammar::Rule::Command {
-                    command: grammar::Commands::Compare {
-                        left: "count".to_string(),
-                        right: "zero".to_string(), // zero is not defined, so it will be 0
-                        comparison: grammar::Comparison::Equal,
-                        rules: vec![grammar::Rule::Command {
-                            command: grammar::Commands::Error {
-                                message: "No strings found".to_string(),
-                            },
-                        }],
-                    },
-                },
-            ],
-            variables,
-        });
-
-        let result = parser.parse(&tokens, txt).unwrap();
-        let strings = result.entry.get_list("strings");
-        assert_eq!(strings.len(), 2);
-
-        // first string
-        assert_eq!(result.stringify_node(&strings[0], txt), r#""úťf-8 štring""#);
-
-        // second string
-        assert_eq!(
-            result.stringify_node(&strings[1], txt),
-            r#""second string""#
-        );
-    }
-
-    #[test]
-    fn vec_char_eq() {
-        let a = vec!['a', 'b', 'c'];
-        let b = vec!['a', 'b', 'c'];
-        let c = vec!['a', 'b', 'd'];
-        assert_eq!(a, b);
-        assert_eq!(true, a == b);
-        assert_eq!(false, a == c);
-
-        let slice_a = &a[0..2];
-        let slice_b = &b[0..2];
-        let slice_c = &c[1..3];
-        assert_eq!(slice_a, slice_b);
-        assert_eq!(true, slice_a == slice_b);
-        assert_eq!(false, slice_a == slice_c);
-    }
-
-    /// Fields are ordered according to the order of the lines in the meta file
-    struct Meta {
-        lines: usize,
-        line_length: usize,
-    }
-
-    fn read_dotmeta() -> Meta {
-        use std::fs;
-        let meta = fs::read_to_string("workload.meta").unwrap();
-        let mut lns = meta.lines();
-        let lines = lns.next().unwrap().parse().unwrap();
-        let line_length = lns.next().unwrap().parse().unwrap();
-        Meta { lines, line_length }
-    }
-
-    #[test]
-    fn workload_file() {
-        let meta = read_dotmeta();
-        let mut parser = Parser::new();
-        // let txt = include_str!("../workload.txt"); // The size of the file is 100MB which would make it impractical to include it in the tests
-        use std::fs;
-        let txt = fs::read_to_string("workload.txt").unwrap();
-        parser.lexer.add_token("\"".to_string());
-
-        let lex_start = std::time::Instant::now();
-        let tokens = parser.lexer.lex_utf8(&txt).unwrap();
-
-        let variables = Map::new();
-        parser.grammar.add_node(grammar::Node {
-            name: "string".to_string(),
-            rules: vec![
-                // detect the start
-                grammar::Rule::Is {
-                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
-                    rules: vec![],
-                    parameters: vec![Parameters::NodeStart, Parameters::HardError(true)],
-                },
-                grammar::Rule::Until {
-                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
-                    rules: vec![],
-                    parameters: vec![Parameters::NodeEnd],
-                },
-            ],
-            variables,
-        });
-
-        let mut variables = Map::new();
-        variables.insert("strings".to_string(), VariableKind::NodeList);
-        variables.insert("count".to_string(), VariableKind::Number);
-        variables.insert("zero".to_string(), VariableKind::Number);
-
-        parser.grammar.add_node(grammar::Node {
-            name: "entry".to_string(),
-            rules: vec![
-                grammar::Rule::While {
-                    token: grammar::MatchToken::Node("string".to_string()),
-                    rules: vec![],
-                    parameters: vec![
-                        Parameters::Set("strings".to_string()),
-                        Parameters::Increment("count".to_string()),
-                    ],
-                },
-                grammar::Rule::Command {
-                    command: grammar::Commands::Compare {
-                        left: "count".to_string(),
-                        right: "zero".to_string(), // zero is not defined, so it will be 0
-                        comparison: grammar::Comparison::Equal,
-                        rules: vec![grammar::Rule::Command {
-                            command: grammar::Commands::Error {
-                                message: "No strings found".to_string(),
-                            },
-                        }],
-                    },
-                },
-            ],
-            variables,
-        });
-
-        let result = parser.parse(&tokens, &txt).unwrap();
-        let strings = result.entry.get_list("strings");
-        // verify the result
-        assert_eq!(strings.len(), meta.lines);
-        for s in strings {
-            assert_eq!(result.stringify_node(s, &txt).len(), meta.line_length);
-        }
-    }
-
-    #[test]
-    fn load_json() {
-        use std::io::Read;
-
-        let mut file = std::fs::File::open("KWLet.json").unwrap();
-        let mut parser = String::new();
-        file.read_to_string(&mut parser).unwrap();
-
-        let parser: Parser = serde_json::from_str(&parser).unwrap();
-
-        let txt = "let a: int = 500 * 9;";
-
-        let tokens = parser.lexer.lex_utf8(txt).unwrap();
-
-        let result = parser.parse(&tokens, txt).unwrap();
-
-        assert_eq!(
-            result.stringify_node(result.entry.try_get_node("value").as_ref().unwrap(), txt),
-            " 500 * 9"
-        );
-    }
-}
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub mod api;
+pub mod ast;
+pub mod builder;
+pub mod codegen;
+pub mod de;
+pub mod dialect;
+pub mod dump;
+pub mod ebnf;
+pub mod format;
+pub mod grammar;
+pub mod lexer;
+pub mod lines;
+pub mod parser;
+pub mod pattern;
+pub mod railroad;
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+
+        pub type Map<K, V> = std::collections::HashMap<K, V>;
+    } else {
+        extern crate alloc;
+        pub use alloc::string::*;
+        pub use alloc::vec::*;
+        use alloc::vec;
+
+        pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+    }
+}
+
+pub use de::from_tree;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Parser {
+    pub lexer: lexer::Lexer,
+    pub grammar: grammar::Grammar,
+    pub parser: parser::Parser,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        let lexer = lexer::Lexer::new();
+        let grammar = grammar::Grammar::new();
+        Parser {
+            lexer,
+            grammar,
+            parser: parser::Parser::new(),
+        }
+    }
+
+    pub fn parse(
+        &self,
+        tokens: &Vec<lexer::Token>,
+        text: &str,
+    ) -> Result<parser::ParseResult, parser::ParseError> {
+        self.parser.parse(&self.grammar, &self.lexer, text, tokens)
+    }
+
+    /// Parses tokens that are lexed while the parser reads them
+    ///
+    /// `parser.parse_source(parser.lexer.tokens(text), text)` does not lex the text
+    /// past the point where the parser stopped. Lexers with preprocessors can not be
+    /// used lazily, parse the tokens of `lex_utf8` instead
+    #[allow(clippy::result_large_err)]
+    pub fn parse_source<'a>(
+        &self,
+        tokens: impl Into<parser::TokenSource<'a>>,
+        text: &str,
+    ) -> Result<parser::ParseResult, parser::ParseError> {
+        let mut tokens = tokens.into();
+        self.parser
+            .parse_source(&self.grammar, &self.lexer, text, &mut tokens)
+    }
+
+    /// Imports another parser under a namespace
+    ///
+    /// The grammar is imported using `Grammar::import` and the lexer tokens that
+    /// are missing are added to the lexer. Patterns, literals, keywords, modes and mode
+    /// changes are copied unless the lexer already has one with the same name, if the
+    /// existing one is different the clash is reported by the validator
+    ///
+    /// Preprocessors are not copied since they run over the whole text
+    pub fn import(&mut self, namespace: &str, other: &Parser) {
+        use grammar::Clash;
+
+        self.grammar.import(namespace, &other.grammar);
+        let clashes = &mut self.grammar.clashes;
+        for token in &other.lexer.token_kinds {
+            if !self.lexer.token_kinds.contains(token) {
+                self.lexer.add_token(token.clone());
+            }
+        }
+        for pattern in &other.lexer.patterns {
+            match self.lexer.patterns.iter().find(|p| p.name == pattern.name) {
+                Some(existing) if existing != pattern => {
+                    clashes.push(Clash::Pattern(pattern.name.clone()))
+                }
+                Some(_) => (),
+                None => self.lexer.patterns.push(pattern.clone()),
+            }
+        }
+        for literal in &other.lexer.literals {
+            match self.lexer.literals.iter().find(|l| l.name == literal.name) {
+                Some(existing) if existing != literal => {
+                    clashes.push(Clash::Literal(literal.name.clone()))
+                }
+                Some(_) => (),
+                None => self.lexer.literals.push(literal.clone()),
+            }
+        }
+        for keyword in &other.lexer.keywords {
+            match self.lexer.keywords.iter().find(|k| k.word == keyword.word) {
+                Some(existing) if existing != keyword => {
+                    clashes.push(Clash::Keyword(keyword.word.clone()))
+                }
+                Some(_) => (),
+                None => self.lexer.keywords.push(keyword.clone()),
+            }
+        }
+        for (name, mode) in &other.lexer.modes {
+            match self.lexer.modes.get(name) {
+                Some(existing) if existing != mode => clashes.push(Clash::Mode(name.clone())),
+                Some(_) => (),
+                None => {
+                    self.lexer.modes.insert(name.clone(), mode.clone());
+                }
+            }
+        }
+        for (token, action) in &other.lexer.actions {
+            match self.lexer.actions.get(token) {
+                Some(existing) if existing != action => {
+                    clashes.push(Clash::ModeAction(token.clone()))
+                }
+                Some(_) => (),
+                None => {
+                    self.lexer.actions.insert(token.clone(), action.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+
+    use std::io::Write;
+
+    use crate::lexer::TokenKinds;
+
+    use self::grammar::{Parameters, VariableKind};
+
+    use super::*;
+
+    #[test]
+    fn arithmetic_tokens() {
+        let mut parser = Parser::new();
+        let txt = "Function 1 +\n 2 * 3 - 4 /= 5";
+        // Tokens that will be recognized by the lexer
+        //
+        // White space is ignored by default
+        //
+        // Everything else is a text token
+        parser.lexer.add_tokens(&[
+            "+".to_string(),
+            "-".to_string(),
+            "*".to_string(),
+            "/=".to_string(),
+            "Function".to_string(),
+        ]);
+
+        // Parse the text
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+
+        assert_eq!(tokens.len(), 21);
+    }
+
+    #[test]
+    fn stringify() {
+        let mut parser = Parser::new();
+        let txt = "Functiond\t 1 +\n 2 * 3 - 4 /= 5";
+        // Tokens that will be recognized by the lexer
+        //
+        // White space is ignored by default
+        //
+        // Everything else is a text token
+        parser.lexer.add_tokens(&[
+            "+".to_string(),
+            "-".to_string(),
+            "*".to_string(),
+            "/=".to_string(),
+            "Function".to_string(),
+        ]);
+
+        // Parse the text
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+
+        assert_eq!(parser.lexer.stringify_slice(&tokens, txt), txt);
+        assert_eq!(parser.lexer.stringify_slice(&tokens[0..1], txt), "Function");
+        assert_eq!(parser.lexer.stringify_slice(&tokens[1..5], txt), "d\t 1");
+    }
+
+    #[test]
+    fn unfinished_token() {
+        let mut parser = Parser::new();
+        let txt = "fun";
+        parser.lexer.add_token("function".to_string());
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        assert_eq!(tokens[0].kind, TokenKinds::Text);
+    }
+
+    #[test]
+    fn rules() {
+        let mut parser = Parser::new();
+        let txt = "let   danda=  1+60;";
+        parser.lexer.add_token("=".to_string());
+        parser.lexer.add_token(":".to_string());
+        parser.lexer.add_token("+".to_string());
+        parser.lexer.add_token(";".to_string());
+        parser.lexer.add_token("-".to_string());
+        parser.lexer.add_token("*".to_string());
+        parser.lexer.add_token("/".to_string());
+
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+
+        let mut variables = Map::new();
+        variables.insert("ident".to_string(), VariableKind::Node);
+        variables.insert("type".to_string(), VariableKind::Node);
+        variables.insert("value".to_string(), VariableKind::Node);
+
+        parser.grammar.enumerators.insert(
+            "operators".to_string(),
+            grammar::Enumerator {
+                name: "operators".to_string(),
+                values: vec![
+                    grammar::MatchToken::Token(TokenKinds::Token("+".to_string())),
+                    grammar::MatchToken::Token(TokenKinds::Token("-".to_string())),
+                    grammar::MatchToken::Token(TokenKinds::Token("*".to_string())),
+                    grammar::MatchToken::Token(TokenKinds::Token("/".to_string())),
+                ],
+            },
+        );
+
+        parser.grammar.add_node(grammar::Node {
+            name: "KWLet".to_string(),
+            rules: vec![
+                // detect the keyword
+                grammar::Rule::Is {
+                    token: grammar::MatchToken::Word("let".to_string()),
+                    rules: vec![],
+                    parameters: vec![Parameters::HardError(true)],
+                },
+                // detect the ident
+                grammar::Rule::Is {
+                    token: grammar::MatchToken::Token(TokenKinds::Text),
+                    rules: vec![],
+                    parameters: vec![Parameters::Set("ident".to_string())],
+                },
+                // detect the type if it exists
+                grammar::Rule::Maybe {
+                    token: grammar::MatchToken::Token(TokenKinds::Token(":".to_string())),
+                    is: vec![grammar::Rule::Is {
+                        token: grammar::MatchToken::Token(TokenKinds::Text),
+                        rules: vec![],
+                        parameters: vec![Parameters::Set("type".to_string())],
+                    }],
+                    isnt: vec![],
+                    parameters: vec![],
+                },
+                // detect the value if it exists
+                grammar::Rule::Maybe {
+                    token: grammar::MatchToken::Token(TokenKinds::Token("=".to_string())),
+                    is: vec![grammar::Rule::Is {
+                        token: grammar::MatchToken::Node("value".to_string()),
+                        rules: vec![],
+                        parameters: vec![Parameters::Set("value".to_string())],
+                    }],
+                    isnt: vec![],
+                    parameters: vec![],
+                },
+                // consume the semicolon (optional)
+                grammar::Rule::Maybe {
+                    token: grammar::MatchToken::Token(TokenKinds::Token(";".to_string())),
+                    is: vec![],
+                    isnt: vec![],
+                    parameters: vec![],
+                },
+            ],
+            variables,
+        });
+        let mut variables = Map::new();
+        variables.insert("nodes".to_string(), VariableKind::NodeList);
+        parser.grammar.add_node(grammar::Node {
+            name: "value".to_string(),
+            rules: vec![
+                // detect the value[0]
+                grammar::Rule::Is {
+                    token: grammar::MatchToken::Token(TokenKinds::Text),
+                    rules: vec![],
+                    parameters: vec![Parameters::Set("nodes".to_string())],
+                },
+                // detect the operator
+                grammar::Rule::While {
+                    token: grammar::MatchToken::Enumerator("operators".to_string()),
+                    // detect the value[n]
+                    rules: vec![grammar::Rule::Is {
+                        token: grammar::MatchToken::Token(TokenKinds::Text),
+                        rules: vec![],
+                        parameters: vec![Parameters::Set("nodes".to_string())],
+                    }],
+                    parameters: vec![Parameters::Set("nodes".to_string())],
+                },
+            ],
+            variables,
+        });
+        parser.parser.entry = String::from("KWLet");
+
+        let dump = serde_json::to_string(&parser);
+
+        let mut file = std::fs::File::create("KWLet.json").unwrap();
+        match dump {
+            Ok(ref dump) => {
+                file.write_all(dump.as_bytes()).unwrap();
+            }
+            Err(err) => panic!("Failed to dump grammar: {:?}", err),
+        }
+
+        parser.parse(&tokens, txt).unwrap();
+    }
+
+    #[test]
+    fn string() {
+        let txt = r#"
+
+
+"úťf-8 štring"
+"second string"
+"#;
+
+        let mut parser = Parser::new();
+        parser.lexer.add_token("\"".to_string());
+
+        // add random tokens to test the lexer
+        parser.lexer.add_token("=".to_string());
+        parser.lexer.add_token(";".to_string());
+        parser.lexer.add_token(":".to_string());
+        parser.lexer.add_token("+".to_string());
+        parser.lexer.add_token("-".to_string());
+        parser.lexer.add_token("*".to_string());
+        parser.lexer.add_token("/".to_string());
+        parser.lexer.add_token("let".to_string());
+        parser.lexer.add_token("function".to_string());
+        parser.lexer.add_token("danda".to_string());
+        parser.lexer.add_token("1".to_string());
+        parser.lexer.add_token("60".to_string());
+        parser.lexer.add_token("string".to_string());
+        parser.lexer.add_token(" ".to_string());
+
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+
+        let mut variables = Map::new();
+        variables.insert("start".to_string(), VariableKind::Node);
+        variables.insert("end".to_string(), VariableKind::Node);
+        parser.grammar.add_node(grammar::Node {
+            name: "string".to_string(),
+            rules: vec![
+                // detect the start
+                grammar::Rule::Is {
+                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
+                    rules: vec![],
+                    parameters: vec![Parameters::Set("start".to_string()), Parameters::NodeStart],
+                },
+                grammar::Rule::Until {
+                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
+                    rules: vec![],
+                    parameters: vec![Parameters::Set("end".to_string()), Parameters::NodeEnd],
+                },
+            ],
+            variables,
+        });
+
+        let mut variables = Map::new();
+        variables.insert("strings".to_string(), VariableKind::NodeList);
+        variables.insert("count".to_string(), VariableKind::Number);
+        variables.insert("zero".to_string(), VariableKind::Number);
+
+        parser.grammar.add_node(grammar::Node {
+            name: "entry".to_string(),
+            rules: vec![
+                grammar::Rule::While {
+                    token: grammar::MatchToken::Node("string".to_string()),
+                    rules: vec![],
+                    parameters: vec![
+                        Parameters::Set("strings".to_string()),
+                        Parameters::Increment("count".to_string()),
+                    ],
+                },
+                grammar::Rule::Command {
+                    command: grammar::Commands::Compare {
+                        left: "count".to_string(),
+                        right: "zero".to_string(), // zero is not defined, so it will be 0
+                        comparison: grammar::Comparison::Equal,
+                        rules: vec![grammar::Rule::Command {
+                            command: grammar::Commands::Error {
+                                message: "No strings found".to_string(),
+                            },
+                        }],
+                    },
+                },
+            ],
+            variables,
+        });
+
+        let result = parser.parse(&tokens, txt).unwrap();
+        let strings = result.entry.get_list("strings");
+        assert_eq!(strings.len(), 2);
+
+        // first string
+        assert_eq!(result.stringify_node(&strings[0], txt), r#""úťf-8 štring""#);
+
+        // second string
+        assert_eq!(
+            result.stringify_node(&strings[1], txt),
+            r#""second string""#
+        );
+    }
+
+    #[test]
+    fn grammar_file() {
+        let txt = r#"
+"úťf-8 štring"
+"second string"
+"#;
+
+        let parser = format::load(
+            r#"
+            tokens "\"" "=" ";";
+
+            node string {
+                var start: node;
+                var end: node;
+
+                // detect the start
+                is "\"" [set(start), node_start];
+                until "\"" [set(end), node_end];
+            }
+
+            node entry {
+                var strings: list;
+                var count: number;
+                var zero: number;
+
+                while string [set(strings), increment(count)];
+                compare count == zero {
+                    error("No strings found");
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        assert!(parser.grammar.validate(&parser.lexer).pass());
+
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        let result = parser.parse(&tokens, txt).unwrap();
+        let strings = result.entry.get_list("strings");
+        assert_eq!(strings.len(), 2);
+        assert_eq!(
+            result.stringify_node(&strings[1], txt),
+            r#""second string""#
+        );
+
+        let err = format::load("node entry { is #text [set(x)] }").unwrap_err();
+        assert_eq!(err.location, lexer::TextLocation::new(0, 31));
+        match err.kind {
+            format::FormatErrors::Expected { .. } => (),
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn grammar_file_round_trip() {
+        let dump = std::fs::read_to_string("neruda/ruda_grammar.json").unwrap();
+        // the preprocessor of neruda is not needed for printing
+        let mut preprocessors = lexer::Preprocessors::new();
+        preprocessors.add(
+            "neruda",
+            lexer::Preprocessor::from_fn(|_, tokens| Ok(tokens)),
+        );
+        let parser = dump::load_with(&dump, &preprocessors).unwrap();
+
+        let printed = format::print(&parser);
+        let loaded = format::load_with(&printed, &preprocessors).unwrap();
+
+        assert_eq!(loaded.grammar, parser.grammar);
+        assert_eq!(loaded.lexer.token_kinds, parser.lexer.token_kinds);
+        assert_eq!(
+            loaded.lexer.preprocessor_names(),
+            parser.lexer.preprocessor_names()
+        );
+        assert_eq!(loaded.parser.entry, parser.parser.entry);
+        // printing is stable
+        assert_eq!(format::print(&loaded), printed);
+
+        // named preprocessors are printed and bound again when loading
+        let mut parser = format::load(r#"tokens "+";"#).unwrap();
+        let identity = preprocessors.get("neruda").unwrap();
+        parser.lexer.add_preprocessor("neruda", identity.clone());
+        parser.lexer.preprocessors.push(identity);
+        let printed = format::print(&parser);
+        assert!(printed.starts_with("// 1 unnamed preprocessor(s)"));
+        assert!(printed.contains("\npreprocessor \"neruda\";\n"));
+        let err = format::load(&printed).unwrap_err();
+        assert!(matches!(
+            err.kind,
+            format::FormatErrors::MissingPreprocessor(name) if name == "neruda"
+        ));
+        let loaded = format::load_with(&printed, &preprocessors).unwrap();
+        assert_eq!(loaded.lexer.preprocessor_names(), ["neruda"]);
+        assert_eq!(loaded.lexer.lex_utf8("a + b").unwrap().len(), 6);
+    }
+
+    #[test]
+    fn ebnf_import() {
+        let parser = ebnf::import(
+            r#"
+            /* W3C style */
+            expr   ::= term (("+" | "-") term)*
+            term   ::= factor (("*" | "/") factor)*
+            factor ::= number | "(" expr ")"
+            number ::= "1" | "2" | "3"
+            "#,
+        )
+        .unwrap();
+        assert!(parser.grammar.validate(&parser.lexer).pass());
+        assert_eq!(parser.parser.entry, "expr");
+
+        let txt = "1 + 2 * (3 - 1)";
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        let result = parser.parse(&tokens, txt).unwrap();
+        let tail = result.entry.get_list("expr_1");
+        assert_eq!(tail.len(), 1);
+        assert_eq!(result.stringify_node(&tail[0], txt).trim(), "+ 2 * (3 - 1)");
+        let term = tail[0].try_get_node("term").as_ref().unwrap();
+        assert_eq!(term.get_list("term_1").len(), 1);
+
+        let parser = ebnf::import(
+            r#"
+            (* ISO style *)
+            greeting = "hello", [","], {name}, "!";
+            name = "bob" | "alice";
+            "#,
+        )
+        .unwrap();
+        let txt = "hello, bob alice !";
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        let result = parser.parse(&tokens, txt).unwrap();
+        assert_eq!(result.entry.get_list("name").len(), 2);
+    }
+
+    #[test]
+    fn ebnf_diagnostics() {
+        let errors = ebnf::import(
+            r#"
+            list ::= list "," item | item
+            item ::= [a-z]+ | missing
+            "#,
+        )
+        .unwrap_err();
+        let kinds = errors.into_iter().map(|e| e.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                ebnf::EbnfErrors::Unsupported("character class [a-z]".to_string()),
+                ebnf::EbnfErrors::UndefinedRule("missing".to_string()),
+                ebnf::EbnfErrors::LeftRecursion(vec!["list".to_string(), "list".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn ebnf_export() {
+        let imported = ebnf::import(
+            r#"
+            expr   ::= term (("+" | "-") term)*
+            term   ::= factor (("*" | "/") factor)*
+            factor ::= number | "(" expr ")"
+            number ::= "1" | "2" | "3"
+            "#,
+        )
+        .unwrap();
+        let exported = ebnf::export(&imported);
+        assert!(exported.contains("expr ::= term\n         expr_1*\n"));
+        let reimported = ebnf::import(&exported).unwrap();
+        assert_eq!(reimported.grammar, imported.grammar);
+        assert_eq!(reimported.parser.entry, "expr");
+
+        let parser = format::load(
+            r#"
+            node entry {
+                var a: number;
+                var b: number;
+
+                label(start);
+                maybe #text [back(1)];
+                compare a < b {
+                    goto(start);
+                }
+                until ";";
+            }
+            "#,
+        )
+        .unwrap();
+        let exported = ebnf::export(&parser);
+        assert!(exported.contains("/* provided by the lexer: ANY, TEXT */"));
+        assert!(exported.contains("/* variables: a: Number, b: Number */"));
+        assert!(exported.contains("( TEXT /* back 1 */ )?"));
+        assert!(exported.contains("( /* if a < b */ /* goto start */ )?"));
+        assert!(exported.contains("( ANY - \";\" )*\n"));
+    }
+
+    #[test]
+    fn railroad_diagrams() {
+        let parser = format::load(
+            r#"
+            tokens "=" ";" "," "&";
+            enum values { 'true', 'false', %int }
+
+            node entry {
+                var lines: list;
+
+                while line [set(lines)];
+            }
+
+            node line {
+                var ident: node;
+                var values: list;
+
+                is #text [set(ident)];
+                maybe "=" {
+                    is value [set(values)];
+                    while "," {
+                        is value [set(values)];
+                    }
+                }
+                is ";";
+            }
+
+            node value {
+                one_of {
+                    "&" { is #text; }
+                    @values;
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let svg = railroad::svg(&parser.grammar, "line").unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect class=\"token\"").count(), 3);
+        assert_eq!(svg.matches("<rect class=\"node\"").count(), 2);
+        assert!(svg.contains(">TEXT</text>"));
+        assert!(railroad::svg(&parser.grammar, "missing").is_none());
+
+        // enumerators are expanded into choices
+        let svg = railroad::svg(&parser.grammar, "value").unwrap();
+        assert!(svg.contains(">false</text>"));
+        assert!(svg.contains(">INT</text>"));
+
+        let html = railroad::html(&parser.grammar);
+        assert_eq!(html.matches("<svg").count(), 3);
+        assert!(html.contains("<h2 id=\"value\">value</h2>"));
+        assert!(html.contains("<a href=\"#value\">"));
+    }
+
+    #[test]
+    fn grammar_modules() {
+        let lists = format::load(
+            r#"
+            tokens "," "[" "]";
+            enum item { #text, list }
+
+            node list {
+                var items: list;
+
+                is "[";
+                maybe @item [set(items)] {
+                    while "," {
+                        is @item [set(items)];
+                    }
+                }
+                is "]";
+            }
+            "#,
+        )
+        .unwrap();
+        let mut parser = format::load(
+            r#"
+            tokens "=" "[";
+
+            node entry {
+                var name: node;
+                var value: node;
+
+                is #text [set(name)];
+                is "=";
+                is lists::list [set(value)];
+            }
+            "#,
+        )
+        .unwrap();
+        parser.import("lists", &lists);
+        assert!(parser.grammar.validate(&parser.lexer).pass());
+        assert!(parser.grammar.enumerators.contains_key("lists::item"));
+
+        let txt = "a = [b, [c], d]";
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        let result = parser.parse(&tokens, txt).unwrap();
+        let value = result.entry.try_get_node("value").as_ref().unwrap();
+        assert_eq!(value.name(), "lists::list");
+        let items = value.get_list("items");
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[1].name(), "lists::list");
+
+        // importing the same namespace twice clashes
+        parser.import("lists", &lists);
+        let validation = parser.grammar.validate(&parser.lexer);
+        assert!(!validation.pass());
+        assert!(validation.errors.iter().any(|err| matches!(
+            &err.kind,
+            grammar::validator::ValidationErrors::NameClash(grammar::Clash::Node(name)) if name == "lists::list"
+        )));
+
+        // lexer definitions are not namespaced, the same ones are shared
+        let numbers = format::load(r#"pattern int "\\d+"; keywords "fn";"#).unwrap();
+        let mut host = format::load(r#"pattern int "\\d+"; keywords ignore_case "fn";"#).unwrap();
+        host.import("numbers", &numbers);
+        assert_eq!(
+            host.grammar.clashes,
+            [grammar::Clash::Keyword("fn".to_string())]
+        );
+        let hex = format::load(r#"pattern int "[0-9a-f]+";"#).unwrap();
+        host.import("hex", &hex);
+        assert_eq!(host.lexer.patterns(), numbers.lexer.patterns());
+        let validation = host.grammar.validate(&host.lexer);
+        let clashes = validation.errors.iter().filter_map(|err| match &err.kind {
+            grammar::validator::ValidationErrors::NameClash(clash) => Some(clash.clone()),
+            _ => None,
+        });
+        assert_eq!(
+            clashes.collect::<Vec<_>>(),
+            [
+                grammar::Clash::Keyword("fn".to_string()),
+                grammar::Clash::Pattern("int".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn templates() {
+        let parser = format::load(
+            r#"
+            tokens "," ";" "(" ")" "=";
+            entry entry;
+
+            template SeparatedList<Item, Sep> {
+                var items: list;
+
+                is Item [set(items)];
+                while Sep {
+                    maybe Item [set(items)];
+                }
+            }
+
+            node entry {
+                var args: node;
+                var body: node;
+
+                is "(";
+                is SeparatedList<#text, ","> [set(args)];
+                is ")";
+                is SeparatedList<assign, ";"> [set(body)];
+            }
+
+            node assign {
+                var name: node;
+                var value: node;
+
+                is #text [set(name)];
+                is "=";
+                is #text [set(value)];
+            }
+            "#,
+        )
+        .unwrap();
+        assert!(parser.grammar.validate(&parser.lexer).pass());
+
+        let txt = "(a, b,) x = a; y = b;";
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        let result = parser.parse(&tokens, txt).unwrap();
+        let args = result.entry.try_get_node("args").as_ref().unwrap();
+        assert_eq!(args.name(), "SeparatedList<#text, \",\">");
+        assert_eq!(args.get_list("items").len(), 2);
+        let body = result.entry.try_get_node("body").as_ref().unwrap();
+        assert_eq!(body.name(), "SeparatedList<assign, \";\">");
+        let items = body.get_list("items");
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].name(), "assign");
+
+        // instances are not printed, they are created again when loading
+        let printed = format::print(&parser);
+        assert!(printed.contains("template SeparatedList<Item, Sep> {"));
+        assert!(!printed.contains("node `SeparatedList"));
+        assert_eq!(format::load(&printed).unwrap().grammar, parser.grammar);
+
+        let err = format::load(
+            r#"
+            template pair<A, B> { is A; is B; }
+            node entry { is pair<#text>; }
+            "#,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err.kind,
+            format::FormatErrors::Template(grammar::TemplateError::Arguments {
+                expected: 2,
+                found: 1,
+                ..
+            })
+        ));
+        assert_eq!(err.location.line, 3);
+    }
+
+    #[test]
+    fn versioned_dumps() {
+        let parser = format::load(
+            r#"
+            tokens "=";
+            entry assign;
+
+            node assign {
+                var name: node;
+
+                is #text [set(name)];
+                is "=";
+            }
+            "#,
+        )
+        .unwrap();
+
+        let dumped = dump::dump(&parser).unwrap();
+        assert_eq!(dump::version(&dumped).unwrap(), dump::VERSION);
+        assert_eq!(dump::load(&dumped).unwrap().grammar, parser.grammar);
+
+        // plain serde dumps from before the header existed are migrated
+        let mut legacy = serde_json::to_value(&parser).unwrap();
+        let grammar = legacy["grammar"].as_object_mut().unwrap();
+        grammar.remove("clashes");
+        grammar.remove("templates");
+        let legacy = legacy.to_string();
+        assert_eq!(dump::version(&legacy).unwrap(), 1);
+        assert_eq!(dump::load(&legacy).unwrap().grammar, parser.grammar);
+
+        // headerless dumps written with the current types load as well
+        let plain = serde_json::to_string(&parser).unwrap();
+        let loaded = dump::load(&plain).unwrap();
+        assert_eq!(loaded.grammar, parser.grammar);
+        let text = "a =";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        assert_eq!(loaded.lexer.lex_utf8(text).unwrap(), tokens);
+        assert!(loaded.parse(&tokens, text).is_ok());
+
+        let newer = dumped.replace(
+            &format!("\"version\":{}", dump::VERSION),
+            &format!("\"version\":{}", dump::VERSION + 1),
+        );
+        assert_eq!(
+            dump::load(&newer).unwrap_err(),
+            dump::DumpError::NewerVersion {
+                found: dump::VERSION + 1,
+                supported: dump::VERSION,
+            }
+        );
+        assert_eq!(dump::load("[1, 2]").unwrap_err(), dump::DumpError::NotADump);
+    }
+
+    #[test]
+    fn builder() {
+        use builder::*;
+
+        let loaded = format::load(
+            r#"
+            global depth: number;
+
+            node KWLet {
+                var ident: node;
+                var type: node;
+                var value: node;
+
+                is 'let' [hard_error(true)];
+                is #text [set(ident)];
+                maybe ":" {
+                    is type [set(type)];
+                }
+                maybe "=" {
+                    is expression [set(value)];
+                }
+                is ";";
+            }
+
+            node type {
+                var path: list;
+                var refs: number;
+                var mutable: bool;
+
+                while "&" [increment(refs)];
+                maybe 'mut' [true(mutable)];
+                is #text [set(path), increment_global(depth)];
+                while "::" {
+                    is #text [set(path)];
+                }
+                one_of {
+                    "?" [true(mutable)];
+                    "!";
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let kw_let = node("KWLet")
+            .word("let")
+            .hard()
+            .text()
+            .set("ident")
+            .maybe(tok(":"), |b| b.node("type").set("type"))
+            .maybe(tok("="), |b| b.node("expression").set("value"))
+            .tok(";");
+        let ty = node("type")
+            .repeat(tok("&"), |b| b)
+            .increment("refs")
+            .maybe(word("mut"), |b| b)
+            .set_true("mutable")
+            .text()
+            .set("path")
+            .increment_global("depth")
+            .repeat(tok("::"), |b| b.text().push("path"))
+            .one_of(
+                options()
+                    .option(tok("?"))
+                    .set_true("mutable")
+                    .option(tok("!")),
+            );
+        let built = grammar().node(kw_let).node(ty).build().unwrap();
+        assert_eq!(built, loaded.grammar);
+
+        let errors = grammar()
+            .node(
+                node("broken")
+                    .set("value")
+                    .text()
+                    .set("value")
+                    .increment("value"),
+            )
+            .build()
+            .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0].kind, BuildErrors::NoToken(_)));
+        assert!(matches!(
+            &errors[1].kind,
+            BuildErrors::VariableKind { variable, .. } if variable == "value"
+        ));
+    }
+
+    #[test]
+    fn codegen() {
+        let parser = format::load(
+            r#"
+            tokens "(" ")" ",";
+            enum values { #text, list }
+
+            node entry {
+                var values: list;
+
+                label(start);
+                maybe @values [set(values)] {
+                    is "," [goto(start)];
+                }
+            }
+
+            node list {
+                is "(" [hard_error(true)];
+                is entry;
+                is ")";
+            }
+            "#,
+        )
+        .unwrap();
+        let code = codegen::generate(&parser);
+
+        // nodes are sorted by name
+        assert!(code.contains("// node entry\n    fn node_0("));
+        assert!(code.contains("// node list\n    fn node_1("));
+        assert!(code.contains("fn enumerator_0("));
+        assert!(code.contains("let result = self.node_0(ctx);"));
+        // labels are resolved when generating
+        assert!(code.contains("\"start\" => 0,"));
+        assert!(code.contains("lexer.add_token(\",\".to_string());"));
+        assert!(code.contains("TokenKinds::Token(\"(\".to_string())"));
+        assert!(code.contains("ctx.hard(node, true);"));
+    }
+
+    #[test]
+    fn typed_ast() {
+        let parser = format::load(
+            r#"
+            tokens "=" ";" ",";
+            entry KWLet;
+            global names: list;
+
+            node KWLet {
+                var ident: node;
+                var value: node;
+                var type: node;
+                var list: list;
+                var mutable: bool;
+
+                is 'let' [hard_error(true)];
+                maybe 'mut' [true(mutable)];
+                is #text [set(ident), global(names)];
+                is "=";
+                one_of {
+                    KWLet [set(value)];
+                    #text [set(value)];
+                }
+                while "," {
+                    is value_list [set(list)];
+                }
+            }
+
+            node value_list {
+                var depth: number;
+                is ";" [increment(depth)];
+            }
+            "#,
+        )
+        .unwrap();
+        let code = ast::generate(&parser.grammar);
+
+        assert!(code.contains("pub struct KWLet {"));
+        assert!(code.contains("pub ident: Option<::rparse::lexer::Token>,"));
+        assert!(code.contains("pub value: Option<KWLetValue>,"));
+        assert!(code.contains("pub r#type: Option<::rparse::parser::Nodes>,"));
+        assert!(code.contains("pub list: Vec<Box<ValueList>>,"));
+        assert!(code.contains("pub mutable: bool,"));
+        assert!(code.contains("pub struct ValueList {\n    pub depth: i32,"));
+        assert!(code.contains("pub enum KWLetValue {\n    KWLet(Box<KWLet>),\n    Token("));
+        assert!(code.contains("pub names: Vec<::rparse::lexer::Token>,"));
+
+        // reserved words are raw identifiers and clashing fields are numbered
+        let mut grammar = parser.grammar.clone();
+        let variables = &mut grammar.nodes.get_mut("value_list").unwrap().variables;
+        variables.insert("try".to_string(), VariableKind::Number);
+        variables.insert("a-b".to_string(), VariableKind::Number);
+        variables.insert("a_b".to_string(), VariableKind::Number);
+        let code = ast::generate(&grammar);
+        assert!(code.contains("pub r#try: i32,"));
+        assert!(code.contains("pub a_b: i32,\n    pub a_b2: i32,"));
+        assert!(code.contains("a_b2: ::rparse::ast::number(variables, name, \"a_b\")?,"));
+
+        // the conversion helpers check the variables
+        let text = "let mut a = b, ;";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let result = parser.parse(&tokens, text).unwrap();
+        let entry = &result.entry;
+        assert!(ast::expect_node(entry, "KWLet").is_ok());
+        assert!(ast::expect_node(entry, "value_list").is_err());
+        assert!(ast::boolean(&entry.variables, "KWLet", "mutable").unwrap());
+        assert_eq!(
+            ast::list(&entry.variables, "KWLet", "list").unwrap().len(),
+            1
+        );
+        let value = ast::single(&entry.variables, "KWLet", "value")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            parser.lexer.stringify(&ast::token(value).unwrap(), text),
+            "b"
+        );
+        assert!(ast::node(value).is_err());
+        assert!(ast::number(&entry.variables, "KWLet", "ident").is_err());
+    }
+
+    #[test]
+    fn deserialize_tree() {
+        let parser = format::load(
+            r#"
+            tokens "=" ";" ",";
+            entry KWLet;
+
+            node KWLet {
+                var ident: node;
+                var value: node;
+                var type: node;
+                var list: list;
+                var mutable: bool;
+
+                is 'let';
+                maybe 'mut' [true(mutable)];
+                is #text [set(ident)];
+                is "=";
+                one_of {
+                    KWLet [set(value)];
+                    #text [set(value)];
+                }
+                while "," {
+                    is value_list [set(list)];
+                }
+            }
+
+            node value_list {
+                var depth: number;
+                is ";" [increment(depth)];
+            }
+            "#,
+        )
+        .unwrap();
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct LetDecl<'a> {
+            ident: &'a str,
+            value: Option<Value<'a>>,
+            #[serde(rename = "type")]
+            kind: Option<String>,
+            list: Vec<ValueList>,
+            mutable: bool,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum Value<'a> {
+            #[serde(borrow)]
+            KWLet(Box<LetDecl<'a>>),
+            #[serde(rename = "d")]
+            D,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct ValueList {
+            depth: u8,
+        }
+
+        let text = "let a = let mut c = d, ;";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let result = parser.parse(&tokens, text).unwrap();
+        let decl: LetDecl = from_tree(&result, text).unwrap();
+        assert_eq!(
+            decl,
+            LetDecl {
+                ident: "a",
+                value: Some(Value::KWLet(Box::new(LetDecl {
+                    ident: "c",
+                    value: Some(Value::D),
+                    kind: None,
+                    list: vec![ValueList { depth: 1 }],
+                    mutable: true,
+                }))),
+                kind: None,
+                list: vec![],
+                mutable: false,
+            }
+        );
+
+        // nodes can be read as their text, tokens are parsed
+        let whole: Map<String, serde_json::Value> = from_tree(&result, text).unwrap();
+        assert_eq!(whole["ident"], "a");
+        assert_eq!(whole["type"], serde_json::Value::Null);
+        let node: LetDecl = de::from_node(&result.entry, text).unwrap();
+        let parser::VariableKind::Node(Some(value)) = &result.entry.variables["value"] else {
+            unreachable!()
+        };
+        let inner: &str = de::from_nodes(value, text).unwrap();
+        assert_eq!(inner, result.stringify_node(value, text));
+        assert_eq!(node.ident, "a");
+
+        #[derive(Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct Numeric {
+            ident: u32,
+        }
+        assert!(matches!(
+            from_tree::<Numeric>(&result, text),
+            Err(de::DeError::InvalidText {
+                expected: "u32",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn pattern_tokens() {
+        use pattern::{Pattern, PatternErrors};
+
+        let mut parser = Parser::new();
+        parser.lexer.add_tokens(&["+".to_string(), ".".to_string()]);
+        parser.lexer.add_pattern("int", r"\d+").unwrap();
+        parser.lexer.add_pattern("float", r"\d+\.\d+").unwrap();
+        parser.lexer.add_pattern("ident", r"[a-zA-Z_]\w*").unwrap();
+        parser
+            .lexer
+            .add_pattern("string", r#""([^"\\]|\\.)*""#)
+            .unwrap();
+
+        let text = "x1 + 12.5 + \"a + \\\"b\" + 7.x\n\"c\nd\" 3";
+        let kinds = |tokens: &[lexer::Token]| {
+            tokens
+                .iter()
+                .filter(|tok| tok.kind != TokenKinds::Whitespace)
+                .map(|tok| match &tok.kind {
+                    TokenKinds::Complex(name) => name.clone(),
+                    other => format!("{:?}", other),
+                })
+                .collect::<Vec<_>>()
+        };
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                "ident",
+                "Token(\"+\")",
+                "float",
+                "Token(\"+\")",
+                "string",
+                "Token(\"+\")",
+                "int",
+                "Token(\".\")",
+                "ident",
+                "Control(Eol)",
+                "string",
+                "int",
+                "Control(Eof)",
+            ]
+        );
+        assert_eq!(parser.lexer.stringify(&tokens[8], text), "\"a + \\\"b\"");
+        // tokens after a multi line pattern keep the right location
+        let last = &tokens[tokens.len() - 2];
+        assert_eq!(parser.lexer.stringify(last, text), "3");
+        assert_eq!(last.location, lexer::TextLocation::new(2, 3));
+        assert_eq!(
+            kinds(&parser.lexer.lex_ascii(text).unwrap()),
+            kinds(&tokens)
+        );
+
+        // longest match, counted repetitions and errors
+        assert_eq!(Pattern::new("a|ab").unwrap().longest("abc"), Some(2));
+        assert_eq!(Pattern::new(r"\d{2,3}").unwrap().longest("12345"), Some(3));
+        assert_eq!(Pattern::new("x{2}").unwrap().longest("x"), None);
+        assert_eq!(Pattern::new("[^a-c]+").unwrap().longest("xyzb"), Some(3));
+        assert!(Pattern::new("a*").unwrap_err().kind == PatternErrors::MatchesEmpty);
+        assert!(Pattern::new("(a").unwrap_err().kind == PatternErrors::UnexpectedEnd);
+        assert!(Pattern::new("a{3,1}").unwrap_err().kind == PatternErrors::InvalidRepetition);
+        // every count is in the limit, but together they would be a billion instructions
+        let nested = Pattern::new("((a{1000}){1000}){1000}").unwrap_err();
+        assert!(nested.kind == PatternErrors::TooLarge);
+        assert!(Pattern::new("(a{100}b?){100}").is_ok());
+        assert!(Pattern::new("a)").unwrap_err().kind == PatternErrors::Unexpected(')'));
+
+        // patterns survive dumps and the grammar format
+        let loaded = dump::load(&dump::dump(&parser).unwrap()).unwrap();
+        assert_eq!(loaded.lexer.patterns(), parser.lexer.patterns());
+        assert_eq!(loaded.lexer.lex_utf8(text).unwrap(), tokens);
+
+        let printed = format::print(&parser);
+        assert!(printed.contains(r#"pattern int "\\d+";"#));
+        let reloaded = format::load(&printed).unwrap();
+        assert_eq!(reloaded.lexer.patterns(), parser.lexer.patterns());
+    }
+
+    #[test]
+    fn token_trie() {
+        let mut parser = Parser::new();
+        parser.lexer.add_tokens(&[
+            "=".to_string(),
+            "===".to_string(),
+            "==".to_string(),
+            "→".to_string(),
+            "fn".to_string(),
+        ]);
+        let text = "a === b == c→d =fn =x= fnord";
+        let words = |tokens: &[lexer::Token]| {
+            tokens
+                .iter()
+                .filter(|tok| !tok.kind.is_whitespace())
+                .map(|tok| parser.lexer.stringify(tok, text).to_string())
+                .collect::<Vec<_>>()
+        };
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        assert_eq!(
+            words(&tokens),
+            vec![
+                "a", "===", "b", "==", "c", "→", "d", "=", "fn", "=", "x", "=", "fn", "ord",
+                "__EOF__"
+            ]
+        );
+        assert_eq!(tokens[2].kind, TokenKinds::Token("===".to_string()));
+
+        // words at the end of the text are not split
+        let text = "let x = abcdef";
+        let tokens = parser.lexer.lex_ascii(text).unwrap();
+        assert_eq!(
+            parser.lexer.stringify(&tokens[tokens.len() - 2], text),
+            "abcdef"
+        );
+        assert_eq!(
+            parser.lexer.lex_utf8(text).unwrap()[tokens.len() - 2].kind,
+            TokenKinds::Text
+        );
+
+        // the trie is rebuilt when the lexer is loaded
+        let json = serde_json::to_string(&parser).unwrap();
+        let loaded: Parser = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            loaded.lexer.lex_utf8(text).unwrap(),
+            parser.lexer.lex_utf8(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn lexer_modes() {
+        use lexer::{ModeAction, ModeStack};
+
+        let parser = format::load(
+            r#"
+            tokens "+" "\"" "{" "}";
+            on "\"" push string;
+
+            mode string {
+                tokens "\"" "\\\"" "${";
+                merge string;
+                on "\"" pop;
+                on "${" push interpolation;
+            }
+
+            mode interpolation {
+                inherit;
+                on "{" push interpolation;
+                on "}" pop;
+            }
+            "#,
+        )
+        .unwrap();
+        let words = |tokens: &[lexer::Token], text: &str| {
+            tokens
+                .iter()
+                .filter(|tok| !tok.kind.is_whitespace())
+                .map(|tok| match &tok.kind {
+                    TokenKinds::Complex(kind) => {
+                        format!("{}:{}", kind, parser.lexer.stringify(tok, text))
+                    }
+                    _ => parser.lexer.stringify(tok, text).to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let text = r#"a + "x + \"y\" ${b + "c" + {d}} z" + e"#;
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        assert_eq!(
+            words(&tokens, text),
+            vec![
+                "a",
+                "+",
+                r#"string:"x + \"y\" ${"#,
+                "b",
+                "+",
+                r#"string:"c""#,
+                "+",
+                "{",
+                "d",
+                "}",
+                r#"string:} z""#,
+                "+",
+                "e",
+                "__EOF__",
+            ]
+        );
+        assert_eq!(parser.lexer.lex_ascii(text).unwrap(), tokens);
+
+        // lexing can continue from the modes where it stopped
+        let mut modes = ModeStack::new();
+        let first = parser.lexer.lex_utf8_with("a \"b + c", &mut modes).unwrap();
+        assert_eq!(modes.current(), Some("string"));
+        assert_eq!(
+            words(&first, "a \"b + c"),
+            vec!["a", "string:\"b + c", "__EOF__"]
+        );
+        let second = parser.lexer.lex_utf8_with("d\" + e", &mut modes).unwrap();
+        assert_eq!(modes, ModeStack::new());
+        assert_eq!(
+            words(&second, "d\" + e"),
+            vec!["string:d\"", "+", "e", "__EOF__"]
+        );
+        let json = serde_json::to_string(&ModeStack {
+            modes: vec!["string".to_string()],
+        })
+        .unwrap();
+        let mut modes: ModeStack = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parser.lexer.lex_utf8_with("d\"", &mut modes).unwrap().len(),
+            2
+        );
+
+        let mut broken = parser.clone();
+        broken.lexer.on("}", ModeAction::Pop);
+        assert!(broken.lexer.lex_utf8("a }").is_err());
+        broken
+            .lexer
+            .on("+", ModeAction::Push("missing".to_string()));
+        assert!(broken.lexer.lex_utf8("a +").is_err());
+
+        // modes are saved with the lexer
+        let loaded = dump::load(&dump::dump(&parser).unwrap()).unwrap();
+        assert_eq!(loaded.lexer.lex_utf8(text).unwrap(), tokens);
+        let printed = format::print(&parser);
+        assert!(printed.contains("mode interpolation {\n    on \"{\" push interpolation;\n"));
+        let reloaded = format::load(&printed).unwrap();
+        assert_eq!(reloaded.lexer.lex_utf8(text).unwrap(), tokens);
+        assert!(codegen::generate(&parser).contains(".merge(\"string\")"));
+    }
+
+    #[test]
+    fn lexer_literals() {
+        use lexer::{Lexer, Literal, TextLocation};
+
+        let parser = format::load(
+            r##"
+            tokens "+" "/";
+            literal string string("\"", escape: "\\");
+            literal char string("'", escape: "\\");
+            literal comment line_comment("//");
+            literal block block_comment("/*", "*/", nested: true);
+            literal raw raw_string("r#\"", "\"#");
+            "##,
+        )
+        .unwrap();
+        let words = |lexer: &Lexer, text: &str| {
+            lexer
+                .lex_utf8(text)
+                .unwrap()
+                .iter()
+                .filter(|tok| !tok.kind.is_whitespace())
+                .map(|tok| match &tok.kind {
+                    TokenKinds::Complex(kind) => format!("{}:{}", kind, lexer.stringify(tok, text)),
+                    _ => lexer.stringify(tok, text).to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let text = concat!(
+            "a + \"b \\\" // c\" / x // comment\n",
+            "/* outer /* inner */ still */ r#\"raw \"quoted\"\nline\"# 'c'"
+        );
+        assert_eq!(
+            words(&parser.lexer, text),
+            vec![
+                "a",
+                "+",
+                "string:\"b \\\" // c\"",
+                "/",
+                "x",
+                "comment:// comment",
+                "block:/* outer /* inner */ still */",
+                "raw:r#\"raw \"quoted\"\nline\"#",
+                "char:'c'",
+                "__EOF__",
+            ]
+        );
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let raw = tokens
+            .iter()
+            .find(|tok| tok.kind == TokenKinds::Complex("raw".to_string()));
+        assert_eq!(raw.unwrap().location, TextLocation::new(1, 30));
+        let char = &tokens[tokens.len() - 2];
+        assert_eq!(char.location, TextLocation::new(2, 7));
+        assert_eq!(char.index, text.len() - 3);
+        assert_eq!(char.len, 3);
+        assert_eq!(parser.lexer.lex_ascii(text).unwrap(), tokens);
+
+        // literals end the text before them
+        assert_eq!(
+            words(&parser.lexer, "x\"y\"z"),
+            vec!["x", "string:\"y\"", "z", "__EOF__"]
+        );
+
+        let err = parser.lexer.lex_utf8("a\n  \"abc").unwrap_err();
+        assert_eq!(err.location, TextLocation::new(1, 2));
+        assert_eq!(err.len, 4);
+        assert!(parser.lexer.lex_utf8("/* /* */").is_err());
+        assert!(parser.lexer.lex_utf8("\"abc\\\"").is_err());
+
+        // doubled quotes when the escape is the quote
+        let mut sql = Lexer::new();
+        sql.add_literal("sql", Literal::string("'", Some('\'')));
+        assert_eq!(
+            words(&sql, "'it''s' x"),
+            vec!["sql:'it''s'", "x", "__EOF__"]
+        );
+
+        // literals are saved with the lexer
+        let loaded = dump::load(&dump::dump(&parser).unwrap()).unwrap();
+        assert_eq!(loaded.lexer.lex_utf8(text).unwrap(), tokens);
+        let printed = format::print(&parser);
+        assert!(printed.contains("literal block block_comment(\"/*\", \"*/\", nested: true);"));
+        assert_eq!(
+            format::load(&printed).unwrap().lexer.literals(),
+            parser.lexer.literals()
+        );
+        assert!(codegen::generate(&parser).contains("Literal::string(\"'\", Some('\\\\'))"));
+        assert!(format::load("literal x heredoc(\"<<\");").is_err());
+    }
+
+    #[test]
+    fn lazy_tokens() {
+        let parser = format::load(
+            r#"
+            tokens "=" ";" "\"" "}";
+            literal comment block_comment("/*", "*/");
+            on "\"" push string;
+
+            mode string {
+                tokens "\"" "${";
+                merge string;
+                on "\"" pop;
+                on "${" push interpolation;
+            }
+
+            mode interpolation {
+                inherit;
+                on "}" pop;
+            }
+
+            node entry {
+                var name: node;
+                var value: node;
+
+                is 'let';
+                is #text [set(name)];
+                is "=";
+                is %string [set(value)];
+                is ";";
+            }
+            "#,
+        )
+        .unwrap();
+
+        let text = "let a = \"x\n${ b /* c\n */ } y\"; /* end */\n";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let lazy = parser
+            .lexer
+            .tokens(text)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(lazy, tokens);
+        let read = parser.lexer.read_tokens(text.as_bytes());
+        assert_eq!(read.collect::<Result<Vec<_>, _>>().unwrap(), tokens);
+        // the last line does not have to end with a new line
+        let read = parser.lexer.read_tokens(text.trim_end().as_bytes());
+        let read = read.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(read, parser.lexer.lex_utf8(text.trim_end()).unwrap());
+
+        // tokens before an error are returned
+        let mut broken = parser.lexer.tokens("a = /* b");
+        assert_eq!(broken.next().unwrap().unwrap().kind, TokenKinds::Text);
+        assert!(broken.by_ref().any(|token| token.is_err()));
+        assert!(broken.next().is_none());
+        let mut read = parser.lexer.read_tokens("a = /* b\n c".as_bytes());
+        assert!(read.any(|token| token.is_err()));
+
+        let text = "let a = \"b /* c */\";\n";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let result = parser.parse(&tokens, text).unwrap();
+        let lazy = parser
+            .parse_source(parser.lexer.tokens(text), text)
+            .unwrap();
+        for name in ["name", "value"] {
+            let (lazy, result) = (&lazy.entry.variables[name], &result.entry.variables[name]);
+            assert_eq!(format!("{:?}", lazy), format!("{:?}", result));
+        }
+        assert_eq!(lazy.entry.last_string_idx, result.entry.last_string_idx);
+        let text = "let a = \"b\"; /*";
+        let err = parser.parse_source(parser.lexer.tokens(text), text);
+        assert!(format!("{:?}", err.unwrap_err()).contains("Lexer error: Unterminated comment"));
+
+        // preprocessors need all of the tokens, the lazy lexers fail instead of skipping them
+        let mut parser = parser.clone();
+        let preprocessor = lexer::Preprocessor::from_fn(|_, tokens| Ok(tokens));
+        parser.lexer.preprocessors.push(preprocessor);
+        let text = "let a = \"b\";";
+        assert!(parser.lexer.tokens(text).next().unwrap().is_err());
+        let mut read = parser.lexer.read_tokens(text.as_bytes());
+        assert!(read.next().unwrap().is_err());
+        assert!(read.next().is_none());
+        let tokens = parser.lexer.tokens(text);
+        let err = parser.parse_source(tokens, text).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            parser::ParseErrors::Lexer(message) if message.starts_with("Preprocessors can not")
+        ));
+    }
+
+    #[test]
+    fn relex_edits() {
+        use lexer::{Lexer, Preprocessor, PreprocessorError, TextEdit, Token};
+
+        fn no_whitespace(_: &str, tokens: Vec<Token>) -> Result<Vec<Token>, PreprocessorError> {
+            Ok(tokens
+                .into_iter()
+                .filter(|tok| tok.kind != TokenKinds::Whitespace)
+                .collect())
+        }
+
+        let mut parser = format::load(
+            r#"
+            tokens "=" "==" "+" ";" "(" ")" "{" "}" "fn";
+            pattern int "\\d+";
+            literal string string("\"", escape: "\\");
+            literal comment line_comment("//");
+            literal block block_comment("/*", "*/", nested: true);
+            "#,
+        )
+        .unwrap();
+        parser
+            .lexer
+            .preprocessors
+            .push(Preprocessor::from_fn(no_whitespace));
+
+        // the same edits are made with `relex` and with `lex_utf8` of the whole text
+        let check = |lexer: &Lexer, text: &mut String, tokens: &mut Vec<Token>, seed: &mut u64| {
+            let mut random = |max: usize| {
+                *seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (*seed >> 33) as usize % max
+            };
+            const SNIPPETS: [&str; 12] = [
+                "",
+                "a",
+                "=",
+                "+ 1",
+                "\n",
+                "/*",
+                "*/",
+                "\"",
+                "//",
+                "fn x() {\n}",
+                "é ",
+                "12;\n",
+            ];
+            let bounds = text
+                .char_indices()
+                .map(|(idx, _)| idx)
+                .chain([text.len()])
+                .collect::<Vec<_>>();
+            let start = bounds[random(bounds.len())];
+            let end = bounds[random(bounds.len())].max(start);
+            let end = end.min(start + 8 * random(2));
+            let end = *bounds.iter().find(|&&idx| idx >= end).unwrap();
+            let edit = TextEdit::new(start..end, SNIPPETS[random(SNIPPETS.len())]);
+            let edited = edit.apply(text);
+            match lexer.lex_utf8(&edited) {
+                Ok(expected) => {
+                    assert_eq!(
+                        lexer.relex(tokens, text, &edit).unwrap(),
+                        expected,
+                        "{:?}",
+                        edit
+                    );
+                    *text = edited;
+                    *tokens = expected;
+                }
+                // unterminated literals, the text is not changed
+                Err(_) => assert!(lexer.relex(tokens, text, &edit).is_err()),
+            }
+        };
+
+        let mut text = String::from(concat!(
+            "fn main() {\n",
+            "    a = 1 + 2; // sum\n",
+            "    /* block\n",
+            "       comment */ b == \"str\\\"ing\";\n",
+            "}\n",
+        ));
+        let mut tokens = parser.lexer.lex_utf8(&text).unwrap();
+        let mut seed = 7;
+        for _ in 0..2000 {
+            check(&parser.lexer, &mut text, &mut tokens, &mut seed);
+        }
+
+        // modes lex the whole text again
+        parser
+            .lexer
+            .on("{", lexer::ModeAction::Push("block".to_string()));
+        parser
+            .lexer
+            .add_mode("block")
+            .inherit(true)
+            .on("}", lexer::ModeAction::Pop);
+        let mut tokens = parser.lexer.lex_utf8(&text).unwrap();
+        for _ in 0..200 {
+            check(&parser.lexer, &mut text, &mut tokens, &mut seed);
+        }
+    }
+
+    #[test]
+    fn line_index() {
+        use lines::{Encoding, LineIndex, Position};
+
+        let text = "a\t\u{e9}\u{1F600}b\n\n\tc\u{1F600}";
+        let lines = LineIndex::new(text);
+        assert_eq!(lines.lines(), 3);
+        assert_eq!(lines.line_start(2), Some(text.find("\tc").unwrap()));
+        let b = text.find('b').unwrap();
+        assert_eq!(lines.position(b, Encoding::Utf8), Position::new(0, 8));
+        assert_eq!(lines.position(b, Encoding::Char), Position::new(0, 4));
+        assert_eq!(lines.position(b, Encoding::Utf16), Position::new(0, 5));
+        let lines = lines.with_tab_width(4);
+        assert_eq!(lines.position(b, Encoding::Utf16), Position::new(0, 7));
+        assert_eq!(
+            lines.position(text.len(), Encoding::Utf16),
+            Position::new(2, 7)
+        );
+        // inside of a character and past the end of a line
+        assert_eq!(lines.position(b - 1, Encoding::Utf8), Position::new(0, 6));
+        assert_eq!(
+            lines.offset(Position::new(0, 5), Encoding::Utf16),
+            Some(b - 4)
+        );
+        assert_eq!(
+            lines.offset(Position::new(0, 50), Encoding::Char),
+            Some(b + 1)
+        );
+        assert_eq!(
+            lines.offset(Position::new(1, 2), Encoding::Char),
+            Some(b + 2)
+        );
+        assert_eq!(lines.offset(Position::new(3, 0), Encoding::Char), None);
+        for width in [1, 3, 4] {
+            let lines = LineIndex::new(text).with_tab_width(width);
+            for encoding in [Encoding::Utf8, Encoding::Char, Encoding::Utf16] {
+                for (idx, _) in text.char_indices().chain([(text.len(), ' ')]) {
+                    let position = lines.position(idx, encoding);
+                    assert_eq!(
+                        lines.offset(position, encoding),
+                        Some(idx),
+                        "{:?}",
+                        position
+                    );
+                }
+            }
+        }
+
+        // the locations of the lexer, a new line is the last character of its line
+        let parser = format::load(
+            r#"
+            tokens "=" ";";
+            literal comment block_comment("/*", "*/");
+
+            node entry {
+                is 'let';
+                is #text;
+                is "=";
+            }
+            "#,
+        )
+        .unwrap();
+        let text = "let\t\u{1F600} /* \u{e9}\n*/ ;\r\nx = \u{e9}\n\n";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let lines = LineIndex::new(text);
+        for token in &tokens {
+            assert_eq!(token.location, lines.location(token.index), "{:?}", token);
+            assert_eq!(lines.location_offset(&token.location), Some(token.index));
+        }
+        let eol = &parser.lexer.lex_ascii("a b\n=\n").unwrap()[3];
+        assert_eq!(eol.location, lexer::TextLocation::new(0, 3));
+        let semicolon = tokens
+            .iter()
+            .find(|tok| tok.kind == TokenKinds::Token(";".into()));
+        let semicolon = semicolon.unwrap();
+        assert_eq!(
+            semicolon.position(&lines, Encoding::Utf16),
+            Position::new(1, 3)
+        );
+        assert_eq!(semicolon.end(&lines, Encoding::Utf16), Position::new(1, 4));
+
+        let text = "let \u{1F600} ;";
+        let err = parser
+            .parse(&parser.lexer.lex_utf8(text).unwrap(), text)
+            .unwrap_err();
+        assert_eq!(err.location(), &lexer::TextLocation::new(0, 6));
+        let lines = LineIndex::new(text);
+        assert_eq!(
+            err.position(&lines, Encoding::Utf16),
+            Some(Position::new(0, 7))
+        );
+    }
+
+    #[test]
+    fn indentation() {
+        use lexer::{ControlTokenKind, TextEdit};
+
+        let parser = format::load(
+            r##"
+            tokens ":" "(" ")" ",";
+            literal comment line_comment("#");
+            indentation;
+            on "(" push call;
+
+            mode call {
+                inherit;
+                on ")" pop;
+            }
+
+            node entry {
+                var statements: list;
+
+                while statement [set(statements)];
+            }
+
+            node statement {
+                var name: node;
+                var body: node;
+
+                is #text [set(name)];
+                maybe ":" {
+                    is block [set(body)];
+                }
+            }
+
+            node block {
+                var statements: list;
+
+                is #indent;
+                while statement [set(statements)];
+                is #dedent;
+            }
+            "##,
+        )
+        .unwrap();
+        let kinds = |text: &str| {
+            let tokens = parser.lexer.lex_utf8(text).unwrap();
+            let kinds = tokens.iter().filter_map(|token| match &token.kind {
+                TokenKinds::Text => Some(text[token.index..token.index + token.len].to_string()),
+                TokenKinds::Control(ControlTokenKind::Indent) => Some(">".to_string()),
+                TokenKinds::Control(ControlTokenKind::Dedent) => Some("<".to_string()),
+                _ => None,
+            });
+            kinds.collect::<Vec<_>>().join(" ")
+        };
+
+        // blank lines and comments do not change the indentation
+        let text = "a:\n    b\n    c:\n\t\n        d\n    # comment\n  \n\ne\n";
+        assert_eq!(kinds(text), "a > b c > d < < e");
+        assert_eq!(kinds("a:\n\tb\n\t\tc"), "a > b > c < <");
+        // lines in other modes are not indented
+        assert_eq!(kinds("a(x,\n    y):\n  b"), "a x y > b <");
+
+        let err = parser.lexer.lex_utf8("a:\n    b\n  c").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Dedent does not match any outer indentation level"
+        );
+        assert_eq!(err.location, lexer::TextLocation::new(2, 0));
+        let err = parser.lexer.lex_utf8("a:\n\tb\n    c").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Inconsistent use of tabs and spaces in indentation"
+        );
+        assert!(parser.lexer.lex_utf8("a:\n  \tb\n\t  c").is_err());
+
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let lazy = parser
+            .lexer
+            .tokens(text)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(lazy, tokens);
+        let read = parser.lexer.read_tokens(text.as_bytes());
+        assert_eq!(read.collect::<Result<Vec<_>, _>>().unwrap(), tokens);
+        let edit = TextEdit::new(0..0, "x\n");
+        let expected = parser.lexer.lex_utf8(&edit.apply(text)).unwrap();
+        assert_eq!(parser.lexer.relex(&tokens, text, &edit).unwrap(), expected);
+
+        // comments are tokens for the parser
+        let text = "a:\n    b\n    c:\n\n        d\ne\n";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let result = parser.parse(&tokens, text).unwrap();
+        let statements = result.entry.get_list("statements");
+        assert_eq!(statements.len(), 2);
+        let body = statements[0].try_get_node("body").as_ref().unwrap();
+        assert_eq!(body.get_list("statements").len(), 2);
+
+        let printed = format::print(&parser);
+        assert!(printed.contains("indentation;\n"));
+        assert!(printed.contains("is #indent;"));
+        let loaded = format::load(&printed).unwrap();
+        assert!(loaded.lexer.indentation());
+        assert!(dump::load(&dump::dump(&parser).unwrap())
+            .unwrap()
+            .lexer
+            .indentation());
+        assert!(codegen::generate(&parser).contains("lexer.set_indentation(true);"));
+    }
+
+    #[test]
+    fn keyword_tokens() {
+        use grammar::validator::ValidationWarnings;
+
+        let mut parser = format::load(
+            r#"
+            tokens "(" ")" "," "*";
+            pattern ident "[a-z_]+";
+            keywords "fn" "Function";
+            keywords ignore_case "select" "from";
+
+            node entry {
+                var columns: list;
+                var table: node;
+
+                is "select";
+                while %ident [set(columns)] {
+                    maybe ",";
+                }
+                is "from";
+                is %ident [set(table)];
+            }
+            "#,
+        )
+        .unwrap();
+        let kinds = |parser: &Parser, text: &str| {
+            let tokens = parser.lexer.lex_utf8(text).unwrap();
+            let kinds = tokens.into_iter().filter_map(|token| match token.kind {
+                TokenKinds::Token(token) => Some(token),
+                TokenKinds::Complex(name) => Some(format!("%{}", name)),
+                TokenKinds::Text => Some(format!("#{}", &text[token.index..][..token.len])),
+                _ => None,
+            });
+            kinds.collect::<Vec<_>>().join(" ")
+        };
+
+        // keywords do not split words and win over patterns of the same length
+        assert_eq!(
+            kinds(&parser, "Functiond Function(fn)"),
+            "#Functiond Function ( fn )"
+        );
+        assert_eq!(kinds(&parser, "fn fnord fn_ fn"), "fn %ident %ident fn");
+        assert_eq!(
+            kinds(&parser, "SELECT * From selected"),
+            "select * from %ident"
+        );
+        assert_eq!(kinds(&parser, "Fn FN"), "#Fn #FN");
+        // text before a keyword ends at a character that is not a part of a word
+        let mut plain = Parser::new();
+        plain.lexer.add_token(".".to_string());
+        plain.lexer.add_keyword("fn", false);
+        assert_eq!(kinds(&plain, "fn$ a.fn"), "fn #$ #a . fn");
+        assert_eq!(kinds(&plain, "$fn (fn)"), "#$ fn #( fn #)");
+        assert_eq!(kinds(&plain, "x_fn $fnord"), "#x_fn #$fnord");
+        let text = "fn fnord\nSelect fn";
+        let read = parser.lexer.read_tokens(text.as_bytes());
+        let read = read.collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(read, parser.lexer.lex_utf8(text).unwrap());
+
+        let text = "Select a, b FROM c";
+        let result = parser
+            .parse(&parser.lexer.lex_utf8(text).unwrap(), text)
+            .unwrap();
+        assert_eq!(result.entry.get_list("columns").len(), 2);
+
+        let printed = format::print(&parser);
+        assert!(printed.contains("keywords \"fn\" \"Function\";\n"));
+        assert!(printed.contains("keywords ignore_case \"select\" \"from\";\n"));
+        assert_eq!(
+            format::load(&printed).unwrap().lexer.keywords(),
+            parser.lexer.keywords()
+        );
+        let loaded = dump::load(&dump::dump(&parser).unwrap()).unwrap();
+        assert_eq!(loaded.lexer.keywords(), parser.lexer.keywords());
+        assert!(codegen::generate(&parser).contains("lexer.add_keyword(\"select\", true);"));
+
+        // plain tokens that match the start of a keyword split the words again
+        let overlaps = |parser: &Parser| {
+            let result = parser.grammar.validate(&parser.lexer);
+            let overlaps = result
+                .warnings
+                .iter()
+                .filter_map(|warning| match &warning.kind {
+                    ValidationWarnings::KeywordOverlap(keyword, token) => {
+                        Some(format!("{} {}", keyword, token))
+                    }
+                    _ => None,
+                });
+            overlaps.collect::<Vec<_>>()
+        };
+        assert!(overlaps(&parser).is_empty());
+        parser.lexer.add_token("SEL".to_string());
+        parser.lexer.add_token("Fun".to_string());
+        parser.lexer.add_token("FUN".to_string());
+        assert_eq!(overlaps(&parser), ["Function Fun", "select SEL"]);
+        assert_eq!(kinds(&parser, "Functiond"), "Fun %ident");
+    }
+
+    #[test]
+    fn preprocessor_registry() {
+        use lexer::{Preprocessor, PreprocessorError, Preprocessors, Token};
+
+        fn no_whitespace(_: &str, tokens: Vec<Token>) -> Result<Vec<Token>, PreprocessorError> {
+            Ok(tokens
+                .into_iter()
+                .filter(|tok| !tok.kind.is_whitespace())
+                .collect())
+        }
+        fn numbers(text: &str, mut tokens: Vec<Token>) -> Result<Vec<Token>, PreprocessorError> {
+            for token in &mut tokens {
+                let word = &text[token.index..token.index + token.len];
+                if token.kind == TokenKinds::Text && word.parse::<i64>().is_ok() {
+                    token.kind = TokenKinds::Complex("number".to_string());
+                }
+            }
+            Ok(tokens)
+        }
+
+        let mut parser = format::load(r#"tokens "+" ";";"#).unwrap();
+        parser
+            .lexer
+            .add_preprocessor("no_whitespace", Preprocessor::from_fn(no_whitespace));
+        parser
+            .lexer
+            .add_preprocessor("numbers", Preprocessor::from_fn(numbers));
+        let text = "a + 12;\n";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        assert_eq!(tokens.len(), 5);
+        assert_eq!(tokens[2].kind, TokenKinds::Complex("number".to_string()));
+
+        let dumped = dump::dump(&parser).unwrap();
+        let mut preprocessors = Preprocessors::new();
+        preprocessors.add("no_whitespace", Preprocessor::from_fn(no_whitespace));
+        assert_eq!(
+            dump::load_with(&dumped, &preprocessors).unwrap_err(),
+            dump::DumpError::MissingPreprocessor("numbers".to_string())
+        );
+        assert!(dump::load(&dumped).is_err());
+        preprocessors.add("numbers", Preprocessor::from_fn(numbers));
+        let loaded = dump::load_with(&dumped, &preprocessors).unwrap();
+        assert_eq!(
+            loaded.lexer.preprocessor_names(),
+            ["no_whitespace", "numbers"]
+        );
+        assert_eq!(loaded.lexer.lex_utf8(text).unwrap(), tokens);
+
+        // a lexer that was not loaded with the registry does not lex without them
+        let json = serde_json::to_string(&parser).unwrap();
+        let unbound: Parser = serde_json::from_str(&json).unwrap();
+        let err = unbound.lexer.lex_utf8(text).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Preprocessors are not bound: no_whitespace, numbers"
+        );
+
+        // unnamed preprocessors are not saved
+        let mut parser = format::load(r#"tokens "+" ";";"#).unwrap();
+        parser
+            .lexer
+            .preprocessors
+            .push(Preprocessor::from_fn(no_whitespace));
+        let loaded = dump::load(&dump::dump(&parser).unwrap()).unwrap();
+        assert!(loaded.lexer.preprocessors.is_empty());
+        assert_eq!(loaded.lexer.lex_utf8(text).unwrap().len(), 8);
+    }
+
+    #[test]
+    fn token_stream() {
+        use lexer::{Preprocessor, Preprocessors, Token, TokenStream};
+
+        // configuration is captured by the closure
+        let suffixes = "fu".to_string();
+        let numbers = Preprocessor::new(move |tokens| {
+            while let Some(token) = tokens.peek() {
+                if token.kind.is_whitespace() {
+                    tokens.remove();
+                    continue;
+                }
+                let word = tokens.slice(token);
+                if word == "!" {
+                    return Err(tokens.error("Unexpected '!'"));
+                }
+                if token.kind != TokenKinds::Text || word.parse::<u64>().is_err() {
+                    tokens.advance();
+                    continue;
+                }
+                // looking past the last token is not a panic
+                let dot = tokens.lookahead(1).map(|tok| tok.kind.clone());
+                let fraction = tokens.lookahead(2).map(|tok| tokens.slice(tok));
+                match (dot, fraction) {
+                    (Some(TokenKinds::Token(dot)), Some(fraction))
+                        if dot == "." && fraction.ends_with(|c| suffixes.contains(c)) =>
+                    {
+                        tokens.merge(3, TokenKinds::Complex("float".to_string()));
+                    }
+                    _ => tokens.replace_kind(TokenKinds::Complex("int".to_string())),
+                }
+                tokens.advance();
+            }
+            Ok(())
+        });
+
+        let mut parser = format::load(r#"tokens "." "=" "!";"#).unwrap();
+        parser.lexer.preprocessors.push(numbers.clone());
+        let text = "x = 1.5f 2";
+        let tokens = parser.lexer.lex_utf8(text).unwrap();
+        let kinds = tokens
+            .iter()
+            .map(|tok| tok.kind.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                TokenKinds::Text,
+                TokenKinds::Token("=".to_string()),
+                TokenKinds::Complex("float".to_string()),
+                TokenKinds::Complex("int".to_string()),
+                TokenKinds::Control(lexer::ControlTokenKind::Eof),
+            ]
+        );
+        assert_eq!((tokens[2].index, tokens[2].len), (4, 4));
+        assert_eq!(parser.lexer.stringify(&tokens[2], text), "1.5f");
+        let err = parser.lexer.lex_utf8("x !").unwrap_err();
+        assert_eq!(err.message, "Unexpected '!'");
+        assert_eq!(err.location, lexer::TextLocation::new(0, 2));
+
+        // splice replaces tokens and inserts with a count of 0
+        let tokens = parser.lexer.lex_utf8("a = b").unwrap();
+        let mut stream = TokenStream::new("a = b", tokens.clone());
+        stream.splice(1, [tokens[2].clone()]);
+        assert_eq!(stream.passed(), [tokens[2].clone()]);
+        assert_eq!(stream.peek(), Some(&tokens[1]));
+        stream.splice(0, [tokens[0].clone()]);
+        assert_eq!(stream.passed().len(), 2);
+        assert_eq!(stream.finish().len(), tokens.len() + 1);
+
+        // merging tokens that are out of order keeps the start of the first one
+        let mut stream = TokenStream::new("a = b", vec![tokens[2].clone(), tokens[0].clone()]);
+        stream.merge(2, TokenKinds::Text);
+        let merged = stream.peek().unwrap();
+        assert_eq!((merged.index, merged.len), (4, 1));
+        let mut stream = TokenStream::new("a = b", tokens.clone());
+        stream.merge(3, TokenKinds::Text);
+        assert_eq!(stream.slice(stream.peek().unwrap()), "a = b");
+
+        // closures can be registered by name like plain functions
+        let mut preprocessors = Preprocessors::new();
+        preprocessors.add("numbers", numbers);
+        preprocessors.add(
+            "no_eof",
+            Preprocessor::from_fn(|_, mut tokens: Vec<Token>| {
+                tokens.pop();
+                Ok(tokens)
+            }),
+        );
+        let mut parser = format::load(r#"tokens "." "=" "!";"#).unwrap();
+        parser
+            .lexer
+            .add_preprocessor("numbers", preprocessors.get("numbers").unwrap());
+        parser
+            .lexer
+            .add_preprocessor("no_eof", preprocessors.get("no_eof").unwrap());
+        let loaded = dump::load_with(&dump::dump(&parser).unwrap(), &preprocessors).unwrap();
+        let tokens = loaded.lexer.lex_utf8("1.5u").unwrap();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKinds::Complex("float".to_string()));
+    }
+
+    #[test]
+    fn dialect_templates() {
+        use builder::*;
+        use dialect::{Conflicts, Dialect};
+        use grammar::TemplateError;
+
+        let base = format::load(
+            r#"
+            tokens ",";
+
+            template list<item, sep> {
+                var items: list;
+
+                is item [set(items)];
+                while sep {
+                    is item [set(items)];
+                }
+            }
+
+            node entry {
+                var items: node;
+
+                is list<#text, ","> [set(items)];
+            }
+            "#,
+        )
+        .unwrap();
+
+        // the dialect uses an instance that the base grammar does not have
+        let mut semicolons = Dialect::new("semicolons");
+        semicolons.add_tokens(&[";".to_string()]);
+        semicolons.override_node(
+            node("entry")
+                .is(template("list", vec![text(), tok(";")]))
+                .set("items")
+                .build()
+                .unwrap(),
+        );
+        let parser = dialect::apply(&base, &[semicolons]).unwrap();
+        assert!(parser.grammar.validate(&parser.lexer).pass());
+        let txt = "a; b; c";
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        let result = parser.parse(&tokens, txt).unwrap();
+        let items = result.entry.try_get_node("items").as_ref().unwrap();
+        assert_eq!(items.name(), "list<#text, \";\">");
+        assert_eq!(items.get_list("items").len(), 3);
+
+        let mut broken = Dialect::new("broken");
+        let other = node("other").is(template("missing", vec![text()]));
+        broken.add_node(other.build().unwrap());
+        let conflicts = dialect::apply(&base, &[broken]).unwrap_err();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].dialect, "broken");
+        assert_eq!(
+            conflicts[0].kind,
+            Conflicts::Template(TemplateError::NotFound("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn dialects() {
+        use dialect::{Conflicts, Dialect};
+        use grammar::{MatchToken, OneOf, Rule};
+
+        let strict = format::load(
+            r#"
+            tokens "+" "-";
+            enum operators { "+", "-" }
+
+            node entry {
+                var values: list;
+
+                is value [set(values)];
+                while @operators {
+                    is value [set(values)];
+                }
+            }
+
+            node value {
+                one_of {
+                    #text;
+                }
+            }
+            "#,
+        )
+        .unwrap();
+
+        let token = |t: &str| MatchToken::Token(TokenKinds::Token(t.to_string()));
+        let mut extended = Dialect::new("extended");
+        extended.add_tokens(&["*".to_string(), "(".to_string(), ")".to_string()]);
+        extended.extend_enumerator("operators", vec![token("*")]);
+        extended.extend_one_of(
+            "value",
+            0,
+            vec![OneOf {
+                token: token("("),
+                rules: vec![
+                    Rule::Is {
+                        token: MatchToken::Node("entry".to_string()),
+                        rules: vec![],
+                        parameters: vec![],
+                    },
+                    Rule::Is {
+                        token: token(")"),
+                        rules: vec![],
+                        parameters: vec![],
+                    },
+                ],
+                parameters: vec![],
+            }],
+        );
+        let parser = dialect::apply(&strict, &[extended.clone()]).unwrap();
+        assert!(parser.grammar.validate(&parser.lexer).pass());
+
+        let txt = "a * (b + c)";
+        let tokens = strict.lexer.lex_utf8(txt).unwrap();
+        assert!(strict.parse(&tokens, txt).is_err());
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+        let result = parser.parse(&tokens, txt).unwrap();
+        assert_eq!(result.entry.get_list("values").len(), 2);
+
+        let mut broken = Dialect::new("broken");
+        broken.override_node(strict.grammar.nodes["value"].clone());
+        broken.override_node(grammar::Node {
+            name: "missing".to_string(),
+            rules: vec![],
+            variables: Map::new(),
+        });
+        broken.extend_enumerator("operators", vec![token("+")]);
+        let conflicts = dialect::apply(&strict, &[extended, broken]).unwrap_err();
+        let kinds = conflicts.into_iter().map(|c| c.kind).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                Conflicts::Overridden {
+                    node: "value".to_string(),
+                    by: "extended".to_string()
+                },
+                Conflicts::NodeNotFound("missing".to_string()),
+                Conflicts::Duplicate {
+                    name: "operators".to_string(),
+                    token: token("+")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn vec_char_eq() {
+        let a = vec!['a', 'b', 'c'];
+        let b = vec!['a', 'b', 'c'];
+        let c = vec!['a', 'b', 'd'];
+        assert_eq!(a, b);
+        assert_eq!(true, a == b);
+        assert_eq!(false, a == c);
+
+        let slice_a = &a[0..2];
+        let slice_b = &b[0..2];
+        let slice_c = &c[1..3];
+        assert_eq!(slice_a, slice_b);
+        assert_eq!(true, slice_a == slice_b);
+        assert_eq!(false, slice_a == slice_c);
+    }
+
+    /// Fields are ordered according to the order of the lines in the meta file
+    struct Meta {
+        lines: usize,
+        line_length: usize,
+    }
+
+    fn read_dotmeta() -> Meta {
+        use std::fs;
+        let meta = fs::read_to_string("workload.meta").unwrap();
+        let mut lns = meta.lines();
+        let lines = lns.next().unwrap().parse().unwrap();
+        let line_length = lns.next().unwrap().parse().unwrap();
+        Meta { lines, line_length }
+    }
+
+    #[test]
+    fn workload_file() {
+        let meta = read_dotmeta();
+        let mut parser = Parser::new();
+        // let txt = include_str!("../workload.txt"); // The size of the file is 100MB which would make it impractical to include it in the tests
+        use std::fs;
+        let txt = fs::read_to_string("workload.txt").unwrap();
+        parser.lexer.add_token("\"".to_string());
+
+        let lex_start = std::time::Instant::now();
+        let tokens = parser.lexer.lex_utf8(&txt).unwrap();
+
+        let variables = Map::new();
+        parser.grammar.add_node(grammar::Node {
+            name: "string".to_string(),
+            rules: vec![
+                // detect the start
+                grammar::Rule::Is {
+                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
+                    rules: vec![],
+                    parameters: vec![Parameters::NodeStart, Parameters::HardError(true)],
+                },
+                grammar::Rule::Until {
+                    token: grammar::MatchToken::Token(TokenKinds::Token("\"".to_string())),
+                    rules: vec![],
+                    parameters: vec![Parameters::NodeEnd],
+                },
+            ],
+            variables,
+        });
+
+        let mut variables = Map::new();
+        variables.insert("strings".to_string(), VariableKind::NodeList);
+        variables.insert("count".to_string(), VariableKind::Number);
+        variables.insert("zero".to_string(), VariableKind::Number);
+
+        parser.grammar.add_node(grammar::Node {
+            name: "entry".to_string(),
+            rules: vec![
+                grammar::Rule::While {
+                    token: grammar::MatchToken::Node("string".to_string()),
+                    rules: vec![],
+                    parameters: vec![
+                        Parameters::Set("strings".to_string()),
+                        Parameters::Increment("count".to_string()),
+                    ],
+                },
+                grammar::Rule::Command {
+                    command: grammar::Commands::Compare {
+                        left: "count".to_string(),
+                        right: "zero".to_string(), // zero is not defined, so it will be 0
+                        comparison: grammar::Comparison::Equal,
+                        rules: vec![grammar::Rule::Command {
+                            command: grammar::Commands::Error {
+                                message: "No strings found".to_string(),
+                            },
+                        }],
+                    },
+                },
+            ],
+            variables,
+        });
+
+        let result = parser.parse(&tokens, &txt).unwrap();
+        let strings = result.entry.get_list("strings");
+        // verify the result
+        assert_eq!(strings.len(), meta.lines);
+        for s in strings {
+            assert_eq!(result.stringify_node(s, &txt).len(), meta.line_length);
+        }
+    }
+
+    #[test]
+    fn load_json() {
+        use std::io::Read;
+
+        let mut file = std::fs::File::open("KWLet.json").unwrap();
+        let mut parser = String::new();
+        file.read_to_string(&mut parser).unwrap();
+
+        let parser: Parser = serde_json::from_str(&parser).unwrap();
+
+        let txt = "let a: int = 500 * 9;";
+
+        let tokens = parser.lexer.lex_utf8(txt).unwrap();
+
+        let result = parser.parse(&tokens, txt).unwrap();
+
+        assert_eq!(
+            result.stringify_node(result.entry.try_get_node("value").as_ref().unwrap(), txt),
+            " 500 * 9"
+        );
+    }
+}
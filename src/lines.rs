@@ -0,0 +1,206 @@
+//! Lines and columns of byte offsets
+//!
+//! Tokens know their byte `index` and a `TextLocation` with 1-based lines and columns
+//! counted in chars. Editors want other columns, LSP clients count UTF-16 code units
+//! by default. `LineIndex` converts byte offsets to zero-based positions and back:
+//!
+//! ```ignore
+//! let lines = LineIndex::new(text).with_tab_width(4);
+//! let position = lines.position(token.index, Encoding::Utf16);
+//! assert_eq!(lines.offset(position, Encoding::Utf16), Some(token.index));
+//! ```
+use serde::{Deserialize, Serialize};
+
+use crate::lexer::TextLocation;
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+    } else {
+        extern crate alloc;
+        use alloc::vec::*;
+        use alloc::vec;
+    }
+}
+
+/// What the columns count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Encoding {
+    /// Bytes of UTF-8
+    Utf8,
+    /// Unicode scalar values, like `TextLocation`
+    Char,
+    /// UTF-16 code units, the default of LSP
+    Utf16,
+}
+
+/// Zero-based line and column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn new(line: usize, column: usize) -> Position {
+        Position { line, column }
+    }
+}
+
+/// Starts of the lines of a text
+///
+/// Only the lines and the characters that are not one column wide (non-ascii and tabs)
+/// are stored, the text itself is not needed after the index is built.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    /// Byte offset of every line, the first one is 0
+    starts: Vec<usize>,
+    /// Non-ascii characters and tabs with their byte offset
+    wide: Vec<(usize, char)>,
+    len: usize,
+    tab_width: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> LineIndex {
+        let mut starts = vec![0];
+        let mut wide = Vec::new();
+        for (idx, c) in text.char_indices() {
+            match c {
+                '\n' => starts.push(idx + 1),
+                '\t' => wide.push((idx, c)),
+                c if !c.is_ascii() => wide.push((idx, c)),
+                _ => (),
+            }
+        }
+        LineIndex {
+            starts,
+            wide,
+            len: text.len(),
+            tab_width: 1,
+        }
+    }
+
+    /// Tabs move the column to the next multiple of the width, the default is 1
+    ///
+    /// Only `position` and `offset` use it, `location` always counts a tab as one column
+    pub fn with_tab_width(mut self, width: usize) -> LineIndex {
+        self.tab_width = width.max(1);
+        self
+    }
+
+    /// Number of lines, a text that ends with a new line has an empty last line
+    pub fn lines(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Byte offset of the start of the line
+    pub fn line_start(&self, line: usize) -> Option<usize> {
+        self.starts.get(line).copied()
+    }
+
+    /// Position of a byte offset
+    ///
+    /// Offsets inside of a character are moved to its start and offsets past the end
+    /// of the text to the end.
+    pub fn position(&self, offset: usize, encoding: Encoding) -> Position {
+        self.find_position(offset, encoding, self.tab_width)
+    }
+
+    /// Byte offset of a position
+    ///
+    /// Columns past the end of the line are moved to the end of the line (before the
+    /// new line) and columns inside of a character to its start. Returns `None` if the
+    /// line does not exist.
+    pub fn offset(&self, position: Position, encoding: Encoding) -> Option<usize> {
+        self.find_offset(position, encoding, self.tab_width)
+    }
+
+    /// Location of a byte offset the way the lexer counts it
+    pub fn location(&self, offset: usize) -> TextLocation {
+        let position = self.find_position(offset, Encoding::Char, 1);
+        TextLocation::new(position.line, position.column)
+    }
+
+    /// Byte offset of a location from the lexer
+    pub fn location_offset(&self, location: &TextLocation) -> Option<usize> {
+        let position = Position::new(
+            location.line.checked_sub(1)?,
+            location.column.saturating_sub(1),
+        );
+        self.find_offset(position, Encoding::Char, 1)
+    }
+
+    fn find_position(&self, offset: usize, encoding: Encoding, tab_width: usize) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.starts.partition_point(|&start| start <= offset) - 1;
+        let mut column = 0;
+        let mut pos = self.starts[line];
+        for &(idx, c) in self.wide_chars(line) {
+            if idx >= offset {
+                break;
+            }
+            column += idx - pos;
+            pos = idx + c.len_utf8();
+            if pos > offset {
+                // inside of the character
+                return Position::new(line, column);
+            }
+            column += width(c, column, encoding, tab_width);
+        }
+        Position::new(line, column + offset - pos)
+    }
+
+    fn find_offset(
+        &self,
+        position: Position,
+        encoding: Encoding,
+        tab_width: usize,
+    ) -> Option<usize> {
+        let start = *self.starts.get(position.line)?;
+        let end = match self.starts.get(position.line + 1) {
+            Some(next) => next - 1,
+            None => self.len,
+        };
+        let mut column = 0;
+        let mut pos = start;
+        for &(idx, c) in self.wide_chars(position.line) {
+            if position.column <= column + idx - pos {
+                break;
+            }
+            column += idx - pos;
+            let width = width(c, column, encoding, tab_width);
+            if position.column < column + width {
+                return Some(idx);
+            }
+            column += width;
+            pos = idx + c.len_utf8();
+        }
+        Some((pos + position.column - column).min(end))
+    }
+
+    /// Wide characters of a line
+    fn wide_chars(&self, line: usize) -> &[(usize, char)] {
+        let start = self.starts[line];
+        let first = self.wide.partition_point(|&(idx, _)| idx < start);
+        let last = match self.starts.get(line + 1) {
+            Some(&next) => self.wide.partition_point(|&(idx, _)| idx < next),
+            None => self.wide.len(),
+        };
+        &self.wide[first..last]
+    }
+}
+
+/// Columns of a character at the column
+fn width(c: char, column: usize, encoding: Encoding, tab_width: usize) -> usize {
+    if c == '\t' {
+        return tab_width - column % tab_width;
+    }
+    match encoding {
+        Encoding::Utf8 => c.len_utf8(),
+        Encoding::Char => 1,
+        Encoding::Utf16 => c.len_utf16(),
+    }
+}
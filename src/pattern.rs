@@ -0,0 +1,572 @@
+//! Patterns for the lexer
+//!
+//! A small regex dialect used by `Lexer::add_pattern` to produce `TokenKinds::Complex`
+//! tokens directly during lexing:
+//!
+//! - `a` - the character itself, `\` escapes the special characters
+//! - `.` - any character except a new line
+//! - `[a-z_]`, `[^"]` - character classes
+//! - `\d`, `\w`, `\s` (and `\D`, `\W`, `\S`) - digits, word characters and whitespace
+//! - `\n`, `\t`, `\r` - control characters
+//! - `ab|cd`, `(ab)` - alternatives and groups
+//! - `*`, `+`, `?`, `{n}`, `{n,}`, `{n,m}` - repetitions
+//!
+//! Patterns always match at the start of the text and return the longest match,
+//! so `a|ab` matches the whole `ab`. Matching is linear in the length of the text.
+//!
+//! ```ignore
+//! lexer.add_pattern("int", r"\d+")?;
+//! lexer.add_pattern("float", r"\d+\.\d+")?;
+//! lexer.add_pattern("ident", r"[a-zA-Z_]\w*")?;
+//! ```
+//!
+//! Patterns are serialized as their source and compiled again when they are loaded.
+use serde::{Deserialize, Serialize};
+
+use core::fmt;
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::vec;
+        use alloc::boxed::Box;
+    }
+}
+
+/// Counted repetitions are copied in the program, this keeps `a{1000000}` from eating the memory
+const MAX_REPEAT: u32 = 1000;
+
+/// Nested repetitions multiply, so the whole program has a limit as well
+const MAX_PROGRAM: u64 = 100_000;
+
+/// Compiled pattern
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern {
+    source: String,
+    program: Vec<Inst>,
+}
+
+impl Pattern {
+    /// Compiles a pattern
+    pub fn new(source: &str) -> Result<Pattern, PatternError> {
+        let mut reader = Reader {
+            chars: source.chars().collect(),
+            idx: 0,
+        };
+        let ast = reader.alternatives()?;
+        if reader.idx < reader.chars.len() {
+            // the only thing that can stop the top level is a closing parenthesis
+            return Err(reader.error(PatternErrors::Unexpected(')')));
+        }
+        if size(&ast) >= MAX_PROGRAM {
+            return Err(PatternError {
+                kind: PatternErrors::TooLarge,
+                position: 0,
+            });
+        }
+        let mut program = Vec::new();
+        compile(&ast, &mut program);
+        program.push(Inst::Match);
+        let pattern = Pattern {
+            source: source.to_string(),
+            program,
+        };
+        if pattern.longest("") == Some(0) {
+            return Err(PatternError {
+                kind: PatternErrors::MatchesEmpty,
+                position: 0,
+            });
+        }
+        Ok(pattern)
+    }
+
+    /// Source of the pattern
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether a match can contain a new line
+    pub fn matches_new_line(&self) -> bool {
+        self.program.iter().any(|inst| match inst {
+            Inst::Char(c) => *c == '\n',
+            Inst::Class(class) => class.matches('\n'),
+            _ => false,
+        })
+    }
+
+    /// Returns the length in bytes of the longest match at the start of the text
+    pub fn longest(&self, text: &str) -> Option<usize> {
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        // generation in which the instruction was added, avoids clearing a set every step
+        let mut added = vec![usize::MAX; self.program.len()];
+        let mut generation = 0;
+        let mut longest = None;
+
+        self.add(&mut current, &mut added, generation, 0);
+        for (idx, c) in text.char_indices() {
+            if current.is_empty() {
+                break;
+            }
+            generation += 1;
+            next.clear();
+            for &pc in &current {
+                match &self.program[pc] {
+                    Inst::Match => longest = Some(idx),
+                    Inst::Char(expected) if *expected == c => {
+                        self.add(&mut next, &mut added, generation, pc + 1)
+                    }
+                    Inst::Any if c != '\n' => self.add(&mut next, &mut added, generation, pc + 1),
+                    Inst::Class(class) if class.matches(c) => {
+                        self.add(&mut next, &mut added, generation, pc + 1)
+                    }
+                    _ => (),
+                }
+            }
+            core::mem::swap(&mut current, &mut next);
+        }
+        if current
+            .iter()
+            .any(|&pc| matches!(self.program[pc], Inst::Match))
+        {
+            longest = Some(text.len());
+        }
+        longest
+    }
+
+    /// Adds an instruction and everything reachable from it without reading a character
+    fn add(&self, list: &mut Vec<usize>, added: &mut [usize], generation: usize, pc: usize) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if added[pc] == generation {
+                continue;
+            }
+            added[pc] = generation;
+            match self.program[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                _ => list.push(pc),
+            }
+        }
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Debug for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Pattern({:?})", self.source)
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = PatternError;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Pattern::new(&source)
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.source
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub struct PatternError {
+    pub kind: PatternErrors,
+    /// Index of the character in the pattern
+    pub position: usize,
+}
+
+#[derive(Clone, PartialEq)]
+pub enum PatternErrors {
+    /// The pattern ended in the middle of a group, class or escape
+    UnexpectedEnd,
+    /// Character that can not be used at this place
+    Unexpected(char),
+    /// Invalid `{n,m}` repetition
+    InvalidRepetition,
+    /// Patterns that match nothing would produce empty tokens
+    MatchesEmpty,
+    /// The repetitions expand to too many instructions
+    TooLarge,
+}
+
+impl fmt::Debug for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PatternErrors::UnexpectedEnd => write!(f, "Unexpected end of pattern"),
+            PatternErrors::Unexpected(c) => {
+                write!(f, "Unexpected character {:?} at {}", c, self.position)
+            }
+            PatternErrors::InvalidRepetition => {
+                write!(f, "Invalid repetition at {}", self.position)
+            }
+            PatternErrors::MatchesEmpty => write!(f, "Pattern matches an empty string"),
+            PatternErrors::TooLarge => write!(f, "Pattern is too large"),
+        }
+    }
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+#[derive(Clone)]
+enum Inst {
+    Char(char),
+    Any,
+    Class(Class),
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Clone)]
+struct Class {
+    negated: bool,
+    items: Vec<ClassItem>,
+}
+
+impl Class {
+    fn matches(&self, c: char) -> bool {
+        self.items.iter().any(|item| item.matches(c)) != self.negated
+    }
+}
+
+#[derive(Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ClassItem::Range(start, end) => *start <= c && c <= *end,
+            ClassItem::Digit(is) => c.is_ascii_digit() == *is,
+            ClassItem::Word(is) => (c.is_alphanumeric() || c == '_') == *is,
+            ClassItem::Space(is) => c.is_whitespace() == *is,
+        }
+    }
+}
+
+enum Ast {
+    Empty,
+    Char(char),
+    Any,
+    Class(Class),
+    Concat(Vec<Ast>),
+    Alternatives(Vec<Ast>),
+    Repeat {
+        ast: Box<Ast>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+struct Reader {
+    chars: Vec<char>,
+    idx: usize,
+}
+
+impl Reader {
+    fn error(&self, kind: PatternErrors) -> PatternError {
+        PatternError {
+            kind,
+            position: self.idx,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.idx).copied()
+    }
+
+    fn next(&mut self) -> Result<char, PatternError> {
+        match self.peek() {
+            Some(c) => {
+                self.idx += 1;
+                Ok(c)
+            }
+            None => Err(self.error(PatternErrors::UnexpectedEnd)),
+        }
+    }
+
+    fn alternatives(&mut self) -> Result<Ast, PatternError> {
+        let mut options = vec![self.concat()?];
+        while self.peek() == Some('|') {
+            self.idx += 1;
+            options.push(self.concat()?);
+        }
+        match options.len() {
+            1 => Ok(options.pop().unwrap()),
+            _ => Ok(Ast::Alternatives(options)),
+        }
+    }
+
+    fn concat(&mut self) -> Result<Ast, PatternError> {
+        let mut items = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            items.push(self.repetitions(atom)?);
+        }
+        match items.len() {
+            0 => Ok(Ast::Empty),
+            1 => Ok(items.pop().unwrap()),
+            _ => Ok(Ast::Concat(items)),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Ast, PatternError> {
+        let c = self.next()?;
+        match c {
+            '(' => {
+                let ast = self.alternatives()?;
+                match self.next()? {
+                    ')' => Ok(ast),
+                    c => Err(self.error(PatternErrors::Unexpected(c))),
+                }
+            }
+            '[' => self.class(),
+            '.' => Ok(Ast::Any),
+            '\\' => match self.escape()? {
+                Escaped::Char(c) => Ok(Ast::Char(c)),
+                Escaped::Item(item) => Ok(Ast::Class(Class {
+                    negated: false,
+                    items: vec![item],
+                })),
+            },
+            '*' | '+' | '?' | '{' | ']' | '}' => {
+                self.idx -= 1;
+                Err(self.error(PatternErrors::Unexpected(c)))
+            }
+            c => Ok(Ast::Char(c)),
+        }
+    }
+
+    fn escape(&mut self) -> Result<Escaped, PatternError> {
+        Ok(match self.next()? {
+            'd' => Escaped::Item(ClassItem::Digit(true)),
+            'D' => Escaped::Item(ClassItem::Digit(false)),
+            'w' => Escaped::Item(ClassItem::Word(true)),
+            'W' => Escaped::Item(ClassItem::Word(false)),
+            's' => Escaped::Item(ClassItem::Space(true)),
+            'S' => Escaped::Item(ClassItem::Space(false)),
+            'n' => Escaped::Char('\n'),
+            't' => Escaped::Char('\t'),
+            'r' => Escaped::Char('\r'),
+            c => Escaped::Char(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<Ast, PatternError> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.idx += 1;
+        }
+        let mut items = Vec::new();
+        loop {
+            let c = self.next()?;
+            // `]` right after the opening bracket is a character
+            if c == ']' && !items.is_empty() {
+                break;
+            }
+            let start = match c {
+                '\\' => match self.escape()? {
+                    Escaped::Char(c) => c,
+                    Escaped::Item(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                },
+                c => c,
+            };
+            // `-` at the end of the class is a character
+            if self.peek() == Some('-') && self.chars.get(self.idx + 1) != Some(&']') {
+                self.idx += 1;
+                let end = match self.next()? {
+                    '\\' => match self.escape()? {
+                        Escaped::Char(c) => c,
+                        Escaped::Item(_) => return Err(self.error(PatternErrors::Unexpected('\\'))),
+                    },
+                    c => c,
+                };
+                if end < start {
+                    return Err(self.error(PatternErrors::Unexpected(end)));
+                }
+                items.push(ClassItem::Range(start, end));
+            } else {
+                items.push(ClassItem::Range(start, start));
+            }
+        }
+        Ok(Ast::Class(Class { negated, items }))
+    }
+
+    fn repetitions(&mut self, mut ast: Ast) -> Result<Ast, PatternError> {
+        while let Some(c) = self.peek() {
+            let (min, max) = match c {
+                '*' => (0, None),
+                '+' => (1, None),
+                '?' => (0, Some(1)),
+                '{' => {
+                    self.idx += 1;
+                    self.counts()?
+                }
+                _ => break,
+            };
+            if c != '{' {
+                self.idx += 1;
+            }
+            ast = Ast::Repeat {
+                ast: Box::new(ast),
+                min,
+                max,
+            };
+        }
+        Ok(ast)
+    }
+
+    /// Reads `n}`, `n,}` or `n,m}`
+    fn counts(&mut self) -> Result<(u32, Option<u32>), PatternError> {
+        let min = self.number()?;
+        let max = match self.next()? {
+            '}' => return Ok((min, Some(min))),
+            ',' if self.peek() == Some('}') => None,
+            ',' => Some(self.number()?),
+            c => return Err(self.error(PatternErrors::Unexpected(c))),
+        };
+        match self.next()? {
+            '}' => (),
+            c => return Err(self.error(PatternErrors::Unexpected(c))),
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error(PatternErrors::InvalidRepetition));
+        }
+        if max.unwrap_or(min) > MAX_REPEAT {
+            return Err(self.error(PatternErrors::InvalidRepetition));
+        }
+        Ok((min, max))
+    }
+
+    fn number(&mut self) -> Result<u32, PatternError> {
+        let start = self.idx;
+        let mut number: u32 = 0;
+        while let Some(digit) = self.peek().and_then(|c| c.to_digit(10)) {
+            number = number.saturating_mul(10).saturating_add(digit);
+            self.idx += 1;
+        }
+        if self.idx == start {
+            return Err(self.error(PatternErrors::InvalidRepetition));
+        }
+        Ok(number)
+    }
+}
+
+enum Escaped {
+    Char(char),
+    Item(ClassItem),
+}
+
+/// Number of instructions `compile` produces for the ast
+fn size(ast: &Ast) -> u64 {
+    match ast {
+        Ast::Empty => 0,
+        Ast::Char(_) | Ast::Any | Ast::Class(_) => 1,
+        Ast::Concat(items) => items.iter().map(size).fold(0, u64::saturating_add),
+        // a split and a jump for every option but the last one
+        Ast::Alternatives(options) => options
+            .iter()
+            .map(|option| size(option).saturating_add(2))
+            .fold(0, u64::saturating_add),
+        Ast::Repeat { ast, min, max } => {
+            let copies = match max {
+                // the loop is one more copy with a split and a jump
+                None => u64::from(*min) + 1,
+                Some(max) => u64::from(*max),
+            };
+            size(ast).saturating_add(2).saturating_mul(copies)
+        }
+    }
+}
+
+fn compile(ast: &Ast, program: &mut Vec<Inst>) {
+    match ast {
+        Ast::Empty => (),
+        Ast::Char(c) => program.push(Inst::Char(*c)),
+        Ast::Any => program.push(Inst::Any),
+        Ast::Class(class) => program.push(Inst::Class(class.clone())),
+        Ast::Concat(items) => {
+            for item in items {
+                compile(item, program);
+            }
+        }
+        Ast::Alternatives(options) => {
+            let mut jumps = Vec::new();
+            for (i, option) in options.iter().enumerate() {
+                if i + 1 == options.len() {
+                    compile(option, program);
+                    break;
+                }
+                let split = program.len();
+                program.push(Inst::Split(split + 1, 0));
+                compile(option, program);
+                jumps.push(program.len());
+                program.push(Inst::Jump(0));
+                program[split] = Inst::Split(split + 1, program.len());
+            }
+            let end = program.len();
+            for jump in jumps {
+                program[jump] = Inst::Jump(end);
+            }
+        }
+        Ast::Repeat { ast, min, max } => {
+            for _ in 0..*min {
+                compile(ast, program);
+            }
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(ast, program);
+                    program.push(Inst::Jump(split));
+                    program[split] = Inst::Split(split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile(ast, program);
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = Inst::Split(split + 1, end);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,540 @@
+//! Railroad diagrams
+//!
+//! Renders nodes as SVG syntax diagrams for documentation.
+//!
+//! - tokens and words are drawn as rounded boxes
+//! - node references are drawn as square boxes (links in the HTML page)
+//! - builtin and complex tokens (`TEXT`, `EOL`, ...) are drawn as dashed boxes
+//! - `maybe`, `while`, `loop`, `one_of` and enumerators become branches and loops
+//! - everything else (`goto`, `compare`, `back`, ...) is written as a comment on the line
+use crate::{
+    grammar::{instance_name, Commands, Grammar, MatchToken, OneOf, Parameters, Rule, Rules},
+    lexer::{ControlTokenKind, TokenKinds},
+};
+
+// Choose between std and alloc
+cfg_if::cfg_if! {
+    if #[cfg(feature = "std")] {
+        extern crate std;
+        use std::prelude::v1::*;
+    } else {
+        extern crate alloc;
+        use alloc::string::*;
+        use alloc::vec::*;
+        use alloc::vec;
+        use alloc::boxed::Box;
+        use alloc::format;
+    }
+}
+
+/// Radius of the arcs
+const ARC: i32 = 10;
+/// Vertical space between branches
+const GAP: i32 = 8;
+/// Horizontal space between items of a sequence
+const SPACE: i32 = 10;
+/// Width of one character of a box
+const CHAR: i32 = 8;
+/// Half of the height of a box
+const BOX: i32 = 11;
+/// Padding around the whole diagram
+const PADDING: i32 = 20;
+
+const STYLE: &str = "path { fill: none; stroke: #333; stroke-width: 2; } \
+rect { stroke: #333; stroke-width: 2; } \
+rect.token { fill: #ffd; } \
+rect.node { fill: #def; } \
+rect.special { fill: #eee; stroke-dasharray: 4 2; } \
+text { font: 14px monospace; text-anchor: middle; } \
+text.comment { font: italic 12px monospace; fill: #666; }";
+
+/// Renders a node as a standalone SVG diagram
+///
+/// Returns `None` if the node does not exist
+pub fn svg(grammar: &Grammar, name: &str) -> Option<String> {
+    let node = grammar.nodes.get(name)?;
+    let diagram = Builder::new(grammar).rules(&node.rules);
+    let mut out = String::new();
+    draw(&diagram, false, &mut out);
+    Some(out)
+}
+
+/// Renders every node of the grammar into a single HTML page
+///
+/// Node references are links to the diagram of the node
+pub fn html(grammar: &Grammar) -> String {
+    let mut names = grammar.nodes.keys().collect::<Vec<_>>();
+    names.sort();
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>Grammar</title>\n</head>\n<body>\n");
+    for name in names {
+        let diagram = Builder::new(grammar).rules(&grammar.nodes[name].rules);
+        out.push_str(&format!(
+            "<h2 id=\"{}\">{}</h2>\n",
+            escape(name),
+            escape(name)
+        ));
+        draw(&diagram, true, &mut out);
+        out.push('\n');
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+enum Diagram {
+    Token(String),
+    Node(String),
+    Special(String),
+    Comment(String),
+    Skip,
+    Sequence(Vec<Diagram>),
+    /// The first branch is on the main line
+    Choice(Vec<Diagram>),
+    /// Item with a line going back from its end to its start
+    Repeat(Box<Diagram>),
+}
+
+fn optional(diagram: Diagram) -> Diagram {
+    Diagram::Choice(vec![Diagram::Skip, diagram])
+}
+
+fn choice(branches: Vec<Diagram>) -> Diagram {
+    if branches.is_empty() {
+        return Diagram::Skip;
+    }
+    Diagram::Choice(branches)
+}
+
+fn sequence(mut items: Vec<Diagram>) -> Diagram {
+    match items.len() {
+        0 => Diagram::Skip,
+        1 => items.pop().unwrap(),
+        _ => Diagram::Sequence(items),
+    }
+}
+
+/// Width, height above the line and height below the line
+struct Size {
+    width: i32,
+    up: i32,
+    down: i32,
+}
+
+impl Diagram {
+    fn size(&self) -> Size {
+        match self {
+            Diagram::Token(text) | Diagram::Node(text) | Diagram::Special(text) => Size {
+                width: text.chars().count() as i32 * CHAR + 2 * SPACE,
+                up: BOX,
+                down: BOX,
+            },
+            Diagram::Comment(text) => Size {
+                width: text.chars().count() as i32 * (CHAR - 1) + SPACE,
+                up: BOX,
+                down: 0,
+            },
+            Diagram::Skip => Size {
+                width: 0,
+                up: 0,
+                down: 0,
+            },
+            Diagram::Sequence(items) => {
+                let mut size = Size {
+                    width: SPACE * (items.len() as i32 - 1).max(0),
+                    up: 0,
+                    down: 0,
+                };
+                for item in items {
+                    let item = item.size();
+                    size.width += item.width;
+                    size.up = size.up.max(item.up);
+                    size.down = size.down.max(item.down);
+                }
+                size
+            }
+            Diagram::Choice(branches) => {
+                let first = branches[0].size();
+                let mut width = first.width;
+                let mut down = first.down;
+                for offset in branch_offsets(branches).into_iter().skip(1) {
+                    width = width.max(offset.1.width);
+                    down = offset.0 + offset.1.down;
+                }
+                Size {
+                    width: width + 4 * ARC,
+                    up: first.up,
+                    down,
+                }
+            }
+            Diagram::Repeat(item) => {
+                let size = item.size();
+                Size {
+                    width: size.width + 2 * ARC,
+                    up: size.up,
+                    down: loop_offset(&size),
+                }
+            }
+        }
+    }
+
+    /// Draws the diagram with its entry point at x, y
+    fn draw(&self, x: i32, y: i32, links: bool, out: &mut String) {
+        match self {
+            Diagram::Token(text) => boxed(x, y, text, "token", ARC, None, out),
+            Diagram::Node(text) => {
+                let link = if links { Some(text.as_str()) } else { None };
+                boxed(x, y, text, "node", 0, link, out)
+            }
+            Diagram::Special(text) => boxed(x, y, text, "special", 0, None, out),
+            Diagram::Comment(text) => {
+                let width = self.size().width;
+                out.push_str(&format!("<path d=\"M{} {} h{}\"/>\n", x, y, width));
+                out.push_str(&format!(
+                    "<text class=\"comment\" x=\"{}\" y=\"{}\">{}</text>\n",
+                    x + width / 2,
+                    y - 4,
+                    escape(text)
+                ));
+            }
+            Diagram::Skip => (),
+            Diagram::Sequence(items) => {
+                let mut x = x;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(&format!("<path d=\"M{} {} h{}\"/>\n", x, y, SPACE));
+                        x += SPACE;
+                    }
+                    item.draw(x, y, links, out);
+                    x += item.size().width;
+                }
+            }
+            Diagram::Choice(branches) => {
+                let width = self.size().width;
+                let inner = width - 4 * ARC;
+                for (i, (offset, size)) in branch_offsets(branches).into_iter().enumerate() {
+                    let branch_y = y + offset;
+                    if i == 0 {
+                        out.push_str(&format!("<path d=\"M{} {} h{}\"/>\n", x, y, 2 * ARC));
+                    } else {
+                        out.push_str(&format!(
+                            "<path d=\"M{} {} a{r} {r} 0 0 1 {r} {r} v{} a{r} {r} 0 0 0 {r} {r}\"/>\n",
+                            x,
+                            y,
+                            offset - 2 * ARC,
+                            r = ARC
+                        ));
+                    }
+                    branches[i].draw(x + 2 * ARC, branch_y, links, out);
+                    let end = x + 2 * ARC + size.width;
+                    if i == 0 {
+                        out.push_str(&format!(
+                            "<path d=\"M{} {} h{}\"/>\n",
+                            end,
+                            y,
+                            inner - size.width + 2 * ARC
+                        ));
+                    } else {
+                        out.push_str(&format!(
+                            "<path d=\"M{} {} h{} a{r} {r} 0 0 0 {r} -{r} v-{} a{r} {r} 0 0 1 {r} -{r}\"/>\n",
+                            end,
+                            branch_y,
+                            inner - size.width,
+                            offset - 2 * ARC,
+                            r = ARC
+                        ));
+                    }
+                }
+            }
+            Diagram::Repeat(item) => {
+                let size = item.size();
+                let back = loop_offset(&size);
+                out.push_str(&format!("<path d=\"M{} {} h{}\"/>\n", x, y, ARC));
+                item.draw(x + ARC, y, links, out);
+                out.push_str(&format!(
+                    "<path d=\"M{} {} h{r} M{} {} a{r} {r} 0 0 1 {r} {r} v{} a{r} {r} 0 0 1 -{r} {r} h-{} a{r} {r} 0 0 1 -{r} -{r} v-{} a{r} {r} 0 0 1 {r} -{r}\"/>\n",
+                    x + ARC + size.width,
+                    y,
+                    x + ARC + size.width,
+                    y,
+                    back - 2 * ARC,
+                    size.width,
+                    back - 2 * ARC,
+                    r = ARC
+                ));
+            }
+        }
+    }
+}
+
+/// Vertical offset of every branch from the main line
+fn branch_offsets(branches: &[Diagram]) -> Vec<(i32, Size)> {
+    let mut offsets: Vec<(i32, Size)> = Vec::with_capacity(branches.len());
+    for branch in branches {
+        let size = branch.size();
+        let offset = match offsets.last() {
+            None => 0,
+            Some((previous, previous_size)) => {
+                previous + (2 * ARC).max(previous_size.down + GAP + size.up)
+            }
+        };
+        offsets.push((offset, size));
+    }
+    offsets
+}
+
+/// Vertical offset of the line going back
+fn loop_offset(size: &Size) -> i32 {
+    (2 * ARC).max(size.down + GAP)
+}
+
+fn boxed(
+    x: i32,
+    y: i32,
+    text: &str,
+    class: &str,
+    radius: i32,
+    link: Option<&str>,
+    out: &mut String,
+) {
+    let width = text.chars().count() as i32 * CHAR + 2 * SPACE;
+    if let Some(link) = link {
+        out.push_str(&format!("<a href=\"#{}\">\n", escape(link)));
+    }
+    out.push_str(&format!(
+        "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\"/>\n",
+        class,
+        x,
+        y - BOX,
+        width,
+        2 * BOX,
+        radius
+    ));
+    out.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\">{}</text>\n",
+        x + width / 2,
+        y + 5,
+        escape(text)
+    ));
+    if link.is_some() {
+        out.push_str("</a>\n");
+    }
+}
+
+fn draw(diagram: &Diagram, links: bool, out: &mut String) {
+    let size = diagram.size();
+    // the diagram starts and ends with a short vertical bar
+    let width = size.width + 2 * PADDING + 2 * SPACE;
+    let height = size.up + size.down + 2 * PADDING;
+    let y = PADDING + size.up;
+    out.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+        width, height, width, height
+    ));
+    out.push_str(&format!("<style>{}</style>\n", STYLE));
+    out.push_str(&format!(
+        "<path d=\"M{} {} v{} m0 -{} h{}\"/>\n",
+        PADDING,
+        y - BOX / 2,
+        BOX,
+        BOX / 2,
+        SPACE
+    ));
+    diagram.draw(PADDING + SPACE, y, links, out);
+    out.push_str(&format!(
+        "<path d=\"M{} {} h{} m0 -{} v{}\"/>\n",
+        PADDING + SPACE + size.width,
+        y,
+        SPACE,
+        BOX / 2,
+        BOX
+    ));
+    out.push_str("</svg>\n");
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Turns rules into diagrams
+struct Builder<'a> {
+    grammar: &'a Grammar,
+    /// Enumerators that are being expanded (guards against cycles)
+    expanding: Vec<&'a str>,
+}
+
+impl<'a> Builder<'a> {
+    fn new(grammar: &'a Grammar) -> Builder<'a> {
+        Builder {
+            grammar,
+            expanding: Vec::new(),
+        }
+    }
+
+    fn token(&mut self, token: &'a MatchToken) -> Diagram {
+        match token {
+            MatchToken::Token(TokenKinds::Token(text)) | MatchToken::Word(text) => {
+                Diagram::Token(text.clone())
+            }
+            MatchToken::Token(TokenKinds::Complex(name)) => Diagram::Special(name.to_uppercase()),
+            MatchToken::Token(TokenKinds::Text) => Diagram::Special("TEXT".to_string()),
+            MatchToken::Token(TokenKinds::Whitespace) => Diagram::Special("WHITESPACE".to_string()),
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eol)) => {
+                Diagram::Special("EOL".to_string())
+            }
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Eof)) => {
+                Diagram::Special("EOF".to_string())
+            }
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Indent)) => {
+                Diagram::Special("INDENT".to_string())
+            }
+            MatchToken::Token(TokenKinds::Control(ControlTokenKind::Dedent)) => {
+                Diagram::Special("DEDENT".to_string())
+            }
+            MatchToken::Any => Diagram::Special("ANY".to_string()),
+            MatchToken::Node(name) => Diagram::Node(name.clone()),
+            MatchToken::Template(name, arguments) => Diagram::Node(instance_name(name, arguments)),
+            MatchToken::Enumerator(name) => {
+                let enumerator = match self.grammar.enumerators.get(name) {
+                    Some(enumerator) if !self.expanding.contains(&name.as_str()) => enumerator,
+                    _ => return Diagram::Special(format!("@{}", name)),
+                };
+                self.expanding.push(name);
+                let values = enumerator
+                    .values
+                    .iter()
+                    .map(|value| self.token(value))
+                    .collect::<Vec<_>>();
+                self.expanding.pop();
+                choice(values)
+            }
+        }
+    }
+
+    /// Matched token, flow parameters and the rules that follow
+    fn matched(
+        &mut self,
+        token: &'a MatchToken,
+        parameters: &[Parameters],
+        rules: &'a Rules,
+    ) -> Vec<Diagram> {
+        let mut items = vec![self.token(token)];
+        for parameter in parameters {
+            match parameter {
+                Parameters::Back(steps) => items.push(Diagram::Comment(format!("back {}", steps))),
+                Parameters::Return => items.push(Diagram::Comment("return".to_string())),
+                Parameters::Break(n) => items.push(Diagram::Comment(format!("break {}", n))),
+                Parameters::Goto(label) => items.push(Diagram::Comment(format!("goto {}", label))),
+                _ => (),
+            }
+        }
+        items.extend(self.items(rules));
+        items
+    }
+
+    fn options(&mut self, options: &'a [OneOf]) -> Vec<Diagram> {
+        options
+            .iter()
+            .map(|option| sequence(self.matched(&option.token, &option.parameters, &option.rules)))
+            .collect()
+    }
+
+    fn rules(&mut self, rules: &'a Rules) -> Diagram {
+        let items = self.items(rules);
+        sequence(items)
+    }
+
+    fn items(&mut self, rules: &'a Rules) -> Vec<Diagram> {
+        let mut items = Vec::new();
+        for rule in rules {
+            match rule {
+                Rule::Is {
+                    token,
+                    rules,
+                    parameters,
+                } => items.extend(self.matched(token, parameters, rules)),
+                Rule::Isnt { token, rules, .. } => {
+                    let not = match self.token(token) {
+                        Diagram::Token(text) | Diagram::Node(text) | Diagram::Special(text) => {
+                            format!("not {}", text)
+                        }
+                        _ => "not".to_string(),
+                    };
+                    items.push(Diagram::Comment(not));
+                    items.extend(self.items(rules));
+                }
+                Rule::IsOneOf { tokens } => items.push(choice(self.options(tokens))),
+                Rule::Maybe {
+                    token,
+                    is,
+                    isnt,
+                    parameters,
+                } => {
+                    let matched = sequence(self.matched(token, parameters, is));
+                    if isnt.is_empty() {
+                        items.push(optional(matched));
+                    } else {
+                        let otherwise = self.rules(isnt);
+                        items.push(Diagram::Choice(vec![matched, otherwise]));
+                    }
+                }
+                Rule::MaybeOneOf { is_one_of, isnt } => {
+                    let mut options = self.options(is_one_of);
+                    if isnt.is_empty() {
+                        items.push(optional(choice(options)));
+                    } else {
+                        options.push(self.rules(isnt));
+                        items.push(Diagram::Choice(options));
+                    }
+                }
+                Rule::While {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    let matched = sequence(self.matched(token, parameters, rules));
+                    items.push(optional(Diagram::Repeat(Box::new(matched))));
+                }
+                Rule::Loop { rules } => {
+                    items.push(Diagram::Repeat(Box::new(self.rules(rules))));
+                }
+                Rule::Until {
+                    token,
+                    rules,
+                    parameters,
+                } => {
+                    items.push(Diagram::Comment("skip until".to_string()));
+                    items.extend(self.matched(token, parameters, rules));
+                }
+                Rule::UntilOneOf { tokens } => {
+                    items.push(Diagram::Comment("skip until".to_string()));
+                    items.push(choice(self.options(tokens)));
+                }
+                Rule::Command { command } => match command {
+                    Commands::Compare {
+                        left, right, rules, ..
+                    } => {
+                        let mut compared =
+                            vec![Diagram::Comment(format!("compare {} {}", left, right))];
+                        compared.extend(self.items(rules));
+                        items.push(optional(sequence(compared)));
+                    }
+                    Commands::Error { message } => {
+                        items.push(Diagram::Comment(format!("error: {}", message)))
+                    }
+                    Commands::Goto { label } => {
+                        items.push(Diagram::Comment(format!("goto {}", label)))
+                    }
+                    Commands::Label { name } => {
+                        items.push(Diagram::Comment(format!("label {}", name)))
+                    }
+                    Commands::HardError { .. } | Commands::Print { .. } => (),
+                },
+                Rule::Debug { .. } => (),
+            }
+        }
+        items
+    }
+}
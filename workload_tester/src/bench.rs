@@ -0,0 +1,174 @@
+//! Lexer benchmark
+//!
+//! Lexes generated source code with a realistic set of tokens and compares the lexer
+//! with the linear scan over every token kind it used before the tokens were compiled
+//! into a trie. Both have to produce the same tokens. The lazy lexers are timed as well.
+
+use std::time::Instant;
+
+use rparse::lexer::{ControlTokenKind, Lexer, TextLocation, Token, TokenKinds};
+
+/// Operators and keywords of a small language
+const TOKENS: [&str; 60] = [
+    "+", "-", "*", "/", "%", "=", "==", "!=", "<", ">", "<=", ">=", "&&", "||", "!", "&", "|",
+    "^", "<<", ">>", "+=", "-=", "*=", "/=", "%=", "(", ")", "{", "}", "[", "]", ";", ",", ".",
+    "..", ":", "::", "->", "=>", "?", "\"", "'", "//", "fun", "let", "mut", "if", "else",
+    "while", "loop", "for", "in", "return", "break", "continue", "struct", "enum", "impl",
+    "import", "const",
+];
+
+pub fn lexer(size: usize) {
+    let text = source(size);
+    let mut lexer = Lexer::new();
+    lexer.add_tokens(&TOKENS.map(String::from));
+    let mut token_kinds = TOKENS.map(String::from).to_vec();
+    token_kinds.sort_by_key(|token| token.len());
+
+    println!("lexing {} bytes with {} tokens", text.len(), TOKENS.len());
+
+    let time = Instant::now();
+    let expected = linear_scan(&token_kinds, &text);
+    let linear = time.elapsed();
+    println!("linear scan: {:?}", linear);
+
+    let time = Instant::now();
+    let tokens = lexer.lex_utf8(&text).unwrap();
+    let trie = time.elapsed();
+    println!("trie: {:?}", trie);
+
+    let time = Instant::now();
+    let ascii = lexer.lex_ascii(&text).unwrap();
+    println!("trie (ascii): {:?}", time.elapsed());
+
+    // the lazy lexers do not keep the tokens
+    let time = Instant::now();
+    let lazy = lexer.tokens(&text).map(Result::unwrap).count();
+    println!("lazy: {:?}", time.elapsed());
+
+    let time = Instant::now();
+    let read = lexer.read_tokens(text.as_bytes()).map(Result::unwrap).count();
+    println!("lazy (reader): {:?}", time.elapsed());
+
+    assert_eq!(tokens, expected);
+    assert_eq!(ascii.len(), tokens.len());
+    assert_eq!(lazy, tokens.len());
+    assert_eq!(read, tokens.len());
+    assert!(lexer.tokens(&text).map(Result::unwrap).eq(tokens.iter().cloned()));
+    println!(
+        "{} tokens, speedup: {:.1}x",
+        tokens.len(),
+        linear.as_secs_f64() / trie.as_secs_f64()
+    );
+}
+
+/// Generates lines of code until the text is at least `size` bytes long
+fn source(size: usize) -> String {
+    let mut text = String::with_capacity(size + 128);
+    let mut i = 0;
+    while text.len() < size {
+        text.push_str(&format!(
+            "fun item_{i}(value: int, list: [int]) -> int {{ let mut acc_{i} = value * {i} + list[{m}]; if acc_{i} >= {i} && !done {{ return acc_{i} << 2; }} else {{ acc_{i} += 1; }} }}\n",
+            i = i,
+            m = i % 7,
+        ));
+        i += 1;
+    }
+    // the old `lex_utf8` split a word at the end of the text ("a+hello" gave "hell" "o"),
+    // the trie lexer fixes that on purpose, the padding only keeps both outputs comparable
+    text.push_str(&" ".repeat(16));
+    text
+}
+
+/// The lexer before the trie, tries every token kind at every character
+fn linear_scan(token_kinds: &[String], text: &str) -> Vec<Token> {
+    let chars = text.char_indices().collect::<Vec<(usize, char)>>();
+    let len = chars.len();
+    let mut tokens = Vec::with_capacity(chars.len() / 4);
+    let mut i = 0;
+    let mut line = 0;
+    let mut column = 0;
+    'chars: while i < len {
+        if chars[i].1 == '\n' {
+            tokens.push(Token {
+                index: chars[i].0,
+                len: 1,
+                location: TextLocation::new(line, column),
+                kind: TokenKinds::Control(ControlTokenKind::Eol),
+            });
+            line += 1;
+            column = 0;
+            i += 1;
+            continue;
+        }
+
+        'tokens: for token_kind in token_kinds.iter().rev() {
+            let tok_len = token_kind.chars().count();
+            if i + tok_len > len {
+                continue;
+            }
+            for (j, c) in token_kind.chars().enumerate() {
+                if c != chars[i + j].1 {
+                    continue 'tokens;
+                }
+            }
+            tokens.push(Token {
+                index: chars[i].0,
+                len: token_kind.len(),
+                location: TextLocation::new(line, column),
+                kind: TokenKinds::Token(token_kind.clone()),
+            });
+            i += tok_len;
+            column += tok_len;
+            continue 'chars;
+        }
+
+        if chars[i].1.is_whitespace() {
+            tokens.push(Token {
+                index: chars[i].0,
+                len: 1,
+                location: TextLocation::new(line, column),
+                kind: TokenKinds::Whitespace,
+            });
+            i += 1;
+            column += 1;
+            continue;
+        }
+
+        let mut j = 0;
+        let mut token_len = 0;
+        'word: while i + j < len {
+            if chars[i + j].1.is_whitespace() {
+                break;
+            }
+            token_len += chars[i + j].1.len_utf8();
+            j += 1;
+            for token_kind in token_kinds {
+                let start = i + j;
+                let tok_len = token_kind.chars().count();
+                let end = if i + j + tok_len < len {
+                    i + j + tok_len
+                } else {
+                    break 'word;
+                };
+                if text[chars[start].0..chars[end].0] == *token_kind {
+                    break 'word;
+                }
+            }
+        }
+        tokens.push(Token {
+            index: chars[i].0,
+            len: token_len,
+            location: TextLocation::new(line, column),
+            kind: TokenKinds::Text,
+        });
+        column += j;
+        i += j;
+    }
+    tokens.push(Token {
+        index: i,
+        len: 0,
+        location: TextLocation::new(line, column),
+        kind: TokenKinds::Control(ControlTokenKind::Eof),
+    });
+    tokens
+}